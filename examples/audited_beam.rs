@@ -0,0 +1,204 @@
+//!
+//! End-to-end audited calculation: bending check of a simply supported beam.
+//!
+//! Reads inputs from a TOML file, records every step of the calculation with its validations
+//! in a session, and writes the expression tree as json, DOT and Markdown with the session manifest.
+//!
+//! ```sh
+//! cargo run --example audited_beam -- examples/beam.toml target/audited_beam
+//! ```
+//!
+//! Exits with status 1 if a check failed.
+//!
+extern crate cherries;
+extern crate uom;
+
+use cherries::export::Tree;
+use cherries::node::{Cherry, Leaf, Origin};
+use cherries::report::Renderer;
+use cherries::session::Session;
+use cherries::units::DisplayUnits;
+use cherries::validate::Validate;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::{env, error::Error, fs, process};
+use uom::si::f64::*;
+use uom::si::{area::square_centimeter, pressure::megapascal, volume::cubic_centimeter};
+use uom::si::{
+    energy::kilojoule,
+    force::kilonewton,
+    length::{meter, millimeter},
+};
+
+// Values of the TOML subset used by the inputs (`[table]` headers and `key = number` pairs),
+// keyed by `table.key`.
+fn read_inputs(toml: &str) -> Result<BTreeMap<String, f64>, String> {
+    let mut table = String::new();
+    let mut values = BTreeMap::new();
+    for (n, line) in toml.lines().enumerate() {
+        let line = line.splitn(2, '#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            table = line[1..line.len() - 1].trim().to_string();
+            continue;
+        }
+        let mut pair = line.splitn(2, '=');
+        let (key, value) = match (pair.next(), pair.next()) {
+            (Some(key), Some(value)) => (key.trim(), value.trim()),
+            _ => return Err(format!("line {}: expected `key = value`", n + 1)),
+        };
+        let value = value
+            .parse::<f64>()
+            .map_err(|_| format!("line {}: `{}` is not a number", n + 1, value))?;
+        values.insert(format!("{}.{}", table, key), value);
+    }
+    Ok(values)
+}
+
+fn input(inputs: &BTreeMap<String, f64>, key: &str) -> Result<f64, String> {
+    inputs
+        .get(key)
+        .cloned()
+        .ok_or_else(|| format!("missing input `{}`", key))
+}
+
+fn constant(value: f64) -> Cherry<f64> {
+    Leaf::new()
+        .value(value)
+        .name(value.to_string())
+        .build()
+        .with_origin(Origin::Standard)
+}
+
+fn run(toml: &Path, out: &Path) -> Result<bool, Box<dyn Error>> {
+    let inputs = read_inputs(&fs::read_to_string(toml)?)?;
+    let source = toml.display().to_string();
+    let mut session = Session::new();
+    session
+        .invariant("must be finite", |v: &f64| v.is_finite())
+        .invariant("stress must be finite", |sigma: &Pressure| {
+            sigma.is_finite()
+        })
+        .on_validation_failure(|failure| {
+            eprintln!("check failed: {}: {}", failure.label, failure.msg)
+        });
+
+    let utilization = session.run(|session| -> Result<Cherry<Ratio>, String> {
+        let span = Leaf::new()
+            .value(Length::new::<meter>(input(&inputs, "geometry.span")?))
+            .name("L")
+            .build()
+            .with_origin(Origin::Measured);
+        let width = Leaf::new()
+            .value(Length::new::<millimeter>(input(&inputs, "geometry.width")?))
+            .name("b")
+            .build()
+            .with_origin(Origin::Measured);
+        let depth = Leaf::new()
+            .value(Length::new::<millimeter>(input(&inputs, "geometry.depth")?))
+            .name("h")
+            .build()
+            .with_origin(Origin::Measured);
+        let load = Leaf::new()
+            .value(Force::new::<kilonewton>(input(
+                &inputs,
+                "loads.point_load",
+            )?))
+            .name("P")
+            .build()
+            .with_origin(Origin::Assumed)
+            .comment("characteristic load at midspan");
+        let fy = Leaf::new()
+            .value(Pressure::new::<megapascal>(input(
+                &inputs,
+                "material.yield_strength",
+            )?))
+            .name("f_y")
+            .build()
+            .with_origin(Origin::Standard);
+        let gamma = Leaf::new()
+            .value(input(&inputs, "material.gamma_m0")?)
+            .name("gamma_M0")
+            .build()
+            .with_origin(Origin::Standard)
+            .comment("partial factor for resistance of cross-sections");
+        for leaf in &[&span, &width, &depth] {
+            session.input_from(*leaf, source.as_str());
+        }
+        session
+            .input_from(&load, source.as_str())
+            .input_from(&fy, source.as_str())
+            .input_from(&gamma, source.as_str());
+
+        let moment = (load * span / constant(4.0)).labeled("M_Ed");
+        let modulus = (width * depth.clone() * depth / constant(6.0)).labeled("W_el");
+        let stress: Cherry<Pressure> = (moment / modulus).labeled("sigma_Ed");
+        let resistance = (fy / gamma).labeled("f_yd");
+        session.check(
+            &stress
+                .clone()
+                .validate("stress must be non-negative", |sigma| sigma.value >= 0.0)
+                .into_result(),
+        );
+        let utilization = (stress / resistance)
+            .labeled("utilization")
+            .comment("bending: sigma_Ed / f_yd");
+        session.check(
+            &utilization
+                .clone()
+                .validate("utilization must not exceed 1", |u| u.value <= 1.0)
+                .into_result(),
+        );
+        session.output(&utilization);
+        Ok(utilization)
+    })?;
+
+    for leaf in &["P", "h"] {
+        let sensitivity = utilization
+            .sensitivity(leaf, 0.01)
+            .map_err(|err| err.to_string())?;
+        println!("{} = {:.3}", sensitivity.name(), sensitivity.quantity());
+    }
+    println!("utilization = {:.3}", utilization.quantity().value);
+
+    let units = DisplayUnits::new()
+        .prefer::<Length, millimeter>()
+        .prefer::<Area, square_centimeter>()
+        .prefer::<Force, kilonewton>()
+        .prefer_as::<Energy, kilojoule, _>("kN·m")
+        .prefer::<Volume, cubic_centimeter>()
+        .prefer::<Pressure, megapascal>();
+    let json = units.to_json(&utilization);
+    let dot = Tree::try_from(&utilization)?.to_dot();
+    let markdown = Renderer::new().units(units).to_markdown(&utilization)?;
+
+    fs::create_dir_all(out)?;
+    fs::write(out.join("beam.json"), json)?;
+    fs::write(out.join("beam.dot"), dot)?;
+    fs::write(out.join("beam.md"), markdown)?;
+    fs::write(out.join("manifest.json"), session.manifest().to_json())?;
+    println!("artifacts written to {}", out.display());
+
+    Ok(session.manifest().checks.iter().all(|check| check.passed))
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let toml = args
+        .next()
+        .unwrap_or_else(|| String::from("examples/beam.toml"));
+    let out = args
+        .next()
+        .unwrap_or_else(|| String::from("target/audited_beam"));
+    match run(Path::new(&toml), Path::new(&out)) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    }
+}
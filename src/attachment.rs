@@ -0,0 +1,106 @@
+use super::store::digest;
+use alloc::{borrow::ToOwned, string::String, sync::Arc, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+///
+/// Where the content of an `Attachment` lives.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// File referenced by path, not read by the crate.
+    Path(String),
+    /// Content kept in memory, referenced by digest in exports.
+    Bytes(Arc<[u8]>),
+}
+
+impl<'a> From<&'a str> for Source {
+    fn from(path: &'a str) -> Self {
+        Source::Path(path.to_owned())
+    }
+}
+
+impl From<String> for Source {
+    fn from(path: String) -> Self {
+        Source::Path(path)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> From<&'a std::path::Path> for Source {
+    fn from(path: &'a std::path::Path) -> Self {
+        Source::Path(path.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::path::PathBuf> for Source {
+    fn from(path: std::path::PathBuf) -> Self {
+        Source::from(path.as_path())
+    }
+}
+
+impl<'a> From<&'a [u8]> for Source {
+    fn from(bytes: &'a [u8]) -> Self {
+        Source::Bytes(Arc::from(bytes))
+    }
+}
+
+impl From<Vec<u8>> for Source {
+    fn from(bytes: Vec<u8>) -> Self {
+        Source::Bytes(Arc::from(bytes))
+    }
+}
+
+///
+/// External evidence (a photo of a test setup, a CSV of raw data, ...) attached to a node.
+///
+/// Attached with `Cherry::attach`.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    pub source: Source,
+    pub mime: String,
+}
+
+impl Attachment {
+    ///
+    /// Returns reference to the attachment written in exports.
+    ///
+    pub fn reference(&self) -> Reference {
+        match &self.source {
+            Source::Path(path) => Reference::Path {
+                path: path.clone(),
+                mime: self.mime.clone(),
+            },
+            Source::Bytes(bytes) => Reference::Bytes {
+                digest: digest(bytes),
+                size: bytes.len(),
+                mime: self.mime.clone(),
+            },
+        }
+    }
+}
+
+///
+/// Attachment in the export schema.
+///
+/// Serialized as `{"path":..,"mime":..}` for files and
+/// `{"digest":..,"size":..,"mime":..}` for content kept in memory.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Reference {
+    Path { path: String, mime: String },
+    Bytes { digest: String, size: usize, mime: String },
+}
+
+// Returns `,"attachments":[..]` (empty if there are no attachments).
+pub(crate) fn json_attachments(attachments: &[Attachment]) -> String {
+    if attachments.is_empty() {
+        return String::new();
+    }
+    let references = attachments.iter().map(Attachment::reference).collect::<Vec<_>>();
+    let mut json = String::from(",\"attachments\":");
+    json.push_str(&serde_json::to_string(&references).unwrap_or_default());
+    json
+}
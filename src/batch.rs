@@ -0,0 +1,165 @@
+use super::node::Cherry;
+use super::export::Tree;
+use super::validate;
+use alloc::{string::String, vec, vec::Vec};
+use core::convert::TryFrom;
+use core::fmt::Debug;
+use serde::ser::{self, Serialize, SerializeStruct, Serializer};
+
+///
+/// Combined outcome of `evaluate_batch`.
+///
+/// Successes and failures keep the index of the item they were computed from.
+///
+#[derive(Debug)]
+pub struct Report<T: Clone + Debug> {
+    pub successes: Vec<(usize, Cherry<T>)>,
+    pub failures: Vec<(usize, validate::Error)>,
+}
+
+impl<T: Clone + Debug> Report<T> {
+    ///
+    /// Returns the number of evaluated items.
+    ///
+    pub fn len(&self) -> usize {
+        self.successes.len() + self.failures.len()
+    }
+    ///
+    /// Returns `true` if no item was evaluated.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    ///
+    /// Returns `true` if every item passed validation.
+    ///
+    pub fn is_all_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+    ///
+    /// Returns report as json string, as serialized by serde.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::batch::evaluate_batch;
+    /// use cherries::node::Leaf;
+    /// use cherries::validate::Validate;
+    ///
+    /// let report = evaluate_batch(vec![2, 3], |v| {
+    ///     Leaf::new()
+    ///         .value(v)
+    ///         .name("x")
+    ///         .build()
+    ///         .validate("must be even", |v| v % 2 == 0)
+    ///         .into_result()
+    /// });
+    /// assert_eq!(
+    ///     report.to_json(),
+    ///     "{\
+    ///         \"total\":2,\"passed\":1,\"failed\":1,\
+    ///         \"successes\":[\
+    ///             {\"index\":0,\"tree\":{\"label\":\"x\",\"value\":2.0,\"unit\":\"dimensionless\"}}\
+    ///         ],\
+    ///         \"failures\":[\
+    ///             {\"index\":1,\"label\":\"x\",\"msg\":[\"must be even\"],\
+    ///              \"tree\":{\"label\":\"x\",\"value\":3.0,\"unit\":\"dimensionless\"}}\
+    ///         ]\
+    ///     }".to_string()
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+// Success of a `Report` as serialized.
+#[derive(serde::Serialize)]
+struct Success {
+    index: usize,
+    tree: Tree,
+}
+
+// Failure of a `Report` as serialized: its index and the fields of the error.
+#[derive(serde::Serialize)]
+struct Failure<'a> {
+    index: usize,
+    #[serde(flatten)]
+    error: &'a validate::Error,
+}
+
+///
+/// Written as `total`, `passed` and `failed` counts, `successes` (`index` and `tree` of the node)
+/// and `failures` (`index` and the fields of `validate::Error`).
+///
+impl<T: Clone + Debug> Serialize for Report<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let successes = self
+            .successes
+            .iter()
+            .map(|(index, node)| {
+                Tree::try_from(node)
+                    .map(|tree| Success { index: *index, tree })
+                    .map_err(ser::Error::custom)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let failures = self
+            .failures
+            .iter()
+            .map(|(index, error)| Failure { index: *index, error })
+            .collect::<Vec<_>>();
+        let mut state = serializer.serialize_struct("Report", 5)?;
+        state.serialize_field("total", &self.len())?;
+        state.serialize_field("passed", &self.successes.len())?;
+        state.serialize_field("failed", &self.failures.len())?;
+        state.serialize_field("successes", &successes)?;
+        state.serialize_field("failures", &failures)?;
+        state.end()
+    }
+}
+
+///
+/// Runs calculation `f` for each of `items` and collects successes and failures.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::batch::evaluate_batch;
+/// use cherries::node::Leaf;
+/// use cherries::validate::Validate;
+///
+/// let members = vec![("B1", 0.7), ("B2", 1.1), ("B3", 0.9)];
+/// let report = evaluate_batch(members, |(name, utilization)| {
+///     Leaf::new()
+///         .value(utilization)
+///         .name(name)
+///         .build()
+///         .validate("utilization must be at most 1.0", |v| v <= &1.0)
+///         .into_result()
+/// });
+/// assert_eq!(report.len(), 3);
+/// assert!(!report.is_all_ok());
+/// assert_eq!(report.failures[0].0, 1);
+/// assert_eq!(report.failures[0].1.label, "B2".to_string());
+/// ```
+pub fn evaluate_batch<I, T, F>(items: I, mut f: F) -> Report<T>
+where
+    I: IntoIterator,
+    T: Clone + Debug,
+    F: FnMut(I::Item) -> validate::Result<T>,
+{
+    let mut report = Report {
+        successes: vec![],
+        failures: vec![],
+    };
+    for (index, item) in items.into_iter().enumerate() {
+        match f(item) {
+            Ok(node) => report.successes.push((index, node)),
+            Err(err) => report.failures.push((index, err)),
+        }
+    }
+    report
+}
@@ -0,0 +1,240 @@
+use super::node::Cherry;
+use super::validate::{Validate, ValidateChain};
+use alloc::{boxed::Box, format, string::String};
+use core::fmt::Debug;
+
+///
+/// Named check of quantities of type `T`, applied by `validate_check`
+/// (and by name in `validate!`).
+///
+/// Messages of failed checks are their names.
+///
+pub struct Check<T> {
+    name: String,
+    predicate: Box<dyn Fn(&T) -> bool>,
+}
+
+impl<T> Check<T> {
+    ///
+    /// Makes check `name` passing quantities `predicate` holds for.
+    ///
+    pub fn new<S, Predicate>(name: S, predicate: Predicate) -> Self
+    where
+        S: Into<String>,
+        Predicate: Fn(&T) -> bool + 'static,
+    {
+        Check {
+            name: name.into(),
+            predicate: Box::new(predicate),
+        }
+    }
+    ///
+    /// Returns name (message of failures).
+    ///
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    ///
+    /// Returns whether `quantity` passes the check.
+    ///
+    pub fn passes(&self, quantity: &T) -> bool {
+        (self.predicate)(quantity)
+    }
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    ///
+    /// Applies `check` as `Severity::Error` (see `validate`).
+    ///
+    pub fn validate_check(self, check: Check<T>) -> ValidateChain<T> {
+        let Check { name, predicate } = check;
+        self.validate(name, move |v| predicate(v))
+    }
+}
+
+impl<T: Clone + Debug> ValidateChain<T> {
+    ///
+    /// Applies `check` as `Severity::Error` (see `validate`).
+    ///
+    pub fn validate_check(self, check: Check<T>) -> ValidateChain<T> {
+        let Check { name, predicate } = check;
+        self.validate(name, move |v| predicate(v))
+    }
+}
+
+///
+/// Zero of numbers, used by `positive` and `non_zero`.
+///
+/// Implemented for primitive numbers and uom quantities.
+///
+pub trait Zero {
+    fn zero() -> Self;
+}
+
+///
+/// Whether numbers are finite (neither infinite nor NaN), used by `finite`.
+///
+/// Implemented for primitive numbers (integers are always finite) and uom quantities.
+///
+pub trait Finite {
+    fn is_finite(&self) -> bool;
+}
+
+macro_rules! impl_zero_finite {
+    ( $( $t:ty ),* ; $( $f:ty ),* ) => {
+        $(
+            impl Zero for $t {
+                fn zero() -> $t {
+                    0
+                }
+            }
+            impl Finite for $t {
+                fn is_finite(&self) -> bool {
+                    true
+                }
+            }
+        )*
+        $(
+            impl Zero for $f {
+                fn zero() -> $f {
+                    0.0
+                }
+            }
+            impl Finite for $f {
+                fn is_finite(&self) -> bool {
+                    <$f>::is_finite(*self)
+                }
+            }
+        )*
+    };
+}
+
+impl_zero_finite!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize; f32, f64);
+
+impl<D, U, V> Zero for uom::si::Quantity<D, U, V>
+where
+    D: uom::si::Dimension + ?Sized,
+    U: uom::si::Units<V> + ?Sized,
+    V: uom::num::Num + uom::Conversion<V>,
+{
+    fn zero() -> Self {
+        uom::si::Quantity {
+            dimension: core::marker::PhantomData,
+            units: core::marker::PhantomData,
+            value: V::zero(),
+        }
+    }
+}
+
+impl<D, U, V> Finite for uom::si::Quantity<D, U, V>
+where
+    D: uom::si::Dimension + ?Sized,
+    U: uom::si::Units<V> + ?Sized,
+    V: uom::num::Num + uom::Conversion<V> + Finite,
+{
+    fn is_finite(&self) -> bool {
+        self.value.is_finite()
+    }
+}
+
+///
+/// Check `must be positive` (greater than zero).
+///
+pub fn positive<T: PartialOrd + Zero + 'static>() -> Check<T> {
+    Check::new("must be positive", |v: &T| *v > T::zero())
+}
+
+///
+/// Check `must be less than {limit}`.
+///
+pub fn less_than<T: PartialOrd + Debug + 'static>(limit: T) -> Check<T> {
+    Check::new(format!("must be less than {:?}", limit), move |v: &T| {
+        *v < limit
+    })
+}
+
+///
+/// Check `must be non-zero`.
+///
+pub fn non_zero<T: PartialOrd + Zero + 'static>() -> Check<T> {
+    Check::new("must be non-zero", |v: &T| *v != T::zero())
+}
+
+///
+/// Check `must be in range [{lower}, {upper}]` (both inclusive).
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::checks::{finite, in_range, non_zero, positive};
+/// use cherries::node::Leaf;
+/// extern crate uom;
+/// use uom::si::{f64::*, pressure::megapascal};
+///
+/// let fy = Leaf::new().name("fy").value(Pressure::new::<megapascal>(690.0)).build();
+/// let lower = Pressure::new::<megapascal>(235.0);
+/// let upper = Pressure::new::<megapascal>(460.0);
+/// let err = fy
+///     .validate_check(positive())
+///     .validate_check(non_zero())
+///     .validate_check(in_range(lower, upper))
+///     .validate_check(finite())
+///     .into_result()
+///     .unwrap_err();
+/// assert_eq!(err.msg, vec![format!("must be in range [{:?}, {:?}]", lower, upper)]);
+///
+/// let n = Leaf::new().name("n").value(0).build();
+/// assert_eq!(n.validate_check(in_range(0, 4)).validate_check(non_zero()).errors().len(), 1);
+/// ```
+pub fn in_range<T: PartialOrd + Debug + 'static>(lower: T, upper: T) -> Check<T> {
+    Check::new(
+        format!("must be in range [{:?}, {:?}]", lower, upper),
+        move |v: &T| lower <= *v && *v <= upper,
+    )
+}
+
+///
+/// Check `must be finite` (neither infinite nor NaN).
+///
+pub fn finite<T: Finite + 'static>() -> Check<T> {
+    Check::new("must be finite", |v: &T| v.is_finite())
+}
+
+///
+/// Validates a node with the named checks of module `checks` (e.g. `positive`,
+/// `less_than(limit)` or `finite`), in order.
+///
+/// Expands to the `ValidateChain` of `validate_check` with each of them,
+/// so failures are aggregated by `into_result`.
+/// The node is a variable or an expression in parentheses.
+///
+/// # Examples
+/// ```
+/// #[macro_use]
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::meter};
+///
+/// # fn main() {
+/// let limit = Length::new::<meter>(0.024);
+/// let deflection = Leaf::new().name("delta").value(Length::new::<meter>(0.03)).build();
+/// let err = validate! { deflection: positive, less_than(limit), finite }
+///     .into_result()
+///     .unwrap_err();
+/// assert_eq!(err.msg, vec![format!("must be less than {:?}", limit)]);
+///
+/// let eta = validate! { (Leaf::new().name("eta").value(0.8).build()): positive, less_than(1.0) };
+/// assert!(eta.into_result().is_ok());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! validate {
+    (
+        $node:tt : $first:ident $( ( $( $first_arg:expr ),* ) )?
+        $( , $check:ident $( ( $( $arg:expr ),* ) )? )* $(,)?
+    ) => {
+        $crate::node::Cherry::validate_check($node, $crate::checks::$first( $( $( $first_arg ),* )? ))
+            $( .validate_check($crate::checks::$check( $( $( $arg ),* )? )) )*
+    };
+}
@@ -1,6 +1,8 @@
-use super::node::Cherry;
-use std::cmp::Ordering;
-use std::fmt::Debug;
+use super::node::{Cherries, Cherry, Node};
+use alloc::vec;
+use core::cmp::Ordering;
+use core::fmt::Debug;
+use core::ops::{Div, Sub};
 
 ///
 /// impl PartialOrd for Cherry<T>
@@ -37,3 +39,181 @@ where
         self.quantity().partial_cmp(other.quantity())
     }
 }
+
+impl<T> Cherry<T>
+where
+    T: 'static + Clone + Debug + PartialOrd,
+{
+    ///
+    /// Restricts quantity to `[lo, hi]` and returns node labeled `(clamp)`.
+    ///
+    /// `self`, `lo` and `hi` are all recorded as subexpressions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// extern crate uom;
+    /// use uom::si::{f32::*, length::meter};
+    ///
+    /// let lo = Leaf::new().value(Length::new::<meter>(0.5)).name("l_min").build();
+    /// let hi = Leaf::new().value(Length::new::<meter>(2.0)).name("l_max").build();
+    /// let l = Leaf::new().value(Length::new::<meter>(3.0)).name("l").build();
+    /// let res = l.clamp(&lo, &hi);
+    /// assert_eq!(res.quantity(), &Length::new::<meter>(2.0));
+    /// assert_eq!(res.name(), &"(clamp)".to_string());
+    /// assert_eq!(
+    ///     res.to_json(),
+    ///     "{\"label\":\"(clamp)\",\"value\":2,\"unit\":\"m^1\",\"dimensions\":{\"L\":1},\"op\":\"clamp\",\"subexpr\":[\
+    ///         {\"label\":\"l\",\"value\":3,\"unit\":\"m^1\",\"dimensions\":{\"L\":1}},\
+    ///         {\"label\":\"l_min\",\"value\":0.5,\"unit\":\"m^1\",\"dimensions\":{\"L\":1}},\
+    ///         {\"label\":\"l_max\",\"value\":2,\"unit\":\"m^1\",\"dimensions\":{\"L\":1}}\
+    ///     ]}".to_string()
+    /// );
+    /// ```
+    pub fn clamp(&self, lo: &Cherry<T>, hi: &Cherry<T>) -> Cherry<T> {
+        let value = if self.quantity() < lo.quantity() {
+            lo.quantity()
+        } else if self.quantity() > hi.quantity() {
+            hi.quantity()
+        } else {
+            self.quantity()
+        };
+        Node::new()
+            .name("(clamp)")
+            .op("clamp")
+            .value(value.clone())
+            .children(vec![self.as_subexpr(), lo.as_subexpr(), hi.as_subexpr()])
+            .build()
+    }
+}
+
+///
+/// Returns `then` if `condition` holds, otherwise `otherwise`.
+///
+/// Result is labeled `(select: then)` or `(select: otherwise)` according to the chosen branch,
+/// and records `condition` and both branches as subexpressions.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::cmp::select;
+///
+/// let span = Leaf::new().value(6.0).name("span").build();
+/// let is_long = span.map(|span| *span > 5.0).labeled("is_long");
+/// let res = select(
+///     is_long,
+///     Leaf::new().value(1.2).name("k_long").build(),
+///     Leaf::new().value(1.0).name("k_short").build(),
+/// );
+/// assert_eq!(res.quantity(), &1.2);
+/// assert_eq!(res.name(), &"(select: then)".to_string());
+/// assert_eq!(
+///     res.to_json(),
+///     "{\"label\":\"(select: then)\",\"value\":1.2,\"unit\":\"dimensionless\",\"op\":\"select\",\"subexpr\":[\
+///         {\"label\":\"is_long\",\"value\":true,\"unit\":\"dimensionless\",\"op\":\"map\",\"subexpr\":[\
+///             {\"label\":\"span\",\"value\":6,\"unit\":\"dimensionless\"}\
+///         ]},\
+///         {\"label\":\"k_long\",\"value\":1.2,\"unit\":\"dimensionless\"},\
+///         {\"label\":\"k_short\",\"value\":1,\"unit\":\"dimensionless\"}\
+///     ]}".to_string()
+/// );
+/// ```
+pub fn select<T: Clone + Debug>(
+    condition: Cherry<bool>,
+    then: Cherry<T>,
+    otherwise: Cherry<T>,
+) -> Cherry<T> {
+    let (name, value) = if *condition.quantity() {
+        ("(select: then)", then.quantity())
+    } else {
+        ("(select: otherwise)", otherwise.quantity())
+    };
+    Node::new()
+        .name(name)
+        .op("select")
+        .value(value.clone())
+        .children(vec![
+            condition.as_subexpr(),
+            then.as_subexpr(),
+            otherwise.as_subexpr(),
+        ])
+        .build()
+}
+
+// Returns `|lhs - rhs|` using only subtraction and comparison.
+fn abs_diff<T: Clone + PartialOrd + Sub<Output = T>>(lhs: &T, rhs: &T) -> T {
+    if lhs >= rhs {
+        lhs.clone() - rhs.clone()
+    } else {
+        rhs.clone() - lhs.clone()
+    }
+}
+
+///
+/// Returns absolute difference `|a - b|` (in units of `a` and `b`) labeled `(diff_abs)`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::cmp::diff_abs;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::millimeter};
+///
+/// let measured = Leaf::new().value(Length::new::<millimeter>(998.0)).name("measured").build();
+/// let nominal = Leaf::new().value(Length::new::<millimeter>(1000.0)).name("nominal").build();
+/// let res = diff_abs(&measured, &nominal);
+/// assert!((res.quantity().get::<millimeter>() - 2.0).abs() < 1e-9);
+/// assert_eq!(res.name(), &"(diff_abs)".to_string());
+/// assert_eq!(res.symbol(), "m^1".to_string());
+/// ```
+pub fn diff_abs<T>(a: &Cherry<T>, b: &Cherry<T>) -> Cherry<T>
+where
+    T: Clone + Debug + PartialOrd + Sub<Output = T>,
+{
+    Node::new()
+        .name("(diff_abs)")
+        .op("diff_abs")
+        .value(abs_diff(a.quantity(), b.quantity()))
+        .children(vec![a.as_subexpr(), b.as_subexpr()])
+        .build()
+}
+
+///
+/// Returns relative difference `|a - b| / |b|` (dimensionless) labeled `(diff_rel)`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::cmp::diff_rel;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::millimeter, ratio::percent};
+///
+/// let measured = Leaf::new().value(Length::new::<millimeter>(998.0)).name("measured").build();
+/// let nominal = Leaf::new().value(Length::new::<millimeter>(1000.0)).name("nominal").build();
+/// let res = diff_rel(&measured, &nominal);
+/// assert!((res.quantity().get::<percent>() - 0.2).abs() < 1e-9);
+/// assert_eq!(res.name(), &"(diff_rel)".to_string());
+/// assert_eq!(res.symbol(), "dimensionless".to_string());
+/// ```
+pub fn diff_rel<T>(a: &Cherry<T>, b: &Cherry<T>) -> Cherry<<T as Div>::Output>
+where
+    T: Clone + Debug + PartialOrd + Sub<Output = T> + Div,
+    <T as Div>::Output: Clone + Debug,
+{
+    let (a_value, b_value) = (a.quantity(), b.quantity());
+    let zero = b_value.clone() - b_value.clone();
+    Node::new()
+        .name("(diff_rel)")
+        .op("diff_rel")
+        .value(abs_diff(a_value, b_value) / abs_diff(b_value, &zero))
+        .children(vec![a.as_subexpr(), b.as_subexpr()])
+        .build()
+}
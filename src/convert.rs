@@ -0,0 +1,341 @@
+use super::node::{Cherries, Cherry, Leaf, Node};
+use alloc::{format, string::String, vec};
+use core::fmt::{self, Debug, Display};
+use core::marker::PhantomData;
+use core::ops::{Add, Mul, Sub};
+
+///
+/// Numeric backings of quantities (`f32` and `f64`, also as storage of uom quantities)
+/// converted by `Cherry::to_f64` and `Cherry::to_f32_lossy`.
+///
+pub trait Backing: Clone + Debug {
+    /// Same quantity backed by `f64`.
+    type F64: 'static + Clone + Debug;
+    /// Same quantity backed by `f32`.
+    type F32: 'static + Clone + Debug;
+    /// Converts to `f64` (always exact).
+    fn to_f64(&self) -> Self::F64;
+    /// Converts to `f32` saturating at `f32::MAX`, and returns whether precision was lost.
+    fn to_f32_lossy(&self) -> (Self::F32, bool);
+}
+
+impl Backing for f32 {
+    type F64 = f64;
+    type F32 = f32;
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+    fn to_f32_lossy(&self) -> (f32, bool) {
+        (*self, false)
+    }
+}
+
+impl Backing for f64 {
+    type F64 = f64;
+    type F32 = f32;
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+    // exact comparison tells whether the value survived the round trip
+    #[allow(clippy::float_cmp)]
+    fn to_f32_lossy(&self) -> (f32, bool) {
+        let value = if self.is_finite() {
+            self.max(f64::from(core::f32::MIN)).min(f64::from(core::f32::MAX)) as f32
+        } else {
+            *self as f32
+        };
+        // NaN is not a loss of precision
+        (value, f64::from(value) != *self && !self.is_nan())
+    }
+}
+
+macro_rules! impl_backing {
+    ( $v:ident; $( $q:ident :: $Q:ident ),* ) => {
+        $(
+            impl Backing for uom::si::$v::$Q {
+                type F64 = uom::si::f64::$Q;
+                type F32 = uom::si::f32::$Q;
+                fn to_f64(&self) -> Self::F64 {
+                    uom::si::f64::$Q {
+                        dimension: PhantomData,
+                        units: PhantomData,
+                        value: self.value.to_f64(),
+                    }
+                }
+                fn to_f32_lossy(&self) -> (Self::F32, bool) {
+                    let (value, lost) = self.value.to_f32_lossy();
+                    let quantity = uom::si::f32::$Q {
+                        dimension: PhantomData,
+                        units: PhantomData,
+                        value,
+                    };
+                    (quantity, lost)
+                }
+            }
+        )*
+    };
+}
+
+for_each_quantity!(impl_backing);
+
+///
+/// Decimal number `digits × 10^-scale` held exactly.
+///
+/// Made from floats by `Cherry::to_decimal`.
+/// Sums, differences and products are exact and saturate at the bounds of the 128-bit digits.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::convert::Decimal;
+///
+/// let price = Decimal::new(1999, 2);
+/// assert_eq!(price.to_string(), "19.99");
+/// assert_eq!((price * Decimal::new(3, 0)).to_string(), "59.97");
+/// assert_eq!(Decimal::new(1, 1) + Decimal::new(2, 1), Decimal::new(3, 1));
+/// assert_eq!(Decimal::new(-5, 2).to_string(), "-0.05");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    digits: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    ///
+    /// Makes decimal `digits × 10^-scale`.
+    ///
+    pub fn new(digits: i128, scale: u32) -> Self {
+        let (mut digits, mut scale) = (digits, scale);
+        // trailing zeros are dropped, so equal numbers compare equal
+        while scale > 0 && digits % 10 == 0 {
+            digits /= 10;
+            scale -= 1;
+        }
+        Decimal { digits, scale }
+    }
+    ///
+    /// Returns digits (the number times `10^scale`).
+    ///
+    pub fn digits(&self) -> i128 {
+        self.digits
+    }
+    ///
+    /// Returns number of digits after the decimal point.
+    ///
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+    ///
+    /// Returns nearest `f64`.
+    ///
+    pub fn to_f64(&self) -> f64 {
+        format!("{}e-{}", self.digits, self.scale)
+            .parse()
+            .unwrap_or(core::f64::NAN)
+    }
+    // Digits of `self` at `scale` (not less than `self.scale`), saturated.
+    fn rescaled(&self, scale: u32) -> i128 {
+        let factor = 10_i128.checked_pow(scale - self.scale);
+        match factor.and_then(|factor| self.digits.checked_mul(factor)) {
+            Some(digits) => digits,
+            None => saturated(self.digits.signum()),
+        }
+    }
+    // Parses float written like `1.25e-3`, returns whether the number was saturated.
+    fn from_float(formats: &str) -> (Decimal, bool) {
+        let mut parts = formats.splitn(2, 'e');
+        let (mantissa, exponent) = (parts.next().unwrap_or(""), parts.next().unwrap_or("0"));
+        let exponent = exponent.parse::<i64>().unwrap_or(0);
+        let fraction = mantissa.find('.').map_or(0, |point| mantissa.len() - point - 1);
+        let digits = mantissa.replace('.', "").parse::<i128>().unwrap_or(0);
+        let scale = fraction as i64 - exponent;
+        if scale >= 0 {
+            (Decimal::new(digits, scale as u32), false)
+        } else {
+            let factor = 10_i128.checked_pow((-scale) as u32);
+            match factor.and_then(|factor| digits.checked_mul(factor)) {
+                Some(digits) => (Decimal::new(digits, 0), false),
+                None => (Decimal::new(saturated(digits.signum()), 0), true),
+            }
+        }
+    }
+}
+
+fn saturated(sign: i128) -> i128 {
+    if sign < 0 {
+        core::i128::MIN
+    } else {
+        core::i128::MAX
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formats = format!("{}", self.digits);
+        let (sign, digits) = match formats.find('-') {
+            Some(_) => ("-", &formats[1..]),
+            None => ("", &formats[..]),
+        };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            write!(f, "{}{}", sign, digits)
+        } else if digits.len() > scale {
+            let (int, frac) = digits.split_at(digits.len() - scale);
+            write!(f, "{}{}.{}", sign, int, frac)
+        } else {
+            let zeros = String::from("0").repeat(scale - digits.len());
+            write!(f, "{}0.{}{}", sign, zeros, digits)
+        }
+    }
+}
+
+// Written as plain number, so json records it as a number.
+impl Debug for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+    fn add(self, other: Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        Decimal::new(self.rescaled(scale).saturating_add(other.rescaled(scale)), scale)
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+    fn sub(self, other: Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        Decimal::new(self.rescaled(scale).saturating_sub(other.rescaled(scale)), scale)
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+    // scales of factors add up
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, other: Decimal) -> Decimal {
+        Decimal::new(self.digits.saturating_mul(other.digits), self.scale + other.scale)
+    }
+}
+
+///
+/// Floats converted to `Decimal` by `Cherry::to_decimal`.
+///
+pub trait ToDecimal: Clone + Debug {
+    /// Returns the shortest decimal which reads back as the same float,
+    /// and whether it was saturated (or is zero for `NaN`).
+    fn to_decimal(&self) -> (Decimal, bool);
+}
+
+impl ToDecimal for f32 {
+    fn to_decimal(&self) -> (Decimal, bool) {
+        f64_to_decimal(format!("{:e}", self), self.is_finite())
+    }
+}
+
+impl ToDecimal for f64 {
+    fn to_decimal(&self) -> (Decimal, bool) {
+        f64_to_decimal(format!("{:e}", self), self.is_finite())
+    }
+}
+
+fn f64_to_decimal(formats: String, finite: bool) -> (Decimal, bool) {
+    if !finite {
+        return match formats.as_str() {
+            "inf" => (Decimal::new(core::i128::MAX, 0), true),
+            "-inf" => (Decimal::new(core::i128::MIN, 0), true),
+            _ => (Decimal::new(0, 0), true),
+        };
+    }
+    Decimal::from_float(&formats)
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    // Node `(op)` of `value` converted from `self` by `op`, flagged if precision was lost.
+    fn converted<U: Clone + Debug>(&self, op: &str, value: U, lost: bool) -> Cherry<U> {
+        let mut children = vec![self.as_subexpr()];
+        if lost {
+            children.push(Leaf::new().value(true).name("precision loss").build().as_subexpr());
+        }
+        Node::new()
+            .name(format!("({})", op))
+            .op(op)
+            .value(value)
+            .children(children)
+            .build()
+    }
+}
+
+impl<T: Backing> Cherry<T> {
+    ///
+    /// Returns same quantity backed by `f64`, labeled `(to_f64)`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// extern crate uom;
+    /// use uom::si::{f32, f64, length::meter};
+    ///
+    /// let l = Leaf::new().value(f32::Length::new::<meter>(1.5)).name("l").build();
+    /// let wide = l.to_f64();
+    /// assert_eq!(wide.quantity(), &f64::Length::new::<meter>(1.5));
+    /// assert_eq!(wide.name(), "(to_f64)");
+    /// ```
+    pub fn to_f64(&self) -> Cherry<T::F64> {
+        self.converted("to_f64", Backing::to_f64(self.quantity()), false)
+    }
+    ///
+    /// Returns same quantity backed by `f32`, labeled `(to_f32)`.
+    ///
+    /// Values beyond the range of `f32` saturate at `f32::MAX` (or `f32::MIN`).
+    /// If precision was lost the node also records leaf `precision loss`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    ///
+    /// let exact = Leaf::new().value(0.5_f64).name("x").build().to_f32_lossy();
+    /// assert_eq!(exact.quantity(), &0.5_f32);
+    /// assert!(!exact.to_json().contains("precision loss"));
+    ///
+    /// let huge = Leaf::new().value(1e300_f64).name("y").build().to_f32_lossy();
+    /// assert_eq!(huge.quantity(), &std::f32::MAX);
+    /// assert!(huge.to_json().contains("{\"label\":\"precision loss\",\"value\":true"));
+    /// assert!(Leaf::new().value(0.1_f64).name("z").build().to_f32_lossy().to_json().contains("precision loss"));
+    /// ```
+    pub fn to_f32_lossy(&self) -> Cherry<T::F32> {
+        let (value, lost) = Backing::to_f32_lossy(self.quantity());
+        self.converted("to_f32", value, lost)
+    }
+}
+
+impl<T: ToDecimal> Cherry<T> {
+    ///
+    /// Returns the value as `Decimal`, labeled `(to_decimal)`.
+    ///
+    /// The decimal is the shortest one which reads back as the same float
+    /// (e.g. `0.1` for `0.1_f32`). Values beyond 128-bit digits saturate,
+    /// and `NaN` becomes zero; both also record leaf `precision loss`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::convert::Decimal;
+    ///
+    /// let rate = Leaf::new().value(0.1_f32).name("rate").build().to_decimal();
+    /// assert_eq!(rate.quantity(), &Decimal::new(1, 1));
+    /// assert_eq!(Leaf::new().value(1.5e3).name("x").build().to_decimal().quantity().to_string(), "1500");
+    /// assert!(Leaf::new().value(std::f64::NAN).name("y").build().to_decimal().to_json().contains("precision loss"));
+    /// ```
+    pub fn to_decimal(&self) -> Cherry<Decimal> {
+        let (value, lost) = self.quantity().to_decimal();
+        self.converted("to_decimal", value, lost)
+    }
+}
@@ -0,0 +1,158 @@
+use super::export::Value;
+use super::node::{Cherry, Leaf};
+use super::rebind::{expr_of, Expr, ReevaluateError};
+use alloc::{format, vec, vec::Vec};
+use core::fmt::Debug;
+
+impl<T: Clone + Debug> Cherry<T> {
+    ///
+    /// Returns tree of the partial derivative of the node with respect to leaf `wrt`
+    /// (in base units), built by the chain rule from the recorded tree.
+    ///
+    /// Every node of the result is labeled `d({label})/d({wrt})`, and values of recorded nodes
+    /// it depends on are leaves with their labels.
+    /// Nodes not depending on `wrt` are left out, so the derivative of a tree without `wrt`
+    /// is leaf `0`.
+    /// Derivatives are known for nodes made by `+`, `-`, `*`, `/`, `mean!`, `weighted_sum!`,
+    /// `clamp` and `override_with` (also after being relabeled);
+    /// other nodes depending on `wrt` are reported as `Unsupported`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    ///
+    /// let w = Leaf::new().value(2.0).name("w").build();
+    /// let h = Leaf::new().value(3.0).name("h").build();
+    /// let t = Leaf::new().value(0.25).name("t").build();
+    /// let volume = ((w * h).labeled("area") * t).labeled("volume");
+    ///
+    /// let dh = volume.differentiate("h").unwrap();
+    /// assert_eq!(dh.name(), "d(volume)/d(h)");
+    /// assert_eq!(dh.quantity(), &0.5);
+    /// assert_eq!(
+    ///     dh.to_json(),
+    ///     "{\"label\":\"d(volume)/d(h)\",\"value\":0.5,\"unit\":\"dimensionless\",\"op\":\"mul\",\"subexpr\":[\
+    ///         {\"label\":\"d(area)/d(h)\",\"value\":2,\"unit\":\"dimensionless\",\"op\":\"mul\",\"subexpr\":[\
+    ///             {\"label\":\"w\",\"value\":2,\"unit\":\"dimensionless\"},\
+    ///             {\"label\":\"d(h)/d(h)\",\"value\":1,\"unit\":\"dimensionless\"}\
+    ///         ]},\
+    ///         {\"label\":\"t\",\"value\":0.25,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// assert_eq!(volume.differentiate("t").unwrap().quantity(), &6.0);
+    /// assert_eq!(volume.differentiate("rho").unwrap().quantity(), &0.0);
+    /// ```
+    pub fn differentiate(&self, wrt: &str) -> Result<Cherry<f32>, ReevaluateError> {
+        let expr = expr_of(self)?;
+        Ok(derive(&expr, wrt)?.unwrap_or_else(zero))
+    }
+}
+
+fn zero() -> Cherry<f32> {
+    Leaf::new().value(0.0).name("0").build()
+}
+
+// Sum of the terms, or `None` if there are none.
+fn sum(terms: Vec<Option<Cherry<f32>>>) -> Option<Cherry<f32>> {
+    terms.into_iter().flatten().fold(None, |acc, term| match acc {
+        Some(acc) => Some(acc + term),
+        None => Some(term),
+    })
+}
+
+// Derivative of `expr`, or `None` if it does not depend on `wrt`.
+fn derive(expr: &Expr, wrt: &str) -> Result<Option<Cherry<f32>>, ReevaluateError> {
+    let label = format!("d({})/d({})", expr.tree.label, wrt);
+    if expr.subexpr.is_empty() {
+        return Ok(if expr.tree.label == wrt {
+            Some(Leaf::new().value(1.0).name(label).build())
+        } else {
+            None
+        });
+    }
+    let mut subs = expr
+        .subexpr
+        .iter()
+        .map(|sub| derive(sub, wrt))
+        .collect::<Result<Vec<_>, _>>()?;
+    if subs.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+    let unsupported = || ReevaluateError::Unsupported {
+        label: expr.tree.label.clone(),
+        op: expr.op.clone(),
+    };
+    let values = expr
+        .subexpr
+        .iter()
+        .map(|sub| match sub.tree.value {
+            Value::Number(value) => Some(value),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(unsupported)?;
+    // Value of subexpression `i` as a leaf.
+    let value = |i: usize| {
+        Leaf::new()
+            .value(values[i])
+            .name(expr.subexpr[i].tree.label.clone())
+            .build()
+    };
+    let derivative = match (expr.op.as_str(), subs.len()) {
+        ("add", 2) => sum(subs),
+        ("sub", 2) => match (subs[0].take(), subs[1].take()) {
+            (lhs, Some(rhs)) => Some(lhs.unwrap_or_else(zero) - rhs),
+            (lhs, None) => lhs,
+        },
+        ("mul", 2) => sum(vec![
+            subs[0].take().map(|lhs| lhs * value(1)),
+            subs[1].take().map(|rhs| value(0) * rhs),
+        ]),
+        ("div", 2) => match (subs[0].take(), subs[1].take()) {
+            (Some(lhs), None) => Some(lhs / value(1)),
+            (lhs, Some(rhs)) => {
+                let numerator = match lhs {
+                    Some(lhs) => lhs * value(1) - value(0) * rhs,
+                    None => zero() - value(0) * rhs,
+                };
+                Some(numerator / (value(1) * value(1)))
+            }
+            (None, None) => None,
+        },
+        ("mean", len) => sum(subs).map(|total| {
+            total / Leaf::new().value(len as f32).name("n").build()
+        }),
+        ("weighted_sum", len) if len % 2 == 0 => {
+            let terms = (0..len)
+                .map(|i| {
+                    let other = i ^ 1;
+                    // pairs of weight and value
+                    subs[i].take().map(|d| {
+                        let (lhs, rhs) = if i % 2 == 0 {
+                            (d, value(other))
+                        } else {
+                            (value(other), d)
+                        };
+                        lhs * rhs
+                    })
+                })
+                .collect();
+            sum(terms)
+        }
+        ("clamp", 3) => {
+            let bound = if values[0] < values[1] {
+                1
+            } else if values[0] > values[2] {
+                2
+            } else {
+                0
+            };
+            subs[bound].take()
+        }
+        ("override", 2) => subs[1].take(),
+        ("to_f64", 1) | ("to_f32", 1) | ("to_decimal", 1) => subs[0].take(),
+        _ => return Err(unsupported()),
+    };
+    Ok(derivative.map(|derivative| derivative.labeled(label)))
+}
@@ -0,0 +1,277 @@
+use super::export::{Tree, Value};
+use super::node::{join, json_string, Cherries, Cherry};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Debug;
+use serde::{Deserialize, Serialize};
+
+///
+/// How a node differs between two trees.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Only in the new tree.
+    Added,
+    /// Only in the old tree.
+    Removed,
+    /// In both trees with another value or unit.
+    Changed,
+}
+
+impl Kind {
+    ///
+    /// Returns name written in json.
+    ///
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Added => "added",
+            Kind::Removed => "removed",
+            Kind::Changed => "changed",
+        }
+    }
+}
+
+///
+/// Value of a node with its unit.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    pub value: Value,
+    pub unit: String,
+}
+
+impl Reading {
+    fn of(tree: &Tree) -> Reading {
+        Reading {
+            value: tree.value.clone(),
+            unit: tree.unit.clone(),
+        }
+    }
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"value\":{},\"unit\":{}}}",
+            self.value.to_json(),
+            json_string(&self.unit)
+        )
+    }
+}
+
+///
+/// Node added, removed or changed between two trees.
+///
+/// `path` is the JSON Pointer of the node from the root (e.g. `/subexpr/0`)
+/// in the new tree, or in the old tree for removed nodes.
+/// Added and removed nodes stand for their whole subtrees.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub kind: Kind,
+    pub path: String,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old: Option<Reading>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new: Option<Reading>,
+}
+
+impl Change {
+    fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"kind\":\"{}\",\"path\":{},\"label\":{}",
+            self.kind.as_str(),
+            json_string(&self.path),
+            json_string(&self.label)
+        );
+        if let Some(old) = &self.old {
+            json.push_str(&format!(",\"old\":{}", old.to_json()));
+        }
+        if let Some(new) = &self.new {
+            json.push_str(&format!(",\"new\":{}", new.to_json()));
+        }
+        json.push('}');
+        json
+    }
+}
+
+///
+/// Changes between two expression trees (e.g. results of the same calculation
+/// in two versions of a program), in pre-order of the new tree.
+///
+/// Subexpressions are matched by label (the n-th of siblings with the same label with the n-th),
+/// so inserted or removed steps do not shift the others.
+/// Nodes are compared by value and unit only.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::diff::{Kind, Reading};
+/// use cherries::export::Value;
+///
+/// let a = Leaf::new().value(2.0).name("a").build();
+/// let b = Leaf::new().value(3.0).name("b").build();
+/// let old = (a.clone() * b.clone()).labeled("area");
+/// let k = Leaf::new().value(1.1).name("k").build();
+/// let new = (a * Leaf::new().value(3.5).name("b").build() * k).labeled("area");
+///
+/// let diff = old.diff(&new).unwrap();
+/// let changes = diff
+///     .changes
+///     .iter()
+///     .map(|change| (change.kind, change.path.as_str(), change.label.as_str()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(
+///     changes,
+///     vec![
+///         (Kind::Changed, "", "area"),
+///         (Kind::Added, "/subexpr/0", "(mul)"),
+///         (Kind::Added, "/subexpr/1", "k"),
+///         (Kind::Removed, "/subexpr/0", "a"),
+///         (Kind::Removed, "/subexpr/1", "b"),
+///     ]
+/// );
+/// assert_eq!(
+///     diff.changes[0].new,
+///     Some(Reading { value: Value::Number(7.7), unit: "dimensionless".to_string() })
+/// );
+/// assert!(diff.to_json().starts_with(
+///     "{\"changes\":[{\"kind\":\"changed\",\"path\":\"\",\"label\":\"area\",\
+///     \"old\":{\"value\":6,\"unit\":\"dimensionless\"},\"new\":{\"value\":7.7,\"unit\":\"dimensionless\"}},"
+/// ));
+/// assert!(old.diff(&old).unwrap().is_empty());
+/// ```
+#[must_use]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    ///
+    /// Returns changes from tree `old` to tree `new`.
+    ///
+    pub fn between(old: &Tree, new: &Tree) -> Diff {
+        let mut diff = Diff::default();
+        diff.compare(old, new, String::new(), String::new());
+        diff
+    }
+    ///
+    /// Returns whether the trees are the same (in values and units).
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+    ///
+    /// Returns change report as json `{"changes":[..]}`.
+    ///
+    pub fn to_json(&self) -> String {
+        let changes = self.changes.iter().map(Change::to_json).collect::<Vec<_>>();
+        format!("{{\"changes\":[{}]}}", join(&changes, ","))
+    }
+    fn compare(&mut self, old: &Tree, new: &Tree, old_path: String, new_path: String) {
+        if old.value != new.value || old.unit != new.unit {
+            self.changes.push(Change {
+                kind: Kind::Changed,
+                path: new_path.clone(),
+                label: new.label.clone(),
+                old: Some(Reading::of(old)),
+                new: Some(Reading::of(new)),
+            });
+        }
+        let mut matched = vec![false; old.subexpr.len()];
+        for (i, sub) in new.subexpr.iter().enumerate() {
+            let path = format!("{}/subexpr/{}", new_path, i);
+            match counterpart(&old.subexpr, &new.subexpr, i) {
+                Some(j) => {
+                    matched[j] = true;
+                    self.compare(
+                        &old.subexpr[j],
+                        sub,
+                        format!("{}/subexpr/{}", old_path, j),
+                        path,
+                    );
+                }
+                None => self.changes.push(Change {
+                    kind: Kind::Added,
+                    path,
+                    label: sub.label.clone(),
+                    old: None,
+                    new: Some(Reading::of(sub)),
+                }),
+            }
+        }
+        for (j, sub) in old.subexpr.iter().enumerate().filter(|&(j, _)| !matched[j]) {
+            self.changes.push(Change {
+                kind: Kind::Removed,
+                path: format!("{}/subexpr/{}", old_path, j),
+                label: sub.label.clone(),
+                old: Some(Reading::of(sub)),
+                new: None,
+            });
+        }
+    }
+}
+
+// Index in `old` of the sibling labeled as `new[i]` with the same number of such siblings before it.
+fn counterpart(old: &[Tree], new: &[Tree], i: usize) -> Option<usize> {
+    let label = &new[i].label;
+    let nth = new[..i].iter().filter(|sub| &sub.label == label).count();
+    old.iter()
+        .enumerate()
+        .filter(|(_, sub)| &sub.label == label)
+        .nth(nth)
+        .map(|(j, _)| j)
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    ///
+    /// Returns changes from this tree to the tree of `other` (see `Diff`).
+    ///
+    pub fn diff(&self, other: &dyn Cherries) -> serde_json::Result<Diff> {
+        let old = Tree::from_json(&self.to_json())?;
+        let new = Tree::from_json(&other.to_json())?;
+        Ok(Diff::between(&old, &new))
+    }
+    ///
+    /// Returns whether this tree and the tree of `other` have the same labels, operations
+    /// and subexpressions in the same order, whatever their values and units
+    /// (e.g. for golden tests of the structure of calculations).
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    ///
+    /// let area = |b: f64, h: f64| {
+    ///     let b = Leaf::new().value(b).name("b").build();
+    ///     let h = Leaf::new().value(h).name("h").build();
+    ///     (b * h).labeled("A")
+    /// };
+    /// assert!(area(0.3, 0.5).same_structure(&area(0.1 + 0.2, 0.5)).unwrap());
+    /// let b = Leaf::new().value(0.3).name("b").build();
+    /// let h = Leaf::new().value(0.5).name("h").build();
+    /// assert!(!area(0.3, 0.5).same_structure(&(b.clone() + h.clone()).labeled("A")).unwrap());
+    /// assert!(!area(0.3, 0.5).same_structure(&(h * b).labeled("A")).unwrap());
+    /// ```
+    pub fn same_structure(&self, other: &dyn Cherries) -> serde_json::Result<bool> {
+        let tree = Tree::from_json(&self.to_json())?;
+        Ok(tree.same_structure(&Tree::from_json(&other.to_json())?))
+    }
+}
+
+impl Tree {
+    ///
+    /// Returns whether `self` and `other` have the same labels, operations and subexpressions
+    /// in the same order, whatever their values, units and metadata.
+    ///
+    pub fn same_structure(&self, other: &Tree) -> bool {
+        self.label == other.label
+            && self.op == other.op
+            && self.subexpr.len() == other.subexpr.len()
+            && self
+                .subexpr
+                .iter()
+                .zip(&other.subexpr)
+                .all(|(a, b)| a.same_structure(b))
+    }
+}
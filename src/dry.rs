@@ -0,0 +1,147 @@
+use super::node::{Cherry, Dimensional, Dimensions, Label, Leaf};
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+use core::ops::{Add, Div, Mul, Sub};
+
+///
+/// Placeholder for a quantity of type `T`, for building the structure of a calculation
+/// without computing (or having) any value.
+///
+/// Operators on shapes only check that the operation is defined for `T` and track dimensions,
+/// so trees of `Cherry<Shape<T>>` have the labels, units and dimensions of the calculation
+/// and every value written as `NaN`.
+/// Made into leaves with `dry::leaf`.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Cherries;
+/// use cherries::dry::{self, Shape};
+/// use cherries::export::{Tree, Value};
+/// use std::convert::TryFrom;
+/// extern crate uom;
+/// use uom::si::f32::*;
+///
+/// let load = dry::leaf::<Force>("P");
+/// let area = (dry::leaf::<Length>("b") * dry::leaf::<Length>("d")).labeled("A");
+/// let stress: cherries::Cherry<Shape<Pressure>> = (load / area).labeled("sigma");
+///
+/// let tree = Tree::try_from(&stress).unwrap();
+/// assert_eq!(tree.unit, "m^-1 kg^1 s^-2".to_string());
+/// assert_eq!(tree.value, Value::Text("NaN".to_string()));
+/// assert_eq!(tree.subexpr[1].label, "A".to_string());
+/// assert_eq!(tree.subexpr[1].unit, "m^2".to_string());
+/// assert!(tree.to_dot().contains("\"sigma\\nNaN m^-1 kg^1 s^-2\""));
+/// ```
+pub struct Shape<T> {
+    dimensions: Dimensions,
+    quantity: PhantomData<fn() -> T>,
+}
+
+impl<T> Shape<T> {
+    fn with(dimensions: Dimensions) -> Self {
+        Shape {
+            dimensions,
+            quantity: PhantomData,
+        }
+    }
+    ///
+    /// Returns dimensions of the quantity.
+    ///
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+}
+
+impl<T: Dimensional> Shape<T> {
+    ///
+    /// Makes placeholder for a quantity of type `T`.
+    ///
+    pub fn new() -> Self {
+        Shape::with(T::dimensions())
+    }
+}
+
+impl<T: Dimensional> Default for Shape<T> {
+    fn default() -> Self {
+        Shape::new()
+    }
+}
+
+impl<T> Clone for Shape<T> {
+    fn clone(&self) -> Self {
+        Shape::with(self.dimensions)
+    }
+}
+
+impl<T> Copy for Shape<T> {}
+
+// Written like a quantity of value `NaN`, so units and dimensions are recorded as usual.
+impl<T> Debug for Shape<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dimensions.is_dimensionless() {
+            write!(f, "NaN")
+        } else {
+            write!(f, "NaN {}", self.dimensions.symbol())
+        }
+    }
+}
+
+///
+/// Makes leaf named `name` holding a placeholder for a quantity of type `T`.
+///
+pub fn leaf<T: 'static + Dimensional>(name: &'static str) -> Cherry<Shape<T>> {
+    named(name)
+}
+
+///
+/// Same as `dry::leaf`, for names made at runtime.
+///
+pub fn named<T: 'static + Dimensional, S: Into<Label>>(name: S) -> Cherry<Shape<T>> {
+    Leaf::new().name(name).value(Shape::<T>::new()).build()
+}
+
+fn combine(lhs: Dimensions, rhs: Dimensions, sign: i8) -> Dimensions {
+    Dimensions {
+        length: lhs.length + sign * rhs.length,
+        mass: lhs.mass + sign * rhs.mass,
+        time: lhs.time + sign * rhs.time,
+        electric_current: lhs.electric_current + sign * rhs.electric_current,
+        thermodynamic_temperature: lhs.thermodynamic_temperature
+            + sign * rhs.thermodynamic_temperature,
+        amount_of_substance: lhs.amount_of_substance + sign * rhs.amount_of_substance,
+        luminous_intensity: lhs.luminous_intensity + sign * rhs.luminous_intensity,
+    }
+}
+
+impl<T: Add<U>, U> Add<Shape<U>> for Shape<T> {
+    type Output = Shape<<T as Add<U>>::Output>;
+
+    fn add(self, _: Shape<U>) -> Self::Output {
+        Shape::with(self.dimensions)
+    }
+}
+
+impl<T: Sub<U>, U> Sub<Shape<U>> for Shape<T> {
+    type Output = Shape<<T as Sub<U>>::Output>;
+
+    fn sub(self, _: Shape<U>) -> Self::Output {
+        Shape::with(self.dimensions)
+    }
+}
+
+impl<T: Mul<U>, U> Mul<Shape<U>> for Shape<T> {
+    type Output = Shape<<T as Mul<U>>::Output>;
+
+    fn mul(self, other: Shape<U>) -> Self::Output {
+        Shape::with(combine(self.dimensions, other.dimensions, 1))
+    }
+}
+
+impl<T: Div<U>, U> Div<Shape<U>> for Shape<T> {
+    type Output = Shape<<T as Div<U>>::Output>;
+
+    fn div(self, other: Shape<U>) -> Self::Output {
+        Shape::with(combine(self.dimensions, other.dimensions, -1))
+    }
+}
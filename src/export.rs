@@ -0,0 +1,1209 @@
+use super::attachment::Reference;
+use super::node::{json_string, raw_json_number};
+use super::node::{Cherries, Cherry, Dimensional, Dimensions, Op, Origin, Step, Visit};
+use super::validate::{self, ErrorCode, Finding};
+use alloc::{borrow::ToOwned, format, string::{String, ToString}, vec, vec::Vec};
+use serde::{de, Deserialize, Serialize};
+use alloc::collections::BTreeMap;
+use core::convert::TryFrom;
+use core::fmt::Debug;
+
+#[cfg(feature = "std")]
+pub use super::session::Manifest;
+
+///
+/// Node value in the export schema.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Number(f32),
+    Bool(bool),
+    Text(String),
+    /// Lower and upper bound (written as `[lower,upper]`).
+    Interval(f32, f32),
+}
+
+impl Value {
+    // Json as written by `Cherries::to_json`.
+    pub(crate) fn to_json(&self) -> String {
+        match self {
+            Value::Number(value) => raw_json_number(*value),
+            Value::Bool(value) => value.to_string(),
+            Value::Text(value) => json_string(value),
+            Value::Interval(lower, upper) => {
+                format!("[{},{}]", raw_json_number(*lower), raw_json_number(*upper))
+            }
+        }
+    }
+}
+
+///
+/// Expression tree in the export schema.
+///
+/// Same structure as `Cherries::to_json()`: `label`, `value`, (if any) `uncertainty`, `unit`,
+/// (for dimensional quantity) `dimensions`, (for non-leaf) `op`, (if recorded) `origin`, `comment`,
+/// `description`, `sig_figs`, `meta`, `location`, `timestamp`, `id`, `references` and `detail`,
+/// (if any) `attachments` and `findings` (warnings and notes of validation checks), (for non-leaf) `subexpr` or (if left out) their count `collapsed`.
+///
+/// Trees are dropped without recursion, so fields cannot be moved out of them:
+/// clone them or take them with `core::mem::replace`.
+///
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tree {
+    pub label: String,
+    pub value: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<f32>,
+    pub unit: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<Dimensions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<Op>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sig_figs: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    pub detail: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Reference>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    /// Count of subexpressions left out (by `collapsed` or `truncated`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapsed: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subexpr: Vec<Tree>,
+}
+
+// Drops deep trees without recursion.
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let mut stack = core::mem::replace(&mut self.subexpr, Vec::new());
+        while let Some(mut tree) = stack.pop() {
+            stack.append(&mut tree.subexpr);
+        }
+    }
+}
+
+impl Tree {
+    ///
+    /// Parses tree from a json string produced by `Cherries::to_json()`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    /// use cherries::export::{Tree, Value};
+    ///
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// let y = Leaf::new().value(3).name("y").build();
+    /// let tree = Tree::from_json(&(x + y).to_json()).unwrap();
+    /// assert_eq!(tree.label, "(add)".to_string());
+    /// assert_eq!(tree.value, Value::Number(5.0));
+    /// assert_eq!(tree.subexpr[1].label, "y".to_string());
+    /// ```
+    ///
+    /// Trees are read in one pass without recursion, so deep trees (e.g. long chains of `+`)
+    /// are not limited by the nesting limit of `serde_json`.
+    ///
+    pub fn from_json(json: &str) -> serde_json::Result<Tree> {
+        let mut reader = Reader { json, position: 0 };
+        let tree = Tree::read(&mut reader)?;
+        reader.end()?;
+        Ok(tree)
+    }
+    // Trees of `json`, subexpressions (joined by commas) as recorded by `Node::prev`.
+    pub(crate) fn list_from_json(json: &str) -> serde_json::Result<Vec<Tree>> {
+        let mut reader = Reader { json, position: 0 };
+        let mut trees = Vec::new();
+        while reader.peek().is_some() {
+            if !trees.is_empty() {
+                reader.expect(b',')?;
+            }
+            trees.push(Tree::read(&mut reader)?);
+        }
+        Ok(trees)
+    }
+    // Reads the tree starting at the next byte of `reader`.
+    fn read(reader: &mut Reader<'_>) -> serde_json::Result<Tree> {
+        // States between tokens of the tree.
+        enum Next {
+            // A node (`{`) starts.
+            Node,
+            // A field (or `}` of a node without fields) follows.
+            Field,
+            // `,` or `}` follows a field.
+            AfterField,
+            // `,` or `]` follows a subexpression.
+            AfterSubexpr,
+        }
+        // Nodes being read, innermost last.
+        let mut open: Vec<Open> = Vec::new();
+        let mut next = Next::Node;
+        loop {
+            next = match next {
+                Next::Node => {
+                    reader.expect(b'{')?;
+                    open.push(Open::default());
+                    if reader.peek() == Some(b'}') {
+                        Next::AfterField
+                    } else {
+                        Next::Field
+                    }
+                }
+                Next::Field => {
+                    let key = reader.string()?;
+                    reader.expect(b':')?;
+                    let node = open.last_mut().ok_or_else(|| reader.error("expected node"))?;
+                    if serde_json::from_str::<String>(key)? == "subexpr" {
+                        if node.subexpr.is_some() {
+                            return Err(reader.error("duplicate field `subexpr`"));
+                        }
+                        node.subexpr = Some(Vec::new());
+                        reader.expect(b'[')?;
+                        if reader.peek() == Some(b']') {
+                            reader.position += 1;
+                            Next::AfterField
+                        } else {
+                            Next::Node
+                        }
+                    } else {
+                        let value = reader.value()?;
+                        node.head.push(if node.head.is_empty() { '{' } else { ',' });
+                        node.head.push_str(key);
+                        node.head.push(':');
+                        node.head.push_str(value);
+                        Next::AfterField
+                    }
+                }
+                Next::AfterField => match reader.token()? {
+                    b',' => Next::Field,
+                    b'}' => {
+                        let tree = open.pop().ok_or_else(|| reader.error("expected node"))?.close()?;
+                        match open.last_mut().and_then(|parent| parent.subexpr.as_mut()) {
+                            Some(subexpr) => {
+                                subexpr.push(tree);
+                                Next::AfterSubexpr
+                            }
+                            None => return Ok(tree),
+                        }
+                    }
+                    _ => return Err(reader.error("expected `,` or `}`")),
+                },
+                Next::AfterSubexpr => match reader.token()? {
+                    b',' => Next::Node,
+                    b']' => Next::AfterField,
+                    _ => return Err(reader.error("expected `,` or `]`")),
+                },
+            }
+        }
+    }
+    ///
+    /// Returns all nodes (including the root) whose dimensions are those of `Q`, in pre-order,
+    /// each with its JSON Pointer path from the root (e.g. `/subexpr/0`).
+    ///
+    /// Nodes without dimensions count as dimensionless.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Leaf;
+    /// use cherries::export::Tree;
+    /// extern crate uom;
+    /// use uom::si::{f32::*, force::newton, length::meter};
+    /// use std::convert::TryFrom;
+    ///
+    /// let f = Leaf::new().value(Force::new::<newton>(6.0)).name("F").build();
+    /// let e = Leaf::new().value(Length::new::<meter>(0.5)).name("e").build();
+    /// let n = Leaf::new().value(Force::new::<newton>(3.0)).name("N").build();
+    /// let m = (f * e).labeled("M");
+    /// let tree = Tree::try_from(&(m / n).labeled("arm")).unwrap();
+    /// let forces = tree
+    ///     .filter_by_dimension::<Force>()
+    ///     .into_iter()
+    ///     .map(|(path, node)| (path, node.label.as_str()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     forces,
+    ///     vec![("/subexpr/0/subexpr/0".to_string(), "F"), ("/subexpr/1".to_string(), "N")]
+    /// );
+    /// let lengths = tree.filter_by_dimension::<Length>();
+    /// assert_eq!(lengths[0].0, "".to_string());
+    /// assert_eq!(lengths[1].0, "/subexpr/0/subexpr/1".to_string());
+    /// assert!(tree.filter_by_dimension::<Ratio>().is_empty());
+    /// ```
+    pub fn filter_by_dimension<Q: Dimensional>(&self) -> Vec<(String, &Tree)> {
+        let mut found = Vec::new();
+        self.collect_dimension(&Q::dimensions(), String::new(), &mut found);
+        found
+    }
+    fn collect_dimension<'a>(
+        &'a self,
+        dimensions: &Dimensions,
+        path: String,
+        found: &mut Vec<(String, &'a Tree)>,
+    ) {
+        if self.dimensions.unwrap_or_default() == *dimensions {
+            found.push((path.clone(), self));
+        }
+        for (i, subexpr) in self.subexpr.iter().enumerate() {
+            subexpr.collect_dimension(dimensions, format!("{}/subexpr/{}", path, i), found);
+        }
+    }
+    ///
+    /// Returns the node (including the root) with id `id` (see `Cherry::id`), if any.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{set_record_ids, Leaf};
+    /// use cherries::export::Tree;
+    /// use std::convert::TryFrom;
+    ///
+    /// let previous = set_record_ids(true);
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// let y = Leaf::new().value(3).name("y").build();
+    /// let z = x * y.clone();
+    /// set_record_ids(previous);
+    /// let tree = Tree::try_from(&z).unwrap();
+    /// assert_eq!(tree.find_id(y.id().unwrap()).map(|node| node.label.as_str()), Some("y"));
+    /// assert_eq!(tree.find_id(0), None);
+    /// ```
+    pub fn find_id(&self, id: u64) -> Option<&Tree> {
+        if self.id == Some(id) {
+            return Some(self);
+        }
+        self.subexpr.iter().find_map(|subexpr| subexpr.find_id(id))
+    }
+    ///
+    /// Returns the first node (in pre-order, including the root) labeled `label`, if any.
+    ///
+    pub fn find_label(&self, label: &str) -> Option<&Tree> {
+        if self.label == label {
+            return Some(self);
+        }
+        self.subexpr.iter().find_map(|subexpr| subexpr.find_label(label))
+    }
+    ///
+    /// Returns tree whose nodes marked by `Cherry::detail` are summarized by their value
+    /// and `collapsed` (count of their subexpressions, which are left out).
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// extern crate serde_json;
+    /// use cherries::node::{Leaf, Cherries};
+    /// use cherries::export::Tree;
+    ///
+    /// let b = Leaf::new().value(0.3).name("b").build();
+    /// let h = Leaf::new().value(0.5).name("h").build();
+    /// let fy = Leaf::new().value(355.0).name("f_y").build();
+    /// let resistance = ((b * h).labeled("A").detail() * fy).labeled("N_Rd");
+    ///
+    /// let tree = Tree::from_json(&resistance.to_json()).unwrap();
+    /// let summary = tree.collapsed();
+    /// assert_eq!(summary.subexpr[0].collapsed, Some(2));
+    /// assert!(summary.subexpr[0].subexpr.is_empty());
+    /// assert_eq!(
+    ///     serde_json::to_string(&summary.subexpr[0]).unwrap(),
+    ///     "{\"label\":\"A\",\"value\":0.15,\"unit\":\"dimensionless\",\"op\":\"mul\",\"detail\":true,\"collapsed\":2}"
+    /// );
+    /// // full detail remains in the tree
+    /// assert_eq!(tree.subexpr[0].subexpr.len(), 2);
+    /// ```
+    pub fn collapsed(&self) -> Tree {
+        let mut tree = self.clone();
+        let mut stack = vec![&mut tree];
+        while let Some(node) = stack.pop() {
+            if node.detail && !node.subexpr.is_empty() {
+                node.collapsed = Some(node.subexpr.len());
+                node.subexpr.clear();
+            }
+            stack.extend(node.subexpr.iter_mut());
+        }
+        tree
+    }
+    // Tree of `node` with its label, value and unit alone (for nodes whose json cannot be read).
+    pub(crate) fn bare(node: &dyn Cherries) -> Tree {
+        Tree {
+            label: node.name().to_owned(),
+            value: node.value().map(Value::Number).unwrap_or_else(Value::Text),
+            uncertainty: None,
+            unit: node.symbol(),
+            dimensions: None,
+            op: None,
+            origin: None,
+            comment: None,
+            description: None,
+            sig_figs: None,
+            meta: BTreeMap::new(),
+            location: None,
+            timestamp: None,
+            id: None,
+            references: Vec::new(),
+            detail: false,
+            attachments: Vec::new(),
+            findings: Vec::new(),
+            collapsed: None,
+            subexpr: Vec::new(),
+        }
+    }
+    ///
+    /// Returns tree down to depth `max_depth` (`0` for the root only), whose nodes at that depth
+    /// are written with `collapsed` (count of their subexpressions, which are left out).
+    ///
+    pub fn truncated(&self, max_depth: usize) -> Tree {
+        let mut tree = self.clone();
+        let mut stack = vec![(&mut tree, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            if depth >= max_depth && !node.subexpr.is_empty() {
+                node.collapsed = Some(node.subexpr.len());
+                node.subexpr.clear();
+            }
+            stack.extend(node.subexpr.iter_mut().map(|sub| (sub, depth + 1)));
+        }
+        tree
+    }
+    ///
+    /// Returns tree without the subexpressions (and everything below them) for which
+    /// `keep` returns `false`. The root is always kept.
+    ///
+    pub fn filtered<F: FnMut(&Tree) -> bool>(&self, mut keep: F) -> Tree {
+        let mut tree = self.clone();
+        let mut stack = vec![&mut tree];
+        while let Some(node) = stack.pop() {
+            node.subexpr.retain(|sub| keep(sub));
+            stack.extend(node.subexpr.iter_mut());
+        }
+        tree
+    }
+    ///
+    /// Returns tree as Graphviz DOT graph, each node labeled with its label and quantity.
+    ///
+    /// Nodes with a recorded origin are filled with its color (`Origin::color`),
+    /// and comments are shown as tooltips.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Origin};
+    /// use cherries::export::Tree;
+    /// extern crate uom;
+    /// use uom::si::{f32::*, length::meter};
+    /// use std::convert::TryFrom;
+    ///
+    /// let w = Leaf::new().value(Length::new::<meter>(2.0)).name("w").build();
+    /// let n = Leaf::new().value(3.0).name("n").build();
+    /// let tree = Tree::try_from(&(w * n).labeled("\"L\"")).unwrap();
+    /// assert_eq!(
+    ///     tree.to_dot(),
+    ///     "digraph {\n\
+    ///     \x20 n0 [label=\"\\\"L\\\"\\n6 m^1\"];\n\
+    ///     \x20 n1 [label=\"w\\n2 m^1\"];\n\
+    ///     \x20 n0 -> n1;\n\
+    ///     \x20 n2 [label=\"n\\n3\"];\n\
+    ///     \x20 n0 -> n2;\n\
+    ///     }\n"
+    /// );
+    ///
+    /// let fy = Leaf::new().value(355.0).name("fy").build().with_origin(Origin::Standard);
+    /// let k = Leaf::new().value(0.9).name("k").build().with_origin(Origin::Assumed);
+    /// let dot = Tree::try_from(&(k * fy)).unwrap().to_dot();
+    /// assert!(dot.contains("  n1 [label=\"k\\n0.9\", style=filled, fillcolor=\"#fdcdac\"];\n"));
+    /// assert!(dot.contains("  n2 [label=\"fy\\n355\", style=filled, fillcolor=\"#cbd5e8\"];\n"));
+    ///
+    /// let dot = Tree::try_from(&Leaf::new().value(1.2).name("k").build().comment("see \"note 3\""))
+    ///     .unwrap()
+    ///     .to_dot();
+    /// assert!(dot.contains("  n0 [label=\"k\\n1.2\", tooltip=\"see \\\"note 3\\\"\"];\n"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n");
+        let mut stack = vec![(self, None)];
+        let mut next = 0;
+        while let Some((tree, parent)) = stack.pop() {
+            let value = match &tree.value {
+                Value::Number(value) => value.to_string(),
+                Value::Bool(value) => value.to_string(),
+                Value::Text(value) => value.clone(),
+                Value::Interval(lower, upper) => format!("[{}, {}]", lower, upper),
+            };
+            let quantity = match tree.unit.as_str() {
+                "dimensionless" => value,
+                unit => format!("{} {}", value, unit),
+            };
+            let mut fill = tree.origin.map_or_else(String::new, |origin| {
+                format!(", style=filled, fillcolor=\"{}\"", origin.color())
+            });
+            if let Some(comment) = &tree.comment {
+                fill.push_str(&format!(", tooltip=\"{}\"", escape_dot(comment)));
+            }
+            out.push_str(&format!(
+                "  n{} [label=\"{}\\n{}\"{}];\n",
+                next,
+                escape_dot(&tree.label),
+                escape_dot(&quantity),
+                fill
+            ));
+            if let Some(parent) = parent {
+                out.push_str(&format!("  n{} -> n{};\n", parent, next));
+            }
+            stack.extend(tree.subexpr.iter().rev().map(|sub| (sub, Some(next))));
+            next += 1;
+        }
+        out.push_str("}\n");
+        out
+    }
+    ///
+    /// Parses tree like `Tree::from_json` and renames deprecated labels with `aliases`.
+    ///
+    pub fn from_json_with_aliases(json: &str, aliases: &LabelAliases) -> serde_json::Result<Tree> {
+        Tree::from_json(json).map(|tree| aliases.apply(&tree))
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+///
+/// Map from deprecated node labels to their current names.
+///
+/// Applied on import so trees exported before a node was renamed
+/// (e.g. by a newer template version) still line up with current ones.
+/// Aliases may be chained (`a` to `b`, `b` to `c`); cycles stop at the last label visited.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Leaf, Cherries};
+/// use cherries::export::{LabelAliases, Tree};
+///
+/// let aliases = LabelAliases::new()
+///     .alias("load", "axial_load")
+///     .alias("axial_load", "N");
+/// assert_eq!(aliases.canonical("load"), "N");
+/// assert_eq!(aliases.canonical("A"), "A");
+///
+/// let old = Leaf::new().value(2).name("load").build() * Leaf::new().value(3).name("A").build();
+/// let new = Leaf::new().value(2).name("N").build() * Leaf::new().value(3).name("A").build();
+/// let old = Tree::from_json_with_aliases(&old.to_json(), &aliases).unwrap();
+/// assert_eq!(old.subexpr[0].label, "N".to_string());
+/// assert_eq!(old, Tree::from_json(&new.to_json()).unwrap());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabelAliases {
+    aliases: BTreeMap<String, String>,
+}
+
+impl LabelAliases {
+    ///
+    /// Makes empty alias map.
+    ///
+    pub fn new() -> Self {
+        LabelAliases::default()
+    }
+    ///
+    /// Returns map which also renames `old` to `new`.
+    ///
+    pub fn alias<S: Into<String>, N: Into<String>>(mut self, old: S, new: N) -> Self {
+        self.aliases.insert(old.into(), new.into());
+        self
+    }
+    ///
+    /// Returns current name of `label` (`label` itself if it is not deprecated).
+    ///
+    pub fn canonical<'a>(&'a self, label: &'a str) -> &'a str {
+        let mut current = label;
+        for _ in 0..self.aliases.len() {
+            match self.aliases.get(current) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        current
+    }
+    ///
+    /// Returns `tree` with every deprecated label renamed.
+    ///
+    pub fn apply(&self, tree: &Tree) -> Tree {
+        Tree {
+            label: self.canonical(&tree.label).to_owned(),
+            value: tree.value.clone(),
+            uncertainty: tree.uncertainty,
+            unit: tree.unit.clone(),
+            dimensions: tree.dimensions,
+            op: tree.op.clone(),
+            origin: tree.origin,
+            comment: tree.comment.clone(),
+            description: tree.description.clone(),
+            sig_figs: tree.sig_figs,
+            meta: tree.meta.clone(),
+            location: tree.location.clone(),
+            timestamp: tree.timestamp.clone(),
+            id: tree.id,
+            references: tree.references.clone(),
+            detail: tree.detail,
+            attachments: tree.attachments.clone(),
+            findings: tree.findings.clone(),
+            collapsed: tree.collapsed,
+            subexpr: tree.subexpr.iter().map(|sub| self.apply(sub)).collect(),
+        }
+    }
+}
+
+// Fields of a node but its subexpressions, as read by `Tree::from_json`.
+#[derive(Deserialize)]
+struct Head {
+    label: String,
+    value: Value,
+    #[serde(default)]
+    uncertainty: Option<f32>,
+    unit: String,
+    #[serde(default)]
+    dimensions: Option<Dimensions>,
+    #[serde(default)]
+    op: Option<Op>,
+    #[serde(default)]
+    origin: Option<Origin>,
+    #[serde(default)]
+    comment: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    sig_figs: Option<u32>,
+    #[serde(default)]
+    meta: BTreeMap<String, String>,
+    #[serde(default)]
+    location: Option<String>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    references: Vec<String>,
+    #[serde(default)]
+    detail: bool,
+    #[serde(default)]
+    attachments: Vec<Reference>,
+    #[serde(default)]
+    findings: Vec<Finding>,
+    #[serde(default)]
+    collapsed: Option<usize>,
+}
+
+impl Head {
+    // Tree of the node with `subexpr`.
+    fn into_tree(self, subexpr: Vec<Tree>) -> Tree {
+        Tree {
+            label: self.label,
+            value: self.value,
+            uncertainty: self.uncertainty,
+            unit: self.unit,
+            dimensions: self.dimensions,
+            op: self.op,
+            origin: self.origin,
+            comment: self.comment,
+            description: self.description,
+            sig_figs: self.sig_figs,
+            meta: self.meta,
+            location: self.location,
+            timestamp: self.timestamp,
+            id: self.id,
+            references: self.references,
+            detail: self.detail,
+            attachments: self.attachments,
+            findings: self.findings,
+            collapsed: self.collapsed,
+            subexpr,
+        }
+    }
+}
+
+// Node being read by `Tree::from_json`: json of its fields but subexpressions
+// (read by `serde_json` once the node is complete) and its subexpressions read so far.
+#[derive(Default)]
+struct Open {
+    head: String,
+    subexpr: Option<Vec<Tree>>,
+}
+
+impl Open {
+    fn close(self) -> serde_json::Result<Tree> {
+        let head = if self.head.is_empty() { "{}".to_owned() } else { self.head + "}" };
+        let head = serde_json::from_str::<Head>(&head)?;
+        Ok(head.into_tree(self.subexpr.unwrap_or_default()))
+    }
+}
+
+// Reader of the structure of trees in json, leaving fields but subexpressions to `serde_json`.
+struct Reader<'a> {
+    json: &'a str,
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, msg: &str) -> serde_json::Error {
+        de::Error::custom(format!("{} at {}", msg, self.position))
+    }
+    // Fails unless only whitespace is left.
+    fn end(&mut self) -> serde_json::Result<()> {
+        match self.peek() {
+            Some(_) => Err(self.error("trailing characters")),
+            None => Ok(()),
+        }
+    }
+    fn skip(&mut self) {
+        let bytes = self.json.as_bytes();
+        while bytes.get(self.position).map_or(false, |b| b.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+    // Next byte after whitespace.
+    fn peek(&mut self) -> Option<u8> {
+        self.skip();
+        self.json.as_bytes().get(self.position).cloned()
+    }
+    // Consumes next byte after whitespace.
+    fn token(&mut self) -> serde_json::Result<u8> {
+        let byte = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+    fn expect(&mut self, byte: u8) -> serde_json::Result<()> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", byte as char)))
+        }
+    }
+    // Json of the string starting at the next byte.
+    fn string(&mut self) -> serde_json::Result<&'a str> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected string"));
+        }
+        let (start, bytes) = (self.position, self.json.as_bytes());
+        self.position += 1;
+        loop {
+            match bytes.get(self.position) {
+                Some(b'"') => break,
+                Some(b'\\') => self.position += 2,
+                Some(_) => self.position += 1,
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        self.position += 1;
+        Ok(&self.json[start..self.position])
+    }
+    // Json of the value starting at the next byte (checked when its node is read).
+    fn value(&mut self) -> serde_json::Result<&'a str> {
+        self.skip();
+        let (start, bytes) = (self.position, self.json.as_bytes());
+        let mut depth = 0usize;
+        loop {
+            match bytes.get(self.position) {
+                Some(b'"') => {
+                    self.string()?;
+                }
+                Some(b'{') | Some(b'[') => {
+                    depth += 1;
+                    self.position += 1;
+                }
+                Some(b'}') | Some(b']') | Some(b',') if depth == 0 => break,
+                Some(b'}') | Some(b']') => {
+                    depth -= 1;
+                    self.position += 1;
+                }
+                Some(_) => self.position += 1,
+                None => return Err(self.error("unexpected end")),
+            }
+        }
+        if start == self.position {
+            return Err(self.error("expected value"));
+        }
+        Ok(&self.json[start..self.position])
+    }
+}
+
+///
+/// Tree of the node read from its recorded data (without recursion, so deep trees are fine).
+///
+/// Values and units are those of `Cherries::to_json` in base units: unlike `to_json`,
+/// the display settings of the thread (`units::set_display_units`, `units::set_symbol_style`
+/// and `rounding::set_print_policy`) are not applied.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::export::{Tree, Value};
+/// use cherries::units::{set_symbol_style, SymbolStyle};
+/// extern crate uom;
+/// use uom::si::{f32::*, length::meter};
+/// use std::convert::TryFrom;
+///
+/// let l = Leaf::new().value(Length::new::<meter>(2.0)).name("l").build();
+/// set_symbol_style(SymbolStyle::Name);
+/// let tree = Tree::try_from(&l).unwrap();
+/// assert!(l.to_json().contains("\"unit\":\"meter\""));
+/// set_symbol_style(SymbolStyle::Base);
+/// assert_eq!(tree.unit, "m^1".to_string());
+/// assert_eq!(tree.value, Value::Number(2.0));
+/// ```
+impl<'a, T: Clone + Debug> TryFrom<&'a Cherry<T>> for Tree {
+    type Error = serde_json::Error;
+
+    fn try_from(node: &'a Cherry<T>) -> serde_json::Result<Tree> {
+        // Trees whose subexpressions are still being read, innermost last.
+        let (mut open, mut root, mut error) = (Vec::<Tree>::new(), None, None);
+        node.traverse(|step| match step {
+            Step::Enter(head) => match Tree::from_json(&format!("{}}}", head)) {
+                Ok(tree) => open.push(tree),
+                Err(err) => error = error.take().or(Some(err)),
+            },
+            Step::Json(json) => match Tree::list_from_json(json) {
+                Ok(mut trees) => {
+                    if let Some(parent) = open.last_mut() {
+                        parent.subexpr.append(&mut trees);
+                    }
+                }
+                Err(err) => error = error.take().or(Some(err)),
+            },
+            Step::Leave => {
+                if let Some(tree) = open.pop() {
+                    match open.last_mut() {
+                        Some(parent) => parent.subexpr.push(tree),
+                        None => root = Some(tree),
+                    }
+                }
+            }
+        });
+        match (error, root) {
+            (Some(err), _) => Err(err),
+            (None, Some(root)) => Ok(root),
+            (None, None) => Err(de::Error::custom("empty tree")),
+        }
+    }
+}
+
+///
+/// Validation failure in the export schema.
+///
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Failure {
+    pub label: String,
+    pub msg: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub codes: Vec<ErrorCode>,
+    pub tree: Tree,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub against: Vec<Tree>,
+}
+
+impl<'a> From<&'a validate::Error> for Failure {
+    fn from(err: &'a validate::Error) -> Failure {
+        Failure {
+            label: err.label.to_owned(),
+            msg: err.msg.to_owned(),
+            codes: err.codes.clone(),
+            tree: err.tree.clone(),
+            against: err.against.clone(),
+        }
+    }
+}
+
+///
+/// Result of a validated calculation in the export schema.
+///
+/// Serialized as `{"status":"ok","tree":{...}}` or `{"status":"error","error":{...}}`,
+/// ready to be returned as a response body of web services.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// extern crate serde_json;
+/// use cherries::node::Leaf;
+/// use cherries::export::Outcome;
+/// use cherries::validate::Validate;
+/// use std::convert::TryFrom;
+///
+/// let x = Leaf::new().value(3).name("x").build();
+/// let res = x.validate("must be even", |v| v % 2 == 0).into_result();
+/// let body = Outcome::try_from(&res).unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&body).unwrap(),
+///     "{\
+///         \"status\":\"error\",\
+///         \"error\":{\
+///             \"label\":\"x\",\
+///             \"msg\":[\"must be even\"],\
+///             \"tree\":{\"label\":\"x\",\"value\":3.0,\"unit\":\"dimensionless\"}\
+///         }\
+///     }"
+/// );
+/// ```
+#[must_use]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Outcome {
+    Ok { tree: Tree },
+    Error { error: Failure },
+}
+
+impl<'a, T: Clone + Debug> TryFrom<&'a validate::Result<T>> for Outcome {
+    type Error = serde_json::Error;
+
+    fn try_from(result: &'a validate::Result<T>) -> serde_json::Result<Outcome> {
+        match result {
+            Ok(node) => Ok(Outcome::Ok {
+                tree: Tree::try_from(node)?,
+            }),
+            Err(err) => Ok(Outcome::Error {
+                error: Failure::from(err),
+            }),
+        }
+    }
+}
+
+///
+/// Output format of an export.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// `Cherries::to_json`.
+    Json,
+    /// `report::Renderer::to_markdown`.
+    Markdown,
+    /// `report::Renderer::to_html`.
+    Html,
+}
+
+///
+/// Options of an export.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Deepest level of nodes written (`Some(0)` writes the root only, `None` the whole tree).
+    pub max_depth: Option<usize>,
+}
+
+impl ExportOptions {
+    ///
+    /// Options writing the root node only.
+    ///
+    pub fn summary_only() -> Self {
+        ExportOptions { max_depth: Some(0) }
+    }
+}
+
+const DIMENSIONLESS: &str = "\"unit\":\"dimensionless\"";
+
+// Sizes of nodes up to a depth, in bytes of their own json (without subexpressions).
+#[derive(Default)]
+struct Scan {
+    nodes: usize,
+    nonleaf: usize,
+    dimensionless: usize,
+    bytes: usize,
+    // Bytes of `,"op":".."` of nodes, not shown in reports.
+    ops: usize,
+    // Sum of depths of nodes.
+    depth: usize,
+    deepest: usize,
+}
+
+impl Scan {
+    fn visit(&mut self, visit: Visit<'_>, max_depth: usize) {
+        match visit {
+            Visit::Node { depth, head, subexpr, .. } => {
+                self.nodes += 1;
+                self.depth += depth;
+                self.deepest = self.deepest.max(depth);
+                self.bytes += head.len() + 1;
+                if head.contains(DIMENSIONLESS) {
+                    self.dimensionless += 1;
+                }
+                if let Some(start) = head.find(",\"op\":\"") {
+                    let name = &head[start + 7..];
+                    self.ops += 7 + name.find('"').map_or(name.len(), |end| end + 1);
+                }
+                if let Some(count) = subexpr {
+                    self.nonleaf += 1;
+                    self.bytes += ",\"subexpr\":[]".len() + count.saturating_sub(1);
+                }
+            }
+            Visit::Json { depth, json } => self.json(json, depth, max_depth),
+        }
+    }
+    // Scans json text of subexpressions at depth `base`, without allocation.
+    fn json(&mut self, json: &str, base: usize, max_depth: usize) {
+        let bytes = json.as_bytes();
+        let (mut depth, mut in_dimensions) = (base - 1, false);
+        let (mut in_string, mut escaped) = (false, false);
+        for (i, &byte) in bytes.iter().enumerate() {
+            if in_string {
+                in_string = escaped || byte != b'"';
+                escaped = !escaped && byte == b'\\';
+            } else {
+                match byte {
+                    b'"' if json[i..].starts_with(DIMENSIONLESS) => {
+                        in_string = true;
+                        if depth <= max_depth {
+                            self.dimensionless += 1;
+                        }
+                    }
+                    b'"' => in_string = true,
+                    b'{' if json[i..].starts_with("{\"label\":") => {
+                        depth += 1;
+                        if depth <= max_depth {
+                            self.nodes += 1;
+                            self.depth += depth;
+                            self.deepest = self.deepest.max(depth);
+                        }
+                    }
+                    b'{' => in_dimensions = true,
+                    b'}' if in_dimensions => in_dimensions = false,
+                    b'}' => depth -= 1,
+                    b'[' if depth < max_depth => self.nonleaf += 1,
+                    _ => (),
+                }
+            }
+            if depth <= max_depth {
+                self.bytes += 1;
+            }
+        }
+    }
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    ///
+    /// Returns number of nodes of the tree (including the root),
+    /// counting subexpressions shared by several nodes once per use.
+    ///
+    /// Counted by walking the tree without serializing it, so expressions can be checked
+    /// for runaway growth cheaply.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    ///
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// let y = Leaf::new().value(3).name("y").build();
+    /// assert_eq!(x.node_count(), 1);
+    /// assert_eq!(x.depth(), 0);
+    /// let z = (x.clone() + y).labeled("z") * x;
+    /// assert_eq!(z.node_count(), 5);
+    /// assert_eq!(z.depth(), 2);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.scan(usize::max_value()).nodes
+    }
+    ///
+    /// Returns the largest depth of a node of the tree (`0` for leaves, see `iter_nodes`).
+    ///
+    pub fn depth(&self) -> usize {
+        self.scan(usize::max_value()).deepest
+    }
+    fn scan(&self, max_depth: usize) -> Scan {
+        let mut scan = Scan::default();
+        self.walk(max_depth, |visit| scan.visit(visit, max_depth));
+        scan
+    }
+    ///
+    /// Returns estimated size in bytes of exporting the tree in `format` with `options`.
+    ///
+    /// Estimated by walking the tree without serializing it, so it is cheap
+    /// even for massive trees. Json size is exact unless display units, print policy or
+    /// symbol style are set; Markdown and HTML sizes are approximations.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::export::{ExportOptions, Format};
+    /// use cherries::report::Renderer;
+    ///
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// let y = Leaf::new().value(3).name("y").build();
+    /// let z = (x.clone() + y).labeled("z") * x;
+    /// let json = z.to_json();
+    /// assert_eq!(z.estimated_export_size(Format::Json, ExportOptions::default()), json.len());
+    /// let summary = z.estimated_export_size(Format::Json, ExportOptions::summary_only());
+    /// assert_eq!(summary, "{\"label\":\"(mul)\",\"value\":10,\"unit\":\"dimensionless\",\"op\":\"mul\"}".len());
+    ///
+    /// let close = |actual: usize, estimated: usize| actual * 9 <= estimated * 10 && estimated * 9 <= actual * 10;
+    /// let markdown = Renderer::new().to_markdown(&z).unwrap().len();
+    /// assert!(close(markdown, z.estimated_export_size(Format::Markdown, ExportOptions::default())));
+    /// let html = Renderer::new().to_html(&z).unwrap().len();
+    /// assert!(close(html, z.estimated_export_size(Format::Html, ExportOptions::default())));
+    /// ```
+    pub fn estimated_export_size(&self, format: Format, options: ExportOptions) -> usize {
+        let scan = self.scan(options.max_depth.unwrap_or(usize::max_value()));
+        // Json syntax around every node (`{"label":"","value":,"unit":""}`),
+        // list (`,"subexpr":[]`) and separator, and units and operations not shown in reports.
+        let content = scan.bytes.saturating_sub(
+            scan.nodes * 32
+                + scan.nonleaf * 13
+                + scan.dimensionless * "dimensionless".len()
+                + scan.ops,
+        );
+        match format {
+            Format::Json => scan.bytes,
+            // `- **{label}** = {value} {unit}\n` indented by two spaces per depth.
+            Format::Markdown => content + scan.nodes * 11 + scan.depth * 2,
+            // `<li><b>{label}</b> = {value} {unit}</li>` and `<ul></ul>` per list.
+            Format::Html => content + scan.nodes * 20 + (scan.nonleaf + 1) * 9,
+        }
+    }
+}
+
+///
+/// JSON Schema (draft-07) of `Tree`, the json written by `Cherries::to_json`.
+///
+pub const SCHEMA: &str = r##"{"$schema":"http://json-schema.org/draft-07/schema#","title":"cherries expression tree","$ref":"#/definitions/tree","definitions":{"tree":{"type":"object","required":["label","value","unit"],"properties":{"label":{"type":"string"},"value":{"type":["number","boolean","string","array"],"items":{"type":"number"},"minItems":2,"maxItems":2},"uncertainty":{"type":"number"},"unit":{"type":"string"},"dimensions":{"type":"object","additionalProperties":{"type":"integer"}},"op":{"type":"string"},"origin":{"enum":["measured","assumed","standard","computed"]},"comment":{"type":"string"},"description":{"type":"string"},"sig_figs":{"type":"integer","minimum":0},"meta":{"type":"object","additionalProperties":{"type":"string"}},"location":{"type":"string"},"timestamp":{"type":"string","format":"date-time"},"id":{"type":"integer","minimum":1},"references":{"type":"array","items":{"type":"string"}},"detail":{"type":"boolean"},"attachments":{"type":"array","items":{"type":"object","required":["mime"],"properties":{"path":{"type":"string"},"digest":{"type":"string"},"size":{"type":"integer"},"mime":{"type":"string"}}}},"findings":{"type":"array","items":{"type":"object","required":["severity","msg"],"properties":{"severity":{"enum":["error","warning","info"]},"msg":{"type":"string"}}}},"collapsed":{"type":"integer","minimum":0},"subexpr":{"type":"array","items":{"$ref":"#/definitions/tree"}}}}}}"##;
+
+// File in the index of a bundle.
+#[cfg(feature = "std")]
+#[derive(Serialize)]
+struct IndexEntry {
+    name: String,
+    mime: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+}
+
+///
+/// Writes a zip archive to `path` holding everything needed to audit the calculation of `node`
+/// (requires feature `std`).
+///
+/// See `write_bundle_zip` for the content of the archive.
+///
+#[cfg(feature = "std")]
+pub fn bundle_zip<T, P>(path: P, node: &Cherry<T>, manifest: &Manifest) -> std::io::Result<()>
+where
+    T: Clone + Debug,
+    P: AsRef<std::path::Path>,
+{
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write_bundle_zip(file, node, manifest).map(drop)
+}
+
+///
+/// Writes zip archive of the calculation of `node` to `out` and returns `out`
+/// (requires feature `std`).
+///
+/// The archive holds `index.json` (name and mime type of every file), `tree.json`,
+/// `manifest.json`, `tree.dot` (see `Tree::to_dot`), `schema.json` (see `SCHEMA`) and
+/// the attachments of all nodes as `attachments/{digest}`.
+/// Attachments referenced by path are read from the file system.
+/// Files are stored uncompressed with a fixed timestamp, so identical calculations
+/// produce identical archives.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// use cherries::export::write_bundle_zip;
+/// use cherries::session::Session;
+///
+/// let fc = Leaf::new()
+///     .value(24.5)
+///     .name("fc")
+///     .build()
+///     .attach(b"specimen,strength\n1,24.1\n2,24.9\n".to_vec(), "text/csv");
+/// let k = Leaf::new().value(0.85).name("k").build();
+/// let mut session = Session::new();
+/// session.input(&fc).input(&k);
+/// let design = (fc * k).labeled("fcd");
+/// session.output(&design);
+///
+/// let zip = write_bundle_zip(Vec::new(), &design, &session.manifest()).unwrap();
+/// assert_eq!(&zip[..4], b"PK\x03\x04");
+/// let content = String::from_utf8_lossy(&zip);
+/// for name in &["index.json", "tree.json", "manifest.json", "tree.dot", "schema.json", "attachments/"] {
+///     assert!(content.contains(name));
+/// }
+/// assert!(content.contains("specimen,strength"));
+/// assert_eq!(zip, write_bundle_zip(Vec::new(), &design, &session.manifest()).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn write_bundle_zip<T, W>(out: W, node: &Cherry<T>, manifest: &Manifest) -> std::io::Result<W>
+where
+    T: Clone + Debug,
+    W: std::io::Write,
+{
+    use super::attachment::Source;
+    use std::io::{Error, ErrorKind};
+
+    let invalid = |err: serde_json::Error| Error::new(ErrorKind::InvalidData, err);
+    let json = node.to_json();
+    let dot = Tree::from_json(&json).map_err(invalid)?.to_dot();
+    let mut attachments = Vec::new();
+    node.walk(usize::max_value(), |visit| {
+        if let Visit::Node { attachments: found, .. } = visit {
+            attachments.extend(found.iter().cloned());
+        }
+    });
+    let mut files = vec![
+        ("tree.json".to_owned(), "application/json".to_owned(), None, json.into_bytes()),
+        (
+            "manifest.json".to_owned(),
+            "application/json".to_owned(),
+            None,
+            manifest.to_json().into_bytes(),
+        ),
+        ("tree.dot".to_owned(), "text/vnd.graphviz".to_owned(), None, dot.into_bytes()),
+        ("schema.json".to_owned(), "application/schema+json".to_owned(), None, SCHEMA.as_bytes().to_vec()),
+    ];
+    let mut bundled = std::collections::BTreeSet::new();
+    for attachment in attachments {
+        let (path, content) = match attachment.source {
+            Source::Path(path) => {
+                let content = std::fs::read(&path)?;
+                (Some(path), content)
+            }
+            Source::Bytes(bytes) => (None, bytes.to_vec()),
+        };
+        let name = format!("attachments/{}", super::store::digest(&content));
+        if bundled.insert(name.clone()) {
+            files.push((name, attachment.mime, path, content));
+        }
+    }
+    let index = files
+        .iter()
+        .map(|(name, mime, path, _)| IndexEntry {
+            name: name.clone(),
+            mime: mime.clone(),
+            path: path.clone(),
+        })
+        .collect::<Vec<_>>();
+    let index = serde_json::to_vec(&serde_json::json!({ "files": index })).map_err(invalid)?;
+    let mut zip = super::zip::ZipWriter::new(out);
+    zip.add("index.json", &index)?;
+    for (name, _, _, content) in &files {
+        zip.add(name, content)?;
+    }
+    zip.finish()
+}
@@ -0,0 +1,69 @@
+///
+/// Builds expression from plain values with natural syntax.
+///
+/// Identifiers and literals become leaves named after themselves (values are cloned),
+/// `{node}` takes an existing node (cloned) as it is,
+/// and parentheses and operators are kept as written.
+/// With `name = ...` the result is labeled `name`.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # extern crate uom;
+/// # use uom::si::{f32::*, length::meter, area::square_meter};
+/// # fn main() {
+/// let width = Length::new::<meter>(2.0);
+/// let height = Length::new::<meter>(3.0);
+/// let area = expr! { area = width * height };
+/// assert_eq!(area.name(), "area");
+/// assert_eq!(area.quantity(), &Area::new::<square_meter>(6.0));
+/// let eager = (Leaf::new().value(width).name("width").build()
+///     * Leaf::new().value(height).name("height").build())
+///     .labeled("area");
+/// assert_eq!(area.to_json(), eager.to_json());
+///
+/// let margin = Length::new::<meter>(0.5);
+/// let framed = expr! { framed = {area} + (width + 2.0 * margin) * margin };
+/// assert_eq!(framed.quantity(), &Area::new::<square_meter>(7.5));
+/// assert_eq!(expr!(width - margin).name(), "(sub)");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! expr {
+    ( $name:ident = $( $expr:tt )+ ) => {
+        $crate::expr!(@munch [] $( $expr )+).labeled(stringify!($name))
+    };
+    ( @munch [ $( $acc:tt )* ] ) => {
+        $( $acc )*
+    };
+    ( @munch [ $( $acc:tt )* ] $leaf:ident $( $rest:tt )* ) => {
+        $crate::expr!(@munch [ $( $acc )* ($crate::node::Leaf::new()
+            .value(::core::clone::Clone::clone(&$leaf))
+            .name(stringify!($leaf))
+            .build()) ] $( $rest )*)
+    };
+    // before literals, which would take `- x` as a negative literal
+    ( @munch [ $( $acc:tt )* ] - $( $rest:tt )* ) => {
+        $crate::expr!(@munch [ $( $acc )* - ] $( $rest )*)
+    };
+    ( @munch [ $( $acc:tt )* ] $leaf:literal $( $rest:tt )* ) => {
+        $crate::expr!(@munch [ $( $acc )* ($crate::node::Leaf::new()
+            .value($leaf)
+            .name(stringify!($leaf))
+            .build()) ] $( $rest )*)
+    };
+    ( @munch [ $( $acc:tt )* ] { $( $node:tt )+ } $( $rest:tt )* ) => {
+        $crate::expr!(@munch [ $( $acc )* (::core::clone::Clone::clone(&($( $node )+))) ] $( $rest )*)
+    };
+    ( @munch [ $( $acc:tt )* ] ( $( $inner:tt )+ ) $( $rest:tt )* ) => {
+        $crate::expr!(@munch [ $( $acc )* ($crate::expr!(@munch [] $( $inner )+)) ] $( $rest )*)
+    };
+    ( @munch [ $( $acc:tt )* ] $op:tt $( $rest:tt )* ) => {
+        $crate::expr!(@munch [ $( $acc )* $op ] $( $rest )*)
+    };
+    ( $( $expr:tt )+ ) => {
+        $crate::expr!(@munch [] $( $expr )+)
+    };
+}
@@ -1,36 +1,263 @@
-use super::node::{Cherries, Cherry, Node};
-use std::boxed::Box;
-use std::fmt::Debug;
-use std::ops::{Add, Mul};
-use std::vec::Vec;
+use super::node::{check_invariants, Cherries, Cherry, Label, Node, Op, Subexpr};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::convert::TryFrom;
+use core::fmt::Debug;
+use core::ops::{Add, Mul, Sub};
+use uom::num::FromPrimitive;
+#[cfg(feature = "std")]
+use uom::typenum::{PartialDiv, PartialQuot, P2};
+
+///
+/// Division by the number of folded items.
+///
+/// Implemented for primitive numbers and uom quantities, used by `mean!`.
+///
+/// # Panics
+///
+/// Division of integers and uom quantities panics if `count` is not representable in their
+/// (storage) type (e.g. more than 127 nodes of `i8` values or quantities).
+///
+pub trait DivCount {
+    type Output;
+    fn div_count(self, count: usize) -> Self::Output;
+}
+
+macro_rules! impl_div_count {
+    ( float: $( $t:ty ),* ) => {
+        $(
+            impl DivCount for $t {
+                type Output = $t;
+                fn div_count(self, count: usize) -> $t {
+                    self / count as $t
+                }
+            }
+        )*
+    };
+    ( int: $( $t:ty ),* ) => {
+        $(
+            impl DivCount for $t {
+                type Output = $t;
+                // Counts out of range of `$t` would wrap (see `# Panics`); `usize` converts to itself.
+                #[allow(clippy::expect_used, clippy::useless_conversion)]
+                fn div_count(self, count: usize) -> $t {
+                    self / <$t>::try_from(count).expect("count is not representable in storage type")
+                }
+            }
+        )*
+    };
+}
+
+impl_div_count!(float: f32, f64);
+impl_div_count!(int: i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl<D, U, V> DivCount for uom::si::Quantity<D, U, V>
+where
+    D: uom::si::Dimension + ?Sized,
+    D::Kind: uom::marker::Div,
+    U: uom::si::Units<V> + ?Sized,
+    V: uom::num::Num + uom::Conversion<V> + FromPrimitive,
+{
+    type Output = uom::si::Quantity<D, U, V>;
+
+    // Storage types used with uom represent any realistic count.
+    #[allow(clippy::expect_used)]
+    fn div_count(self, count: usize) -> Self::Output {
+        self / V::from_usize(count).expect("count is not representable in storage type")
+    }
+}
+
+#[cfg(feature = "std")]
+///
+/// Square root, used by `stddev!`.
+///
+/// Implemented for floating point numbers and uom quantities with even dimensions.
+///
+pub trait Sqrt {
+    type Output;
+    fn sqrt(self) -> Self::Output;
+}
+
+#[cfg(feature = "std")]
+impl Sqrt for f32 {
+    type Output = f32;
+    fn sqrt(self) -> f32 {
+        f32::sqrt(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Sqrt for f64 {
+    type Output = f64;
+    fn sqrt(self) -> f64 {
+        f64::sqrt(self)
+    }
+}
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub type SqrtDimension<D> = uom::si::ISQ<
+    PartialQuot<<D as uom::si::Dimension>::L, P2>,
+    PartialQuot<<D as uom::si::Dimension>::M, P2>,
+    PartialQuot<<D as uom::si::Dimension>::T, P2>,
+    PartialQuot<<D as uom::si::Dimension>::I, P2>,
+    PartialQuot<<D as uom::si::Dimension>::Th, P2>,
+    PartialQuot<<D as uom::si::Dimension>::N, P2>,
+    PartialQuot<<D as uom::si::Dimension>::J, P2>,
+>;
+
+#[cfg(feature = "std")]
+impl<D, U, V> Sqrt for uom::si::Quantity<D, U, V>
+where
+    D: uom::si::Dimension + ?Sized,
+    D::L: PartialDiv<P2>,
+    D::M: PartialDiv<P2>,
+    D::T: PartialDiv<P2>,
+    D::I: PartialDiv<P2>,
+    D::Th: PartialDiv<P2>,
+    D::N: PartialDiv<P2>,
+    D::J: PartialDiv<P2>,
+    D::Kind: uom::marker::Div,
+    U: uom::si::Units<V> + ?Sized,
+    V: uom::num::Float + uom::Conversion<V>,
+{
+    type Output = uom::si::Quantity<SqrtDimension<D>, U, V>;
+
+    fn sqrt(self) -> Self::Output {
+        uom::si::Quantity::sqrt(self)
+    }
+}
 
 #[doc(hidden)]
 pub struct FoldProxy<T> {
     pub value: T,
-    pub items: Vec<Box<dyn Cherries>>,
+    pub items: Vec<Subexpr>,
 }
 
 #[doc(hidden)]
-impl<T: Clone + Debug> FoldProxy<T> {
+impl<T: 'static + Clone + Debug> FoldProxy<T> {
+    #[cfg_attr(feature = "track-caller", track_caller)]
     pub fn into_expr(self) -> Cherry<T> {
-        Node::new()
-            .name("foldl".to_string())
-            .value(self.value.clone())
-            .prev(
-                self.items
-                    .iter()
-                    .map(|x| x.to_json())
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-            .build()
+        self.into_node(Op::Fold, "foldl")
+    }
+    // Node `(name)` made by operation `name` (e.g. `mean`).
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn into_expr_named(self, name: &str) -> Cherry<T> {
+        self.into_node(Op::from(name), format!("({})", name))
+    }
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn into_node<S: Into<Label>>(self, op: Op, label: S) -> Cherry<T> {
+        let node = Node::new()
+            .name(label)
+            .op(op)
+            .value(self.value)
+            .children(self.items)
+            .build();
+        check_invariants(&node);
+        node
     }
 }
 
 #[doc(hidden)]
-impl<T: 'static + Clone + Debug + std::cmp::PartialOrd> FoldProxy<T> {
+pub struct SampleProxy<T> {
+    pub samples: Vec<T>,
+    pub items: Vec<Subexpr>,
+}
+
+#[doc(hidden)]
+impl<T: 'static + Clone + Debug> SampleProxy<T> {
+    pub fn push(self, other: Cherry<T>) -> SampleProxy<T> {
+        let mut ret = self;
+        ret.samples.push(other.quantity().clone());
+        ret.items.push(other.as_subexpr());
+        ret
+    }
+}
+
+#[doc(hidden)]
+impl<T> SampleProxy<T>
+where
+    T: Clone + Debug + Add<Output = T> + Sub<Output = T> + Mul<T> + DivCount<Output = T>,
+    <T as Mul<T>>::Output: Clone
+        + Debug
+        + Add<Output = <T as Mul<T>>::Output>
+        + DivCount<Output = <T as Mul<T>>::Output>,
+{
+    pub fn variance(self) -> FoldProxy<<T as Mul<T>>::Output> {
+        let count = self.samples.len();
+        let mean = self.samples[1..]
+            .iter()
+            .cloned()
+            .fold(self.samples[0].clone(), |acc, x| acc + x)
+            .div_count(count);
+        let squares = self
+            .samples
+            .iter()
+            .map(|x| (x.clone() - mean.clone()) * (x.clone() - mean.clone()))
+            .collect::<Vec<_>>();
+        FoldProxy {
+            value: squares[1..]
+                .iter()
+                .cloned()
+                .fold(squares[0].clone(), |acc, x| acc + x)
+                .div_count(count - 1),
+            items: self.items,
+        }
+    }
+    #[cfg(feature = "std")]
+    pub fn stddev(self) -> FoldProxy<<<T as Mul<T>>::Output as Sqrt>::Output>
+    where
+        <T as Mul<T>>::Output: Sqrt,
+        <<T as Mul<T>>::Output as Sqrt>::Output: Clone + Debug,
+    {
+        let variance = self.variance();
+        FoldProxy {
+            value: variance.value.sqrt(),
+            items: variance.items,
+        }
+    }
+}
+
+#[doc(hidden)]
+impl<T> SampleProxy<T>
+where
+    T: Clone + Debug + PartialOrd + Add<Output = T> + DivCount<Output = T>,
+{
+    pub fn median(self) -> FoldProxy<T> {
+        let mut samples = self.samples;
+        samples.sort_by(|lhs, rhs| {
+            lhs.partial_cmp(rhs)
+                .unwrap_or_else(|| panic!("cannot compare {:?} and {:?}.", lhs, rhs))
+        });
+        let mid = samples.len() / 2;
+        FoldProxy {
+            value: if samples.len() % 2 == 0 {
+                (samples[mid - 1].clone() + samples[mid].clone()).div_count(2)
+            } else {
+                samples[mid].clone()
+            },
+            items: self.items,
+        }
+    }
+}
+
+#[doc(hidden)]
+impl<T: Clone + Debug + DivCount> FoldProxy<T>
+where
+    <T as DivCount>::Output: Clone + Debug,
+{
+    pub fn mean(self) -> FoldProxy<<T as DivCount>::Output> {
+        let count = self.items.len();
+        FoldProxy {
+            value: self.value.div_count(count),
+            items: self.items,
+        }
+    }
+}
+
+#[doc(hidden)]
+impl<T: 'static + Clone + Debug + core::cmp::PartialOrd> FoldProxy<T> {
     pub fn max(self, other: Cherry<T>) -> FoldProxy<T> {
-        use std::cmp::Ordering;
+        use core::cmp::Ordering;
         let mut ret = FoldProxy {
             value: match (&self.value).partial_cmp(other.quantity()) {
                 Some(Ordering::Less) => other.quantity().clone(),
@@ -46,11 +273,11 @@ impl<T: 'static + Clone + Debug + std::cmp::PartialOrd> FoldProxy<T> {
             },
             items: self.items,
         };
-        ret.items.push(Box::new(other));
+        ret.items.push(other.as_subexpr());
         ret
     }
     pub fn min(self, other: Cherry<T>) -> FoldProxy<T> {
-        use std::cmp::Ordering;
+        use core::cmp::Ordering;
         let mut ret = FoldProxy {
             value: match (&self.value).partial_cmp(other.quantity()) {
                 Some(Ordering::Less) => self.value.clone(),
@@ -66,7 +293,93 @@ impl<T: 'static + Clone + Debug + std::cmp::PartialOrd> FoldProxy<T> {
             },
             items: self.items,
         };
-        ret.items.push(Box::new(other));
+        ret.items.push(other.as_subexpr());
+        ret
+    }
+}
+
+#[doc(hidden)]
+impl<T: 'static + Clone + Debug> FoldProxy<T> {
+    pub fn add_weighted<W, U>(
+        self,
+        weight: Cherry<W>,
+        other: Cherry<U>,
+    ) -> FoldProxy<<T as Add<<W as Mul<U>>::Output>>::Output>
+    where
+        W: 'static + Clone + Debug + Mul<U>,
+        U: 'static + Clone + Debug,
+        T: Add<<W as Mul<U>>::Output>,
+        <T as Add<<W as Mul<U>>::Output>>::Output: Clone + Debug,
+    {
+        let mut ret = FoldProxy {
+            value: self.value.clone() + weight.quantity().clone() * other.quantity().clone(),
+            items: self.items,
+        };
+        ret.items.push(weight.as_subexpr());
+        ret.items.push(other.as_subexpr());
+        ret
+    }
+}
+
+#[doc(hidden)]
+pub struct ArgProxy<T> {
+    pub value: T,
+    pub winner: String,
+    pub items: Vec<Subexpr>,
+}
+
+#[doc(hidden)]
+impl<T: 'static + Clone + Debug + core::cmp::PartialOrd> ArgProxy<T> {
+    fn select(self, other: Cherry<T>, ordering: core::cmp::Ordering) -> ArgProxy<T> {
+        let replace = match other.quantity().partial_cmp(&self.value) {
+            Some(ord) => ord == ordering,
+            None => panic!(
+                "cannot compare {:?} and {:?}.",
+                self.value,
+                other.quantity()
+            ),
+        };
+        let mut ret = if replace {
+            ArgProxy {
+                value: other.quantity().clone(),
+                winner: other.name().into(),
+                items: self.items,
+            }
+        } else {
+            self
+        };
+        ret.items.push(other.as_subexpr());
+        ret
+    }
+    pub fn max(self, other: Cherry<T>) -> ArgProxy<T> {
+        self.select(other, core::cmp::Ordering::Greater)
+    }
+    pub fn min(self, other: Cherry<T>) -> ArgProxy<T> {
+        self.select(other, core::cmp::Ordering::Less)
+    }
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn into_expr_named(self, name: &str) -> Cherry<T> {
+        let label = format!("({}: {})", name, self.winner);
+        FoldProxy {
+            value: self.value,
+            items: self.items,
+        }
+        .into_node(Op::from(name), label)
+    }
+}
+
+#[doc(hidden)]
+impl<T: Clone + Debug> FoldProxy<T> {
+    pub fn fold_with<U, F>(self, other: Cherry<U>, f: &mut F) -> FoldProxy<T>
+    where
+        U: 'static + Clone + Debug,
+        F: FnMut(T, &U) -> T,
+    {
+        let mut ret = FoldProxy {
+            value: f(self.value, other.quantity()),
+            items: self.items,
+        };
+        ret.items.push(other.as_subexpr());
         ret
     }
 }
@@ -84,7 +397,7 @@ where
             value: self.value.clone() + other.quantity().clone(),
             items: self.items,
         };
-        ret.items.push(Box::new(other));
+        ret.items.push(other.as_subexpr());
         ret
     }
 }
@@ -102,7 +415,7 @@ where
             value: self.value.clone() * other.quantity().clone(),
             items: self.items,
         };
-        ret.items.push(Box::new(other));
+        ret.items.push(other.as_subexpr());
         ret
     }
 }
@@ -132,7 +445,7 @@ macro_rules! prod_all {
     ( $head:expr, $( $tail:expr ),* ) => {
         {
             let head = $head;
-            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: vec![Box::new(head)] }$( * $tail)*).into_expr()
+            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$( * $tail)*).into_expr()
         }
     };
 }
@@ -162,7 +475,7 @@ macro_rules! sum_all {
     ( $head:expr, $( $tail:expr ),* ) => {
         {
             let head = $head;
-            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: vec![Box::new(head)] }$( + $tail)*).into_expr()
+            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$( + $tail)*).into_expr()
         }
     };
 }
@@ -171,7 +484,10 @@ macro_rules! sum_all {
 /// Fold left with `min` all given expression.
 ///
 /// This marco uses `partial_cmp` inside the expanded codes.
-/// Panics if and only if `partial_cmp` returns `None`.
+///
+/// # Panics
+///
+/// Panics if `partial_cmp` of two values returns `None` (e.g. for NaN).
 ///
 /// # Examples
 ///
@@ -192,7 +508,7 @@ macro_rules! minimum {
     ( $head:expr, $( $tail:expr ),* ) => {
         {
             let head = $head;
-            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: vec![Box::new(head)] }$(.min($tail))*).into_expr()
+            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$(.min($tail))*).into_expr()
         }
     };
 }
@@ -201,7 +517,10 @@ macro_rules! minimum {
 /// Fold left with `max` all given expression.
 ///
 /// This marco uses `partial_cmp` inside the expanded codes.
-/// Panics if and only if `partial_cmp` returns `None`.
+///
+/// # Panics
+///
+/// Panics if `partial_cmp` of two values returns `None` (e.g. for NaN).
 ///
 /// # Examples
 ///
@@ -224,7 +543,673 @@ macro_rules! maximum {
     ( $head:expr, $( $tail:expr ),* ) => {
         {
             let head = $head;
-            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: vec![Box::new(head)] }$(.max($tail))*).into_expr()
+            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$(.max($tail))*).into_expr()
+        }
+    };
+}
+
+///
+/// Fold left with arithmetic mean of all given expression.
+///
+/// Sums all nodes and divides by the number of nodes (integer values are truncated).
+/// All nodes are recorded in a single node sub-expression labeled `(mean)`.
+///
+/// # Panics
+///
+/// Panics if the count of nodes cannot be divided by (see [`DivCount`](fold/trait.DivCount.html)).
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::mean;
+/// # extern crate uom;
+/// # use uom::si::{f32::*, length::meter};
+/// # fn main() {
+///     let a = Leaf::new().value(2.0).name("a").build();
+///     let b = Leaf::new().value(3.0).name("b").build();
+///     let c = Leaf::new().value(4.0).name("c").build();
+///     let res = mean!(a, b, c);
+///     assert_eq!(&3.0, res.quantity());
+///     assert_eq!(&"(mean)".to_string(), res.name());
+///
+///     let x = Leaf::new().value(Length::new::<meter>(1.0)).name("x").build();
+///     let y = Leaf::new().value(Length::new::<meter>(2.0)).name("y").build();
+///     let res = mean!(x, y);
+///     assert_eq!(&Length::new::<meter>(1.5), res.quantity());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! mean {
+    ( $head:expr, $( $tail:expr ),* ) => {
+        {
+            let head = $head;
+            ($crate::fold::FoldProxy { value: head.quantity().clone(), items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$( + $tail)*).mean().into_expr_named("mean")
+        }
+    };
+}
+
+///
+/// Unbiased sample variance of all given expression.
+///
+/// Divides the sum of squared deviations from the mean by `n - 1`,
+/// so at least two nodes are required (a single node does not compile).
+/// All nodes are recorded in a single node sub-expression labeled `(variance)`.
+///
+/// # Panics
+///
+/// Panics if the count of nodes cannot be divided by (see [`DivCount`](fold/trait.DivCount.html)).
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::variance;
+/// # extern crate uom;
+/// # use uom::si::{f32::*, length::meter, area::square_meter};
+/// # fn main() {
+///     let a = Leaf::new().value(2.0).name("a").build();
+///     let b = Leaf::new().value(4.0).name("b").build();
+///     let c = Leaf::new().value(6.0).name("c").build();
+///     let res = variance!(a, b, c);
+///     assert_eq!(&4.0, res.quantity());
+///     assert_eq!(&"(variance)".to_string(), res.name());
+///
+///     let x = Leaf::new().value(Length::new::<meter>(1.0)).name("x").build();
+///     let y = Leaf::new().value(Length::new::<meter>(3.0)).name("y").build();
+///     let res = variance!(x, y);
+///     assert_eq!(&Area::new::<square_meter>(2.0), res.quantity());
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # fn main() {
+///     let a = Leaf::new().value(2).name("a").build();
+///     let res = variance!(a,);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! variance {
+    ( $head:expr, $( $tail:expr ),+ ) => {
+        {
+            let head = $head;
+            ($crate::fold::SampleProxy { samples: $crate::__private::vec![head.quantity().clone()], items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$(.push($tail))*).variance().into_expr_named("variance")
+        }
+    };
+}
+
+///
+/// Sample standard deviation of all given expression.
+///
+/// Square root of [`variance!`](macro.variance.html), so the result has the same unit as nodes.
+/// All nodes are recorded in a single node sub-expression labeled `(stddev)`.
+///
+/// # Panics
+///
+/// Panics if the count of nodes cannot be divided by (see [`DivCount`](fold/trait.DivCount.html)).
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::stddev;
+/// # extern crate uom;
+/// # use uom::si::{f32::*, length::meter};
+/// # fn main() {
+///     let a = Leaf::new().value(2.0).name("a").build();
+///     let b = Leaf::new().value(4.0).name("b").build();
+///     let c = Leaf::new().value(6.0).name("c").build();
+///     let res = stddev!(a, b, c);
+///     assert_eq!(&2.0, res.quantity());
+///     assert_eq!(&"(stddev)".to_string(), res.name());
+///
+///     let x = Leaf::new().value(Length::new::<meter>(1.0)).name("x").build();
+///     let y = Leaf::new().value(Length::new::<meter>(1.0)).name("y").build();
+///     let res = stddev!(x, y);
+///     assert_eq!(&Length::new::<meter>(0.0), res.quantity());
+///     assert_eq!("m^1".to_string(), res.symbol());
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! stddev {
+    ( $head:expr, $( $tail:expr ),+ ) => {
+        {
+            let head = $head;
+            ($crate::fold::SampleProxy { samples: $crate::__private::vec![head.quantity().clone()], items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$(.push($tail))*).stddev().into_expr_named("stddev")
+        }
+    };
+}
+
+///
+/// Median of all given expression.
+///
+/// Returns the middle value, or the mean of the two middle values for an even number of nodes.
+/// All nodes are recorded in a single node sub-expression labeled `(median)`.
+///
+/// # Panics
+///
+/// Panics if `partial_cmp` of two values returns `None` (e.g. for NaN),
+/// or if the count of nodes cannot be divided by (see [`DivCount`](fold/trait.DivCount.html)).
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::median;
+/// # fn main() {
+///     let a = Leaf::new().value(4.0).name("a").build();
+///     let b = Leaf::new().value(1.0).name("b").build();
+///     let c = Leaf::new().value(3.0).name("c").build();
+///     let res = median!(a, b, c);
+///     assert_eq!(&3.0, res.quantity());
+///     assert_eq!(&"(median)".to_string(), res.name());
+///
+///     let a = Leaf::new().value(4.0).name("a").build();
+///     let b = Leaf::new().value(1.0).name("b").build();
+///     let c = Leaf::new().value(3.0).name("c").build();
+///     let d = Leaf::new().value(2.0).name("d").build();
+///     let res = median!(a, b, c, d);
+///     assert_eq!(&2.5, res.quantity());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! median {
+    ( $head:expr, $( $tail:expr ),* ) => {
+        {
+            let head = $head;
+            ($crate::fold::SampleProxy { samples: $crate::__private::vec![head.quantity().clone()], items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$(.push($tail))*).median().into_expr_named("median")
         }
     };
 }
+
+///
+/// Weighted sum of all given `(weight, expression)` pairs.
+///
+/// Multiplies each expression by its weight and sums the products.
+/// All weights and expressions are recorded in a single node sub-expression labeled `(weighted_sum)`.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::weighted_sum;
+/// # extern crate uom;
+/// # use uom::si::{f32::*, force::newton};
+/// # fn main() {
+///     let g = Leaf::new().value(1.35).name("gamma_g").build();
+///     let dead = Leaf::new().value(Force::new::<newton>(10.0)).name("G").build();
+///     let q = Leaf::new().value(1.5).name("gamma_q").build();
+///     let live = Leaf::new().value(Force::new::<newton>(4.0)).name("Q").build();
+///     let res = weighted_sum!((g, dead), (q, live));
+///     assert_eq!(&Force::new::<newton>(19.5), res.quantity());
+///     assert_eq!(&"(weighted_sum)".to_string(), res.name());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! weighted_sum {
+    ( ($head_weight:expr, $head:expr) $(, ($weight:expr, $tail:expr) )* ) => {
+        {
+            let head_weight = $head_weight;
+            let head = $head;
+            ($crate::fold::FoldProxy {
+                value: head_weight.quantity().clone() * head.quantity().clone(),
+                items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head_weight), $crate::node::Cherries::as_subexpr(&head)]
+            }$(.add_weighted($weight, $tail))*).into_expr_named("weighted_sum")
+        }
+    };
+}
+
+///
+/// Fold left with user-supplied combiner over all given expression.
+///
+/// Starts from `init` and applies `f(acc, quantity)` to each expression in order.
+/// All nodes are recorded in a single node sub-expression, same as `sum_all!`.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::fold_all;
+/// # fn main() {
+///     let a = Leaf::new().value(2).name("a").build();
+///     let b = Leaf::new().value(3).name("b").build();
+///     let c = Leaf::new().value(4).name("c").build();
+///     let res = fold_all!(0, |acc: i32, x: &i32| acc + x * x, a, b, c);
+///     assert_eq!(&29, res.quantity());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fold_all {
+    ( $init:expr, $f:expr, $( $item:expr ),+ ) => {
+        {
+            let mut f = $f;
+            ($crate::fold::FoldProxy { value: $init, items: $crate::__private::Vec::new() }$(.fold_with($item, &mut f))+).into_expr()
+        }
+    };
+}
+
+///
+/// Fold left with user-supplied combiner over runtime-sized collection.
+///
+/// Non-macro version of [`fold_all!`](../macro.fold_all.html).
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::fold::fold_cherries;
+///
+/// let items = (1..=4)
+///     .map(|i| Leaf::new().value(i).name(format!("x{}", i)).build())
+///     .collect::<Vec<_>>();
+/// let res = fold_cherries(items, 1, |acc, x| acc * x);
+/// assert_eq!(&24, res.quantity());
+/// assert_eq!(&"foldl".to_string(), res.name());
+/// ```
+pub fn fold_cherries<T, U, I, F>(iter: I, init: U, mut f: F) -> Cherry<U>
+where
+    T: 'static + Clone + Debug,
+    U: 'static + Clone + Debug,
+    I: IntoIterator<Item = Cherry<T>>,
+    F: FnMut(U, &T) -> U,
+{
+    iter.into_iter()
+        .fold(
+            FoldProxy {
+                value: init,
+                items: vec![],
+            },
+            |acc, item| acc.fold_with(item, &mut f),
+        )
+        .into_expr()
+}
+
+fn fold_iter<T, I, F>(iter: I, f: F) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug,
+    I: IntoIterator<Item = Cherry<T>>,
+    F: Fn(FoldProxy<T>, Cherry<T>) -> FoldProxy<T>,
+{
+    let mut iter = iter.into_iter();
+    iter.next().map(|head| {
+        iter.fold(
+            FoldProxy {
+                value: head.quantity().clone(),
+                items: vec![head.as_subexpr()],
+            },
+            f,
+        )
+        .into_expr()
+    })
+}
+
+///
+/// Fold left with addition over runtime-sized collection.
+///
+/// Iterator version of [`sum_all!`](../macro.sum_all.html).
+/// Returns `None` if `iter` is empty.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::fold::sum_iter;
+///
+/// let items = vec![
+///     Leaf::new().value(2).name("a").build(),
+///     Leaf::new().value(3).name("b").build(),
+///     Leaf::new().value(4).name("c").build(),
+/// ];
+/// let res = sum_iter(items).unwrap();
+/// assert_eq!(&9, res.quantity());
+/// assert!(sum_iter(Vec::<cherries::node::Cherry<i32>>::new()).is_none());
+/// ```
+pub fn sum_iter<T, I>(iter: I) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug + Add<Output = T>,
+    I: IntoIterator<Item = Cherry<T>>,
+{
+    fold_iter(iter, |acc, item| acc + item)
+}
+
+///
+/// Fold left with multiplication over runtime-sized collection.
+///
+/// Iterator version of [`prod_all!`](../macro.prod_all.html),
+/// restricted to values whose product has the same type (e.g. dimensionless).
+/// Returns `None` if `iter` is empty.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::fold::prod_iter;
+///
+/// let items = (1..=4).map(|i| Leaf::new().value(i).name(format!("x{}", i)).build());
+/// let res = prod_iter(items).unwrap();
+/// assert_eq!(&24, res.quantity());
+/// ```
+pub fn prod_iter<T, I>(iter: I) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug + Mul<Output = T>,
+    I: IntoIterator<Item = Cherry<T>>,
+{
+    fold_iter(iter, |acc, item| acc * item)
+}
+
+///
+/// Fold left with `max` over runtime-sized collection.
+///
+/// Iterator version of [`maximum!`](../macro.maximum.html); every candidate is recorded.
+/// Returns `None` if `iter` is empty.
+/// Panics if and only if `partial_cmp` returns `None`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::fold::max_of_iter;
+///
+/// let items = vec![3, 7, 5]
+///     .into_iter()
+///     .map(|v| Leaf::new().value(v).name(format!("case {}", v)).build());
+/// let res = max_of_iter(items).unwrap();
+/// assert_eq!(&7, res.quantity());
+/// assert!(res.to_json().contains("case 3"));
+/// ```
+pub fn max_of_iter<T, I>(iter: I) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug + PartialOrd,
+    I: IntoIterator<Item = Cherry<T>>,
+{
+    fold_iter(iter, FoldProxy::max)
+}
+
+///
+/// Fold left with `min` over runtime-sized collection.
+///
+/// Iterator version of [`minimum!`](../macro.minimum.html); every candidate is recorded.
+/// Returns `None` if `iter` is empty.
+/// Panics if and only if `partial_cmp` returns `None`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::fold::min_of_iter;
+///
+/// let items = vec![3, 7, 5]
+///     .into_iter()
+///     .map(|v| Leaf::new().value(v).name(format!("case {}", v)).build());
+/// let res = min_of_iter(items).unwrap();
+/// assert_eq!(&3, res.quantity());
+/// ```
+pub fn min_of_iter<T, I>(iter: I) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug + PartialOrd,
+    I: IntoIterator<Item = Cherry<T>>,
+{
+    fold_iter(iter, FoldProxy::min)
+}
+
+///
+/// Whether `predicate` holds for all given expression.
+///
+/// Produces `Cherry<bool>` labeled `(all)`, all nodes are recorded in a single node sub-expression.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::all;
+/// # fn main() {
+///     let a = Leaf::new().value(0.8).name("utilization a").build();
+///     let b = Leaf::new().value(0.95).name("utilization b").build();
+///     let c = Leaf::new().value(1.02).name("utilization c").build();
+///     let res = all!(|v: &f64| v < &1.0, a, b, c);
+///     assert_eq!(&false, res.quantity());
+///     assert_eq!(&"(all)".to_string(), res.name());
+///     assert!(res.to_json().starts_with("{\"label\":\"(all)\",\"value\":false,"));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! all {
+    ( $predicate:expr, $( $item:expr ),+ ) => {
+        {
+            let predicate = $predicate;
+            ($crate::fold::FoldProxy { value: true, items: $crate::__private::Vec::new() }$(
+                .fold_with($item, &mut |acc: bool, x| acc && predicate(x))
+            )+).into_expr_named("all")
+        }
+    };
+}
+
+///
+/// Whether `predicate` holds for any of given expression.
+///
+/// Produces `Cherry<bool>` labeled `(any)`, all nodes are recorded in a single node sub-expression.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::any;
+/// # fn main() {
+///     let a = Leaf::new().value(0.8).name("utilization a").build();
+///     let b = Leaf::new().value(1.02).name("utilization b").build();
+///     let res = any!(|v: &f64| v > &1.0, a, b);
+///     assert_eq!(&true, res.quantity());
+///     assert_eq!(&"(any)".to_string(), res.name());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! any {
+    ( $predicate:expr, $( $item:expr ),+ ) => {
+        {
+            let predicate = $predicate;
+            ($crate::fold::FoldProxy { value: false, items: $crate::__private::Vec::new() }$(
+                .fold_with($item, &mut |acc: bool, x| acc || predicate(x))
+            )+).into_expr_named("any")
+        }
+    };
+}
+
+///
+/// Number of given expression for which `predicate` holds.
+///
+/// Produces dimensionless `Cherry<usize>` labeled `(count_if)`,
+/// all nodes are recorded in a single node sub-expression.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::count_if;
+/// # fn main() {
+///     let a = Leaf::new().value(0.8).name("utilization a").build();
+///     let b = Leaf::new().value(1.02).name("utilization b").build();
+///     let c = Leaf::new().value(1.3).name("utilization c").build();
+///     let res = count_if!(|v: &f64| v > &1.0, a, b, c);
+///     assert_eq!(&2, res.quantity());
+///     assert_eq!(&"(count_if)".to_string(), res.name());
+///     assert_eq!("dimensionless".to_string(), res.symbol());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! count_if {
+    ( $predicate:expr, $( $item:expr ),+ ) => {
+        {
+            let predicate = $predicate;
+            ($crate::fold::FoldProxy { value: 0usize, items: $crate::__private::Vec::new() }$(
+                .fold_with($item, &mut |acc: usize, x| if predicate(x) { acc + 1 } else { acc })
+            )+).into_expr_named("count_if")
+        }
+    };
+}
+
+///
+/// Fold left with `max` all given expression, recording which node won.
+///
+/// Same as [`maximum!`](macro.maximum.html) except that the result is labeled
+/// `(argmax: <label of the maximum node>)` (the first one for ties).
+///
+/// # Panics
+///
+/// Panics if `partial_cmp` of two values returns `None` (e.g. for NaN).
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::argmax;
+/// # fn main() {
+///     let a = Leaf::new().value(2).name("LC1").build();
+///     let b = Leaf::new().value(5).name("LC2").build();
+///     let c = Leaf::new().value(3).name("LC3").build();
+///     let res = argmax!(a, b, c);
+///     assert_eq!(&5, res.quantity());
+///     assert_eq!(&"(argmax: LC2)".to_string(), res.name());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! argmax {
+    ( $head:expr, $( $tail:expr ),* ) => {
+        {
+            let head = $head;
+            ($crate::fold::ArgProxy { value: head.quantity().clone(), winner: $crate::node::Cherries::name(&head).into(), items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$(.max($tail))*).into_expr_named("argmax")
+        }
+    };
+}
+
+///
+/// Fold left with `min` all given expression, recording which node won.
+///
+/// Same as [`minimum!`](macro.minimum.html) except that the result is labeled
+/// `(argmin: <label of the minimum node>)` (the first one for ties).
+///
+/// # Panics
+///
+/// Panics if `partial_cmp` of two values returns `None` (e.g. for NaN).
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// # use cherries::node::{Cherries, Leaf};
+/// # use cherries::argmin;
+/// # fn main() {
+///     let a = Leaf::new().value(2).name("LC1").build();
+///     let b = Leaf::new().value(5).name("LC2").build();
+///     let c = Leaf::new().value(2).name("LC3").build();
+///     let res = argmin!(a, b, c);
+///     assert_eq!(&2, res.quantity());
+///     assert_eq!(&"(argmin: LC1)".to_string(), res.name());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! argmin {
+    ( $head:expr, $( $tail:expr ),* ) => {
+        {
+            let head = $head;
+            ($crate::fold::ArgProxy { value: head.quantity().clone(), winner: $crate::node::Cherries::name(&head).into(), items: $crate::__private::vec![$crate::node::Cherries::as_subexpr(&head)] }$(.min($tail))*).into_expr_named("argmin")
+        }
+    };
+}
+
+fn arg_of_iter<T, I>(iter: I, name: &str, ordering: core::cmp::Ordering) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug + PartialOrd,
+    I: IntoIterator<Item = Cherry<T>>,
+{
+    let mut iter = iter.into_iter();
+    iter.next().map(|head| {
+        iter.fold(
+            ArgProxy {
+                value: head.quantity().clone(),
+                winner: head.name().into(),
+                items: vec![head.as_subexpr()],
+            },
+            |acc, item| acc.select(item, ordering),
+        )
+        .into_expr_named(name)
+    })
+}
+
+///
+/// Iterator version of [`argmax!`](../macro.argmax.html).
+///
+/// Returns `None` if `iter` is empty.
+///
+/// # Panics
+///
+/// Panics if `partial_cmp` of two values returns `None` (e.g. for NaN).
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::fold::argmax_of;
+///
+/// let items = vec![3, 7, 5]
+///     .into_iter()
+///     .map(|v| Leaf::new().value(v).name(format!("case {}", v)).build());
+/// let res = argmax_of(items).unwrap();
+/// assert_eq!(&7, res.quantity());
+/// assert_eq!(&"(argmax: case 7)".to_string(), res.name());
+/// ```
+pub fn argmax_of<T, I>(iter: I) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug + PartialOrd,
+    I: IntoIterator<Item = Cherry<T>>,
+{
+    arg_of_iter(iter, "argmax", core::cmp::Ordering::Greater)
+}
+
+///
+/// Iterator version of [`argmin!`](../macro.argmin.html).
+///
+/// Returns `None` if `iter` is empty.
+///
+/// # Panics
+///
+/// Panics if `partial_cmp` of two values returns `None` (e.g. for NaN).
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::fold::argmin_of;
+///
+/// let items = vec![3, 7, 5]
+///     .into_iter()
+///     .map(|v| Leaf::new().value(v).name(format!("case {}", v)).build());
+/// let res = argmin_of(items).unwrap();
+/// assert_eq!(&"(argmin: case 3)".to_string(), res.name());
+/// ```
+pub fn argmin_of<T, I>(iter: I) -> Option<Cherry<T>>
+where
+    T: 'static + Clone + Debug + PartialOrd,
+    I: IntoIterator<Item = Cherry<T>>,
+{
+    arg_of_iter(iter, "argmin", core::cmp::Ordering::Less)
+}
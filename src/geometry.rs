@@ -0,0 +1,438 @@
+use super::node::{Cherries, Cherry, Leaf, Node, Subexpr};
+use alloc::{vec, vec::Vec};
+use core::fmt::Debug;
+use core::ops::Sub;
+use uom::typenum::{P4, Z0};
+
+///
+/// Second moment of area (length^4), which uom does not provide.
+///
+pub type SecondMomentOfArea<V> =
+    uom::si::Quantity<uom::si::ISQ<P4, Z0, Z0, Z0, Z0, Z0, Z0>, uom::si::SI<V>, V>;
+
+///
+/// Point of two length expressions.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::geometry::Point2;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::meter};
+///
+/// let support = Point2::new(
+///     Leaf::new().value(Length::new::<meter>(0.0)).name("x_A").build(),
+///     Leaf::new().value(Length::new::<meter>(0.0)).name("y_A").build(),
+/// );
+/// let load = Point2::new(
+///     Leaf::new().value(Length::new::<meter>(3.0)).name("x_F").build(),
+///     Leaf::new().value(Length::new::<meter>(4.0)).name("y_F").build(),
+/// );
+/// let lever_arm = support.distance(&load);
+/// assert_eq!(lever_arm.quantity(), &Length::new::<meter>(5.0));
+/// assert_eq!(lever_arm.name(), &"(distance)".to_string());
+/// assert_eq!(support.vector_to(&load).x().quantity(), &Length::new::<meter>(3.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Point2<L: Clone + Debug> {
+    x: Cherry<L>,
+    y: Cherry<L>,
+}
+
+///
+/// Vector of two length expressions.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::geometry::Vector2;
+/// extern crate uom;
+/// use uom::si::{f64::*, angle::degree, area::square_meter, length::meter};
+///
+/// let u = Vector2::new(
+///     Leaf::new().value(Length::new::<meter>(2.0)).name("u_x").build(),
+///     Leaf::new().value(Length::new::<meter>(0.0)).name("u_y").build(),
+/// );
+/// let v = Vector2::new(
+///     Leaf::new().value(Length::new::<meter>(3.0)).name("v_x").build(),
+///     Leaf::new().value(Length::new::<meter>(3.0)).name("v_y").build(),
+/// );
+/// assert_eq!(u.dot(&v).quantity(), &Area::new::<square_meter>(6.0));
+/// assert_eq!(u.norm().quantity(), &Length::new::<meter>(2.0));
+/// assert!((u.angle_between(&v).quantity().get::<degree>() - 45.0).abs() < 1e-9);
+/// assert!((v.angle().quantity().get::<degree>() - 45.0).abs() < 1e-9);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Vector2<L: Clone + Debug> {
+    x: Cherry<L>,
+    y: Cherry<L>,
+}
+
+impl<L: Clone + Debug> Point2<L> {
+    ///
+    /// Makes point from its coordinates.
+    ///
+    pub fn new(x: Cherry<L>, y: Cherry<L>) -> Self {
+        Point2 { x, y }
+    }
+    ///
+    /// Returns reference of x coordinate.
+    ///
+    pub fn x(&self) -> &Cherry<L> {
+        &self.x
+    }
+    ///
+    /// Returns reference of y coordinate.
+    ///
+    pub fn y(&self) -> &Cherry<L> {
+        &self.y
+    }
+    #[cfg(feature = "std")]
+    fn subexpr(&self) -> Vec<Subexpr> {
+        vec![self.x.as_subexpr(), self.y.as_subexpr()]
+    }
+}
+
+impl<L> Point2<L>
+where
+    L: 'static + Clone + Debug + Sub<Output = L>,
+{
+    ///
+    /// Returns vector from `self` to `other` (each component recorded as `(sub)`).
+    ///
+    pub fn vector_to(&self, other: &Point2<L>) -> Vector2<L> {
+        Vector2 {
+            x: other.x.clone() - self.x.clone(),
+            y: other.y.clone() - self.y.clone(),
+        }
+    }
+}
+
+impl<L: Clone + Debug> Vector2<L> {
+    ///
+    /// Makes vector from its components.
+    ///
+    pub fn new(x: Cherry<L>, y: Cherry<L>) -> Self {
+        Vector2 { x, y }
+    }
+    ///
+    /// Returns reference of x component.
+    ///
+    pub fn x(&self) -> &Cherry<L> {
+        &self.x
+    }
+    ///
+    /// Returns reference of y component.
+    ///
+    pub fn y(&self) -> &Cherry<L> {
+        &self.y
+    }
+    fn subexpr(&self) -> Vec<Subexpr> {
+        vec![self.x.as_subexpr(), self.y.as_subexpr()]
+    }
+}
+
+macro_rules! impl_geometry {
+    ( $( $v:ident ),* ) => {
+        $(
+            impl Point2<uom::si::$v::Length> {
+                #[cfg(feature = "std")]
+                ///
+                /// Returns distance between `self` and `other` labeled `(distance)`.
+                ///
+                pub fn distance(&self, other: &Self) -> Cherry<uom::si::$v::Length> {
+                    let dx = *other.x.quantity() - *self.x.quantity();
+                    let dy = *other.y.quantity() - *self.y.quantity();
+                    let mut children = self.subexpr();
+                    children.extend(other.subexpr());
+                    Node::new()
+                        .name("(distance)")
+                        .op("distance")
+                        .value(dx.hypot(dy))
+                        .children(children)
+                        .build()
+                }
+            }
+
+            impl Vector2<uom::si::$v::Length> {
+                ///
+                /// Returns dot product of `self` and `other` labeled `(dot)`.
+                ///
+                pub fn dot(&self, other: &Self) -> Cherry<uom::si::$v::Area> {
+                    let mut children = self.subexpr();
+                    children.extend(other.subexpr());
+                    Node::new()
+                        .name("(dot)")
+                        .op("dot")
+                        .value(
+                            *self.x.quantity() * *other.x.quantity()
+                                + *self.y.quantity() * *other.y.quantity(),
+                        )
+                        .children(children)
+                        .build()
+                }
+                #[cfg(feature = "std")]
+                ///
+                /// Returns length of `self` labeled `(norm)`.
+                ///
+                pub fn norm(&self) -> Cherry<uom::si::$v::Length> {
+                    Node::new()
+                        .name("(norm)")
+                        .op("norm")
+                        .value(self.x.quantity().hypot(*self.y.quantity()))
+                        .children(self.subexpr())
+                        .build()
+                }
+                #[cfg(feature = "std")]
+                ///
+                /// Returns direction of `self` measured from x axis labeled `(angle)`.
+                ///
+                pub fn angle(&self) -> Cherry<uom::si::$v::Angle> {
+                    Node::new()
+                        .name("(angle)")
+                        .op("angle")
+                        .value(uom::si::$v::Angle::new::<uom::si::angle::radian>(
+                            self.y.quantity().value.atan2(self.x.quantity().value),
+                        ))
+                        .children(self.subexpr())
+                        .build()
+                }
+                #[cfg(feature = "std")]
+                ///
+                /// Returns angle from `self` to `other` (counterclockwise positive) labeled `(angle)`.
+                ///
+                pub fn angle_between(&self, other: &Self) -> Cherry<uom::si::$v::Angle> {
+                    let (x1, y1) = (self.x.quantity().value, self.y.quantity().value);
+                    let (x2, y2) = (other.x.quantity().value, other.y.quantity().value);
+                    let mut children = self.subexpr();
+                    children.extend(other.subexpr());
+                    Node::new()
+                        .name("(angle)")
+                        .op("angle")
+                        .value(uom::si::$v::Angle::new::<uom::si::angle::radian>(
+                            (x1 * y2 - y1 * x2).atan2(x1 * x2 + y1 * y2),
+                        ))
+                        .children(children)
+                        .build()
+                }
+            }
+        )*
+    };
+}
+
+impl_geometry!(f32, f64);
+
+fn constant<V: Clone + Debug>(name: &'static str, value: V) -> Cherry<V> {
+    Leaf::new().name(name).value(value).build()
+}
+
+///
+/// Solid rectangular section.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::geometry::{Rectangle, SecondMomentOfArea};
+/// extern crate uom;
+/// use uom::si::{f64::*, area::square_meter, length::meter};
+///
+/// let section = Rectangle::new(
+///     Leaf::new().value(Length::new::<meter>(0.2)).name("b").build(),
+///     Leaf::new().value(Length::new::<meter>(0.6)).name("h").build(),
+/// );
+/// assert!((section.area().quantity().get::<square_meter>() - 0.12).abs() < 1e-12);
+/// assert_eq!(section.centroid().quantity(), &Length::new::<meter>(0.3));
+/// let moment = section.second_moment();
+/// assert!((moment.quantity().value - 0.0036).abs() < 1e-12);
+/// assert_eq!(moment.name(), &"(second_moment)".to_string());
+/// assert_eq!(moment.symbol(), "m^4".to_string());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rectangle<L: Clone + Debug> {
+    width: Cherry<L>,
+    height: Cherry<L>,
+}
+
+impl<L: Clone + Debug> Rectangle<L> {
+    ///
+    /// Makes section from its width and height.
+    ///
+    pub fn new(width: Cherry<L>, height: Cherry<L>) -> Self {
+        Rectangle { width, height }
+    }
+}
+
+///
+/// Solid circular section.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::geometry::Circle;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::meter};
+///
+/// let section = Circle::new(Leaf::new().value(Length::new::<meter>(2.0)).name("d").build());
+/// assert!((section.area().quantity().value - std::f64::consts::PI).abs() < 1e-12);
+/// assert_eq!(section.centroid().quantity(), &Length::new::<meter>(1.0));
+/// assert!((section.second_moment().quantity().value - std::f64::consts::PI / 4.0).abs() < 1e-12);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Circle<L: Clone + Debug> {
+    diameter: Cherry<L>,
+}
+
+impl<L: Clone + Debug> Circle<L> {
+    ///
+    /// Makes section from its diameter.
+    ///
+    pub fn new(diameter: Cherry<L>) -> Self {
+        Circle { diameter }
+    }
+}
+
+///
+/// Doubly symmetric I-section.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::geometry::ISection;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::millimeter};
+///
+/// // IPE 300 (without root radius)
+/// let section = ISection::new(
+///     Leaf::new().value(Length::new::<millimeter>(300.0)).name("h").build(),
+///     Leaf::new().value(Length::new::<millimeter>(150.0)).name("b").build(),
+///     Leaf::new().value(Length::new::<millimeter>(10.7)).name("t_f").build(),
+///     Leaf::new().value(Length::new::<millimeter>(7.1)).name("t_w").build(),
+/// );
+/// assert!((section.area().quantity().value - 5.18806e-3).abs() < 1e-9);
+/// assert!((section.second_moment().quantity().value - 7.99899e-5).abs() < 1e-9);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ISection<L: Clone + Debug> {
+    height: Cherry<L>,
+    width: Cherry<L>,
+    flange_thickness: Cherry<L>,
+    web_thickness: Cherry<L>,
+}
+
+impl<L: Clone + Debug> ISection<L> {
+    ///
+    /// Makes section from overall height, flange width, flange thickness and web thickness.
+    ///
+    pub fn new(
+        height: Cherry<L>,
+        width: Cherry<L>,
+        flange_thickness: Cherry<L>,
+        web_thickness: Cherry<L>,
+    ) -> Self {
+        ISection {
+            height,
+            width,
+            flange_thickness,
+            web_thickness,
+        }
+    }
+}
+
+macro_rules! impl_sections {
+    ( $( $v:ident ),* ) => {
+        $(
+            impl Rectangle<uom::si::$v::Length> {
+                ///
+                /// Returns area `b h` labeled `(area)`.
+                ///
+                pub fn area(&self) -> Cherry<uom::si::$v::Area> {
+                    (self.width.clone() * self.height.clone()).labeled("(area)")
+                }
+                ///
+                /// Returns distance of centroid from the bottom fiber `h / 2` labeled `(centroid)`.
+                ///
+                pub fn centroid(&self) -> Cherry<uom::si::$v::Length> {
+                    (self.height.clone() / constant("2", 2.0)).labeled("(centroid)")
+                }
+                ///
+                /// Returns second moment of area about the centroidal axis `b h^3 / 12`
+                /// labeled `(second_moment)`.
+                ///
+                pub fn second_moment(&self) -> Cherry<SecondMomentOfArea<$v>> {
+                    let (b, h) = (self.width.clone(), self.height.clone());
+                    (b * h.clone() * h.clone() * h / constant("12", 12.0)).labeled("(second_moment)")
+                }
+            }
+
+            impl Circle<uom::si::$v::Length> {
+                ///
+                /// Returns area `π d^2 / 4` labeled `(area)`.
+                ///
+                pub fn area(&self) -> Cherry<uom::si::$v::Area> {
+                    let d = self.diameter.clone();
+                    (constant("π", core::$v::consts::PI) * d.clone() * d / constant("4", 4.0))
+                        .labeled("(area)")
+                }
+                ///
+                /// Returns distance of centroid from the bottom fiber `d / 2` labeled `(centroid)`.
+                ///
+                pub fn centroid(&self) -> Cherry<uom::si::$v::Length> {
+                    (self.diameter.clone() / constant("2", 2.0)).labeled("(centroid)")
+                }
+                ///
+                /// Returns second moment of area about the centroidal axis `π d^4 / 64`
+                /// labeled `(second_moment)`.
+                ///
+                pub fn second_moment(&self) -> Cherry<SecondMomentOfArea<$v>> {
+                    let d = self.diameter.clone();
+                    (constant("π", core::$v::consts::PI) * d.clone() * d.clone() * d.clone() * d
+                        / constant("64", 64.0))
+                        .labeled("(second_moment)")
+                }
+            }
+
+            impl ISection<uom::si::$v::Length> {
+                fn web_height(&self) -> Cherry<uom::si::$v::Length> {
+                    self.height.clone() - constant("2", 2.0) * self.flange_thickness.clone()
+                }
+                ///
+                /// Returns area `2 b t_f + (h - 2 t_f) t_w` labeled `(area)`.
+                ///
+                pub fn area(&self) -> Cherry<uom::si::$v::Area> {
+                    (constant("2", 2.0) * self.width.clone() * self.flange_thickness.clone()
+                        + self.web_height() * self.web_thickness.clone())
+                        .labeled("(area)")
+                }
+                ///
+                /// Returns distance of centroid from the bottom fiber `h / 2` labeled `(centroid)`.
+                ///
+                pub fn centroid(&self) -> Cherry<uom::si::$v::Length> {
+                    (self.height.clone() / constant("2", 2.0)).labeled("(centroid)")
+                }
+                ///
+                /// Returns second moment of area about the major axis
+                /// `(b h^3 - (b - t_w) (h - 2 t_f)^3) / 12` labeled `(second_moment)`.
+                ///
+                pub fn second_moment(&self) -> Cherry<SecondMomentOfArea<$v>> {
+                    let (b, h) = (self.width.clone(), self.height.clone());
+                    let outer = b.clone() * h.clone() * h.clone() * h;
+                    let hw = self.web_height();
+                    let inner = (b - self.web_thickness.clone()) * hw.clone() * hw.clone() * hw;
+                    ((outer - inner) / constant("12", 12.0)).labeled("(second_moment)")
+                }
+            }
+        )*
+    };
+}
+
+impl_sections!(f32, f64);
@@ -0,0 +1,221 @@
+use super::export::{Tree, Value};
+use super::validate::Finding;
+use alloc::{string::String, vec, vec::Vec};
+
+///
+/// GraphQL schema (SDL) of the trees resolved by `Query` and `Node` (requires feature `graphql`).
+///
+/// Each field is resolved by the method of the same name (`Finding` by `validate::Finding`),
+/// so a GraphQL server mounts the schema by dispatching field names to them.
+/// `node(path:)` takes indices of subexpressions from the root (`[]` for the root itself),
+/// `find(label:)` returns every node labeled `label` (parents before their subexpressions).
+/// `value` is `null` for non-numeric and non-finite values, which are written in `text`.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// extern crate serde_json;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::export::Tree;
+/// use cherries::graphql::{Node, Query, SCHEMA};
+/// use serde_json::{json, Value};
+///
+/// // Resolves scalar field `field` of type `Node`, as a server mounting `SCHEMA` would.
+/// fn resolve(node: &Node<'_>, field: &str) -> Option<Value> {
+///     Some(match field {
+///         "label" => json!(node.label()),
+///         "value" => json!(node.value()),
+///         "text" => json!(node.text()),
+///         "unit" => json!(node.unit()),
+///         "op" => json!(node.op()),
+///         "comment" => json!(node.comment()),
+///         "collapsed" => json!(node.collapsed()),
+///         _ => return None,
+///     })
+/// }
+///
+/// assert!(SCHEMA.contains("type Node {"));
+/// let b = Leaf::new().value(0.5).name("b").build();
+/// let h = Leaf::new().value(0.25).name("h").build();
+/// let tree = Tree::from_json(&(b * h).labeled("A").to_json()).unwrap();
+/// let root = Query::new(&tree).root();
+/// assert_eq!(resolve(&root, "op"), Some(json!("mul")));
+/// let h = root.child("h").unwrap();
+/// assert_eq!(resolve(&h, "value"), Some(json!(0.25)));
+/// assert_eq!(resolve(&h, "comment"), Some(Value::Null));
+/// ```
+pub const SCHEMA: &str = "type Query {
+  root: Node!
+  node(path: [Int!]!): Node
+  find(label: String!): [Node!]!
+}
+
+type Node {
+  label: String!
+  value: Float
+  text: String!
+  unit: String!
+  op: String
+  comment: String
+  checks: [Finding!]!
+  collapsed: Int
+  children: [Node!]!
+  child(label: String!): Node
+}
+
+type Finding {
+  severity: String!
+  msg: String!
+}
+";
+
+///
+/// Resolvers of type `Query` of `SCHEMA` over a tree (requires feature `graphql`).
+///
+/// Resolvers are plain functions without async runtime, to be wrapped by any GraphQL server.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::export::Tree;
+/// use cherries::graphql::Query;
+///
+/// let x = Leaf::new().value(2).name("x").build();
+/// let y = Leaf::new().value(3).name("y").build();
+/// let tree = Tree::from_json(&(x.clone() * y + x).labeled("z").to_json()).unwrap();
+/// let query = Query::new(&tree);
+/// assert_eq!(query.root().label(), "z");
+/// assert_eq!(query.node(&[0, 1]).map(|node| node.label()), Some("y"));
+/// assert!(query.node(&[2]).is_none());
+/// assert_eq!(query.find("x").len(), 2);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Query<'a> {
+    tree: &'a Tree,
+}
+
+impl<'a> Query<'a> {
+    ///
+    /// Makes queries of `tree`.
+    ///
+    pub fn new(tree: &'a Tree) -> Self {
+        Query { tree }
+    }
+    ///
+    /// Returns the root node.
+    ///
+    pub fn root(&self) -> Node<'a> {
+        Node { tree: self.tree }
+    }
+    ///
+    /// Returns the node at `path` (indices of subexpressions from the root), if any.
+    ///
+    pub fn node(&self, path: &[usize]) -> Option<Node<'a>> {
+        let mut tree = self.tree;
+        for &i in path {
+            tree = tree.subexpr.get(i)?;
+        }
+        Some(Node { tree })
+    }
+    ///
+    /// Returns every node labeled `label`, parents before their subexpressions.
+    ///
+    pub fn find(&self, label: &str) -> Vec<Node<'a>> {
+        let (mut found, mut stack) = (Vec::new(), vec![self.tree]);
+        while let Some(tree) = stack.pop() {
+            if tree.label == label {
+                found.push(Node { tree });
+            }
+            stack.extend(tree.subexpr.iter().rev());
+        }
+        found
+    }
+}
+
+///
+/// Resolvers of type `Node` of `SCHEMA` (requires feature `graphql`).
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Node<'a> {
+    tree: &'a Tree,
+}
+
+impl<'a> Node<'a> {
+    ///
+    /// Returns the tree of the node.
+    ///
+    pub fn tree(&self) -> &'a Tree {
+        self.tree
+    }
+    ///
+    /// Returns label of the node.
+    ///
+    pub fn label(&self) -> &'a str {
+        &self.tree.label
+    }
+    ///
+    /// Returns numeric value (`None` for other and non-finite values).
+    ///
+    pub fn value(&self) -> Option<f32> {
+        match self.tree.value {
+            Value::Number(value) if value.is_finite() => Some(value),
+            _ => None,
+        }
+    }
+    ///
+    /// Returns value as written in json (texts without quotes).
+    ///
+    pub fn text(&self) -> String {
+        match &self.tree.value {
+            Value::Text(text) => text.clone(),
+            value => value.to_json(),
+        }
+    }
+    ///
+    /// Returns unit as written in json (e.g. `m^1`).
+    ///
+    pub fn unit(&self) -> &'a str {
+        &self.tree.unit
+    }
+    ///
+    /// Returns name of the operation (`None` for leaves).
+    ///
+    pub fn op(&self) -> Option<&'a str> {
+        self.tree.op.as_ref().map(|op| op.as_str())
+    }
+    ///
+    /// Returns comment of the node, if any.
+    ///
+    pub fn comment(&self) -> Option<&'a str> {
+        self.tree.comment.as_ref().map(|comment| &comment[..])
+    }
+    ///
+    /// Returns warnings and notes of validation checks (type `Finding` of `SCHEMA`, see `validate::Finding`).
+    ///
+    pub fn checks(&self) -> &'a [Finding] {
+        &self.tree.findings
+    }
+    ///
+    /// Returns count of subexpressions left out (see `Tree::collapsed`).
+    ///
+    pub fn collapsed(&self) -> Option<usize> {
+        self.tree.collapsed
+    }
+    ///
+    /// Returns subexpressions in order (empty for leaves and collapsed nodes).
+    ///
+    pub fn children(&self) -> Vec<Node<'a>> {
+        self.tree.subexpr.iter().map(|tree| Node { tree }).collect()
+    }
+    ///
+    /// Returns the first subexpression labeled `label`, if any.
+    ///
+    pub fn child(&self, label: &str) -> Option<Node<'a>> {
+        self.tree
+            .subexpr
+            .iter()
+            .find(|tree| tree.label == label)
+            .map(|tree| Node { tree })
+    }
+}
@@ -0,0 +1,239 @@
+use super::fold::DivCount;
+use alloc::{borrow::ToOwned, format, string::String};
+use core::cmp::Ordering;
+use core::fmt::{self, Debug};
+use core::ops::{Add, Div, Mul, Sub};
+
+///
+/// Closed interval `[lower, upper]` of values of type `T`, for worst-case calculations.
+///
+/// Sums, differences, products and quotients contain every result of operands in the operand
+/// intervals, so trees of `Cherry<Interval<T>>` record the range of every step and both bounds
+/// are written as `"value":[lower,upper]` in json.
+/// `sum_all!`, `prod_all!` and `mean!` fold intervals as usual.
+/// Intervals compare only when one lies entirely below the other (or they are equal),
+/// so comparisons of overlapping intervals (and `maximum!` or `minimum!` of them) are undecided.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::export::{Tree, Value};
+/// use cherries::interval::Interval;
+/// use cherries::validate::Validate;
+/// use std::convert::TryFrom;
+/// extern crate uom;
+/// use uom::si::{f64::*, force::kilonewton, length::meter};
+///
+/// # fn main() {
+/// let load = Leaf::new()
+///     .value(Interval::new(Force::new::<kilonewton>(8.0), Force::new::<kilonewton>(12.0)))
+///     .name("P")
+///     .build();
+/// let arm = Leaf::new()
+///     .value(Interval::new(Length::new::<meter>(1.9), Length::new::<meter>(2.1)))
+///     .name("a")
+///     .build();
+/// let moment = (load * arm).labeled("M");
+/// let tree = Tree::try_from(&moment).unwrap();
+/// assert_eq!(tree.value, Value::Interval(15200.0, 25200.0));
+/// assert_eq!(tree.unit, "m^2 kg^1 s^-2".to_string());
+/// assert!(moment.to_json().starts_with("{\"label\":\"M\",\"value\":[15200,25200],\"unit\":\"m^2 kg^1 s^-2\""));
+///
+/// let worst = moment
+///     .validate("must not exceed 24 kNm", |m| m.upper().value <= 24000.0)
+///     .into_result();
+/// assert!(worst.is_err());
+///
+/// let a = Leaf::new().value(Interval::new(1.0, 2.0)).name("a").build();
+/// let b = Leaf::new().value(Interval::new(-1.0, 3.0)).name("b").build();
+/// assert_eq!((a.clone() - b.clone()).quantity(), &Interval::new(-2.0, 3.0));
+/// assert_eq!(mean!(a.clone(), b.clone()).quantity(), &Interval::new(0.0, 2.5));
+/// assert_eq!(a.partial_cmp(&b), None);
+/// assert!(a < Leaf::new().value(Interval::new(2.5, 4.0)).name("c").build());
+/// # }
+/// ```
+#[derive(Clone, Copy, PartialEq)]
+pub struct Interval<T> {
+    lower: T,
+    upper: T,
+}
+
+impl<T: PartialOrd> Interval<T> {
+    ///
+    /// Makes interval between `lower` and `upper` (swapped if given in reverse).
+    ///
+    pub fn new(lower: T, upper: T) -> Self {
+        if upper < lower {
+            Interval {
+                lower: upper,
+                upper: lower,
+            }
+        } else {
+            Interval { lower, upper }
+        }
+    }
+    ///
+    /// Returns whether `value` is in the interval.
+    ///
+    pub fn contains(&self, value: &T) -> bool {
+        &self.lower <= value && value <= &self.upper
+    }
+}
+
+impl<T: Clone> Interval<T> {
+    ///
+    /// Makes interval of the single value `value` (e.g. for exact constants).
+    ///
+    pub fn point(value: T) -> Self {
+        Interval {
+            lower: value.clone(),
+            upper: value,
+        }
+    }
+}
+
+impl<T> Interval<T> {
+    ///
+    /// Returns lower bound.
+    ///
+    pub fn lower(&self) -> &T {
+        &self.lower
+    }
+    ///
+    /// Returns upper bound.
+    ///
+    pub fn upper(&self) -> &T {
+        &self.upper
+    }
+}
+
+impl<T: Clone + Sub> Interval<T> {
+    ///
+    /// Returns `upper - lower`.
+    ///
+    pub fn width(&self) -> <T as Sub>::Output {
+        self.upper.clone() - self.lower.clone()
+    }
+}
+
+// Written as `[{lower},{upper}] {symbol}`, so json records both bounds.
+impl<T: Debug> Debug for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lower, upper) = (format!("{:?}", self.lower), format!("{:?}", self.upper));
+        let value = |formats: &str| match formats.find(' ') {
+            Some(space) => (formats[..space].to_owned(), formats[space..].to_owned()),
+            None => (formats.to_owned(), String::new()),
+        };
+        let ((lower, symbol), (upper, _)) = (value(&lower), value(&upper));
+        write!(f, "[{},{}]{}", lower, upper, symbol)
+    }
+}
+
+// Ordered only if the intervals do not overlap (or are equal).
+impl<T: PartialOrd> PartialOrd for Interval<T> {
+    fn partial_cmp(&self, other: &Interval<T>) -> Option<Ordering> {
+        if self.lower == other.lower && self.upper == other.upper {
+            Some(Ordering::Equal)
+        } else if self.upper < other.lower {
+            Some(Ordering::Less)
+        } else if self.lower > other.upper {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
+// Smallest interval containing all of `values`.
+fn hull<T: PartialOrd + Clone>(values: [T; 4]) -> Interval<T> {
+    let mut lower = values[0].clone();
+    let mut upper = values[0].clone();
+    for value in values.iter().skip(1) {
+        if value < &lower {
+            lower = value.clone();
+        }
+        if value > &upper {
+            upper = value.clone();
+        }
+    }
+    Interval { lower, upper }
+}
+
+impl<T: Add<U>, U> Add<Interval<U>> for Interval<T> {
+    type Output = Interval<<T as Add<U>>::Output>;
+
+    fn add(self, other: Interval<U>) -> Self::Output {
+        Interval {
+            lower: self.lower + other.lower,
+            upper: self.upper + other.upper,
+        }
+    }
+}
+
+impl<T: Sub<U>, U> Sub<Interval<U>> for Interval<T> {
+    type Output = Interval<<T as Sub<U>>::Output>;
+
+    fn sub(self, other: Interval<U>) -> Self::Output {
+        Interval {
+            lower: self.lower - other.upper,
+            upper: self.upper - other.lower,
+        }
+    }
+}
+
+impl<T, U> Mul<Interval<U>> for Interval<T>
+where
+    T: Clone + Mul<U>,
+    U: Clone,
+    <T as Mul<U>>::Output: Clone + PartialOrd,
+{
+    type Output = Interval<<T as Mul<U>>::Output>;
+
+    fn mul(self, other: Interval<U>) -> Self::Output {
+        let Interval { lower: a, upper: b } = self;
+        let Interval { lower: c, upper: d } = other;
+        hull([
+            a.clone() * c.clone(),
+            a * d.clone(),
+            b.clone() * c,
+            b * d,
+        ])
+    }
+}
+
+// Division by an interval containing zero is division by zero
+// (both bounds are `NaN` for floats and quantities).
+impl<T, U> Div<Interval<U>> for Interval<T>
+where
+    T: Clone + Default + Div<U>,
+    U: Clone + Default + PartialOrd,
+    <T as Div<U>>::Output: Clone + PartialOrd,
+{
+    type Output = Interval<<T as Div<U>>::Output>;
+
+    fn div(self, other: Interval<U>) -> Self::Output {
+        let Interval { lower: a, upper: b } = self;
+        let Interval { lower: c, upper: d } = other;
+        if c <= U::default() && U::default() <= d {
+            return Interval::point(T::default() / U::default());
+        }
+        hull([
+            a.clone() / c.clone(),
+            a / d.clone(),
+            b.clone() / c,
+            b / d,
+        ])
+    }
+}
+
+impl<T: DivCount> DivCount for Interval<T> {
+    type Output = Interval<<T as DivCount>::Output>;
+
+    fn div_count(self, count: usize) -> Self::Output {
+        Interval {
+            lower: self.lower.div_count(count),
+            upper: self.upper.div_count(count),
+        }
+    }
+}
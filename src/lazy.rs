@@ -0,0 +1,258 @@
+use super::node::{Cherry, Label, Leaf};
+use alloc::collections::BTreeMap;
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use core::any::{Any, TypeId};
+use core::fmt::{self, Debug};
+use core::ops::{Add, Div, Mul, Sub};
+
+///
+/// Environment mapping names to quantities, against which symbolic leaves
+/// of `LazyCherry` expressions are resolved.
+///
+#[derive(Default)]
+pub struct Env {
+    values: BTreeMap<String, Box<dyn Any>>,
+}
+
+impl Env {
+    ///
+    /// Makes empty environment.
+    ///
+    pub fn new() -> Self {
+        Env::default()
+    }
+    ///
+    /// Returns environment which also binds `value` to `name`.
+    ///
+    pub fn bind<S: Into<String>, T: 'static>(mut self, name: S, value: T) -> Self {
+        self.set(name, value);
+        self
+    }
+    ///
+    /// Binds `value` to `name`, replacing the value bound before.
+    ///
+    pub fn set<S: Into<String>, T: 'static>(&mut self, name: S, value: T) -> &mut Self {
+        self.values.insert(name.into(), Box::new(value));
+        self
+    }
+    ///
+    /// Returns `true` if a value is bound to `name`.
+    ///
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+    ///
+    /// Returns value bound to `name`, if it is a `T`.
+    ///
+    pub fn get<T: 'static>(&self, name: &str) -> Option<&T> {
+        self.values.get(name)?.downcast_ref()
+    }
+}
+
+impl Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Env")
+            .field("names", &self.values.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+///
+/// Deferred expression, evaluated into `Cherry<T>` on `evaluate`.
+///
+/// Expressions are built from named leaves with the same operators as `Cherry<T>`,
+/// and evaluated any number of times against different `Env`s binding their leaves.
+/// Evaluated trees are the same as trees computed eagerly.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::lazy::{Env, LazyCherry};
+///
+/// let area = (LazyCherry::<i32>::leaf("w") * LazyCherry::<i32>::leaf("h")).labeled("area");
+///
+/// let small = area.evaluate(&Env::new().bind("w", 2).bind("h", 3)).unwrap();
+/// let large = area.evaluate(&Env::new().bind("w", 20).bind("h", 30)).unwrap();
+/// assert_eq!(small.quantity(), &6);
+/// assert_eq!(large.quantity(), &600);
+/// let eager = (Leaf::new().value(2).name("w").build() * Leaf::new().value(3).name("h").build())
+///     .labeled("area");
+/// assert_eq!(small.to_json(), eager.to_json());
+///
+/// // unbound leaf, or bound to a value of another type
+/// assert!(area.evaluate(&Env::new().bind("w", 2)).is_none());
+/// assert!(area.evaluate(&Env::new().bind("w", 2).bind("h", 3.0)).is_none());
+/// ```
+pub struct LazyCherry<T: Clone + Debug> {
+    symbols: Arc<BTreeMap<String, TypeId>>,
+    eval: Arc<dyn Fn(&Env) -> Option<Cherry<T>> + Send + Sync>,
+}
+
+impl<T: Clone + Debug> Clone for LazyCherry<T> {
+    fn clone(&self) -> Self {
+        LazyCherry {
+            symbols: self.symbols.clone(),
+            eval: self.eval.clone(),
+        }
+    }
+}
+
+impl<T: Clone + Debug> Debug for LazyCherry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyCherry")
+            .field("symbols", &self.symbols)
+            .finish()
+    }
+}
+
+///
+/// Makes symbolic leaf named `name`, resolved against an `Env` on evaluation
+/// (same as `LazyCherry::leaf`, which also takes names made at runtime).
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Cherries;
+/// use cherries::lazy::{symbol, Env, LazyCherry};
+/// extern crate uom;
+/// use uom::si::{f64::*, area::square_meter, force::kilonewton, length::meter, pressure::kilopascal};
+///
+/// let load = symbol::<Force>("P");
+/// let area = symbol::<Length>("b") * symbol::<Length>("d");
+/// let stress: LazyCherry<Pressure> = (load / area).labeled("sigma");
+/// assert_eq!(stress.symbols(), vec!["P", "b", "d"]);
+///
+/// let cases = (1..=3)
+///     .map(|i| {
+///         Env::new()
+///             .bind("P", Force::new::<kilonewton>(10.0 * f64::from(i)))
+///             .bind("b", Length::new::<meter>(0.5))
+///             .bind("d", Length::new::<meter>(0.4))
+///     })
+///     .collect::<Vec<_>>();
+/// let results = stress.evaluate_each(&cases);
+/// assert!((results[2].as_ref().unwrap().quantity().get::<kilopascal>() - 150.0).abs() < 1e-9);
+///
+/// let mut partial = Env::new();
+/// partial.set("P", Force::new::<kilonewton>(10.0)).set("b", Area::new::<square_meter>(0.2));
+/// assert_eq!(stress.unbound(&partial), vec!["b", "d"]);
+/// ```
+pub fn symbol<T: 'static + Clone + Debug>(name: &'static str) -> LazyCherry<T> {
+    LazyCherry::leaf(name)
+}
+
+impl<T: 'static + Clone + Debug> LazyCherry<T> {
+    fn new<F>(symbols: Arc<BTreeMap<String, TypeId>>, eval: F) -> Self
+    where
+        F: 'static + Fn(&Env) -> Option<Cherry<T>> + Send + Sync,
+    {
+        LazyCherry {
+            symbols,
+            eval: Arc::new(eval),
+        }
+    }
+    ///
+    /// Makes leaf named `name`, whose value is bound on evaluation.
+    ///
+    pub fn leaf<S: Into<Label>>(name: S) -> Self {
+        let name = name.into();
+        let mut symbols = BTreeMap::new();
+        symbols.insert(String::from(name.as_ref()), TypeId::of::<T>());
+        let symbols = Arc::new(symbols);
+        LazyCherry::new(symbols, move |env| {
+            let value = env.get::<T>(&name)?.clone();
+            Some(Leaf::new().name(name.clone()).value(value).build())
+        })
+    }
+    ///
+    /// Makes expression which always evaluates to `node`.
+    ///
+    pub fn constant(node: Cherry<T>) -> Self
+    where
+        T: Send + Sync,
+    {
+        LazyCherry::new(Arc::default(), move |_| Some(node.clone()))
+    }
+    ///
+    /// Returns expression whose result is labeled `name`.
+    ///
+    pub fn labeled<S: Into<Label>>(self, name: S) -> Self {
+        let name = name.into();
+        let symbols = self.symbols.clone();
+        LazyCherry::new(symbols, move |env| Some(self.evaluate(env)?.labeled(name.clone())))
+    }
+    ///
+    /// Returns expression whose result is mapped by `f` like `Cherry::map`.
+    ///
+    pub fn map<U, F>(self, f: F) -> LazyCherry<U>
+    where
+        U: 'static + Clone + Debug,
+        F: 'static + Fn(&T) -> U + Send + Sync,
+    {
+        let symbols = self.symbols.clone();
+        LazyCherry::new(symbols, move |env| Some(self.evaluate(env)?.map(&f)))
+    }
+    ///
+    /// Returns names of the leaves in the expression, in order.
+    ///
+    pub fn symbols(&self) -> Vec<&str> {
+        self.symbols.keys().map(String::as_str).collect()
+    }
+    ///
+    /// Returns names of the leaves not bound to a value of their type in `env`, in order.
+    ///
+    pub fn unbound(&self, env: &Env) -> Vec<&str> {
+        self.symbols
+            .iter()
+            .filter(|(name, &id)| {
+                env.values
+                    .get(*name)
+                    .map_or(true, |value| (**value).type_id() != id)
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+    ///
+    /// Evaluates expression with values of its leaves in `env`.
+    ///
+    /// Returns `None` if a leaf is not bound or bound to a value of another type.
+    ///
+    pub fn evaluate(&self, env: &Env) -> Option<Cherry<T>> {
+        (self.eval)(env)
+    }
+    ///
+    /// Evaluates expression against each of `envs`.
+    ///
+    pub fn evaluate_each<'a, I>(&self, envs: I) -> Vec<Option<Cherry<T>>>
+    where
+        I: IntoIterator<Item = &'a Env>,
+    {
+        envs.into_iter().map(|env| self.evaluate(env)).collect()
+    }
+}
+
+macro_rules! impl_lazy_op {
+    ( $( $Op:ident :: $op:ident ),* ) => {
+        $(
+            impl<T, U> $Op<LazyCherry<U>> for LazyCherry<T>
+            where
+                T: 'static + Clone + Debug + $Op<U>,
+                U: 'static + Clone + Debug,
+                <T as $Op<U>>::Output: 'static + Clone + Debug,
+            {
+                type Output = LazyCherry<<T as $Op<U>>::Output>;
+
+                fn $op(self, other: LazyCherry<U>) -> Self::Output {
+                    let mut symbols = (*other.symbols).clone();
+                    symbols.extend(self.symbols.iter().map(|(name, &id)| (name.clone(), id)));
+                    LazyCherry::new(Arc::new(symbols), move |env| {
+                        Some($Op::$op(self.evaluate(env)?, other.evaluate(env)?))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_lazy_op!(Add::add, Sub::sub, Mul::mul, Div::div);
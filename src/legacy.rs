@@ -0,0 +1,103 @@
+use super::export::{Tree, Value};
+use super::node::join;
+use alloc::{format, string::String, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+///
+/// Node in the `previous`-string format.
+///
+/// This is the shape `Serialize for Cherry<T>` has always written:
+/// `label`, `value` and `previous` (subexpressions as a comma separated json string, or `null`).
+/// Unit is not part of the format, so it must be given when converting a record into a `Tree`.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// extern crate serde_json;
+/// use cherries::node::Leaf;
+/// use cherries::legacy::Record;
+///
+/// let x = Leaf::new().value(2.0).name("x").build();
+/// let y = Leaf::new().value(3.0).name("y").build();
+/// let archived = serde_json::to_string(&(x + y).labeled("z")).unwrap();
+///
+/// let record = Record::from_json(&archived).unwrap();
+/// let tree = record.clone().into_tree("dimensionless").unwrap();
+/// assert_eq!(tree.label, "z".to_string());
+/// assert_eq!(tree.subexpr[1].label, "y".to_string());
+/// let emitted = Record::from_tree(&tree).unwrap();
+/// assert_eq!(emitted.into_tree("dimensionless").unwrap(), tree);
+/// assert_eq!(Record::from_json(&record.to_json().unwrap()).unwrap(), record);
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub label: String,
+    pub value: Value,
+    pub previous: Option<String>,
+}
+
+impl Record {
+    ///
+    /// Parses record from a json string in the `previous`-string format.
+    ///
+    pub fn from_json(json: &str) -> serde_json::Result<Record> {
+        serde_json::from_str(json)
+    }
+    ///
+    /// Returns record as json string in the `previous`-string format.
+    ///
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+    ///
+    /// Makes record from `tree` (unit of the root is dropped).
+    ///
+    pub fn from_tree(tree: &Tree) -> serde_json::Result<Record> {
+        let previous = if tree.subexpr.is_empty() {
+            None
+        } else {
+            let subexpr = tree
+                .subexpr
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<serde_json::Result<Vec<_>>>()?;
+            Some(join(&subexpr, ","))
+        };
+        Ok(Record {
+            label: tree.label.clone(),
+            value: tree.value.clone(),
+            previous,
+        })
+    }
+    ///
+    /// Converts record into a `Tree` whose root has `unit`.
+    ///
+    pub fn into_tree<S: Into<String>>(self, unit: S) -> serde_json::Result<Tree> {
+        let subexpr = match self.previous {
+            Some(previous) => serde_json::from_str(&format!("[{}]", previous))?,
+            None => vec![],
+        };
+        Ok(Tree {
+            label: self.label,
+            value: self.value,
+            uncertainty: None,
+            unit: unit.into(),
+            dimensions: None,
+            op: None,
+            origin: None,
+            comment: None,
+            description: None,
+            sig_figs: None,
+            meta: Default::default(),
+            location: None,
+            timestamp: None,
+            id: None,
+            references: vec![],
+            detail: false,
+            attachments: vec![],
+            findings: vec![],
+            collapsed: None,
+            subexpr,
+        })
+    }
+}
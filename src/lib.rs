@@ -40,18 +40,131 @@
 //! //  }
 //! ```
 //!
+//! ## Untrusted input
+//!
+//! Parsing and serialization never panic on untrusted input:
+//! `to_json` always yields valid json (labels are escaped, non-finite values are written as strings),
+//! and parsing entry points (`export::Tree::from_json`, `Deserialize for Cherry<T>`) return `Result`.
+//! `Tree::from_json` and `Tree::try_from` read trees, `to_json` writes them and dropping frees them
+//! without recursion, however deep they are.
+//! Other walks of trees recurse (e.g. `Clone`, `PartialEq` and `Serialize` of `export::Tree`,
+//! display units, print policies and symbol styles applied by `to_json`, `store::Store` and derivatives),
+//! so bound the depth of untrusted trees (e.g. with `Cherry::depth`) before using them there.
+//!
+//! The only panics left are documented in `# Panics` sections: folds comparing values that
+//! cannot be compared (`minimum!`, `maximum!`, `median!`, `argmax!`, `argmin!` on NaN) and
+//! means of more nodes than integers or the storage type of uom quantities can count (`fold::DivCount`).
+//!
+//! ## no_std
+//!
+//! Disabling the default feature `std` builds the crate with `alloc` only.
+//! Modules relying on thread-local state, clocks or I/O (`session`, `stream`, `timing`,
+//! `rounding`, `report`, display units of `units`) and float math (`stddev!`, `distance`, `norm`, `angle`)
+//! are available with `std` only.
+//!
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
 
 ////////////////////////////////////////////////////////////////////////////////
 
+extern crate alloc;
 extern crate uom;
 extern crate serde;
+extern crate serde_json;
+
+// Paths used by exported macros, so they also expand in `no_std` crates.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::{vec, vec::Vec};
+}
+
+pub use export::{Failure, Outcome, Tree};
+pub use node::{Cherries, Cherry, Leaf, Node};
+pub use validate::{Error, ErrorCode, Severity, Validate, ValidateChain};
+
+///
+/// Items used by typical code, imported at once with `use cherries::prelude::*`.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate cherries;
+/// use cherries::prelude::*;
+///
+/// # fn main() {
+/// let a = Leaf::new().value(2).name("a").build();
+/// let b = Leaf::new().value(3).name("b").build();
+/// let total = sum_all!(a, b).labeled("total");
+/// assert_eq!(total.name(), &"total".to_string());
+/// let checked: Result<_, Error> = total.validate("must be positive", |v| *v > 0).into_result();
+/// assert!(checked.is_ok());
+/// # }
+/// ```
+pub mod prelude {
+    pub use crate::node::{Cherries, Leaf};
+    pub use crate::validate::{Error, Validate};
+    #[cfg(feature = "std")]
+    pub use crate::stddev;
+    pub use crate::{
+        all, any, argmax, argmin, count_if, expr, fold_all, maximum, mean, median, minimum, prod_all,
+        sum_all, validate, variance, weighted_sum,
+    };
+}
 
 pub mod cmp;
 pub mod node;
 pub mod ops;
+pub mod lazy;
+pub mod dry;
+pub mod parser;
+pub mod rebind;
+mod derivative;
 #[macro_use]
 pub mod fold;
+#[macro_use]
+mod expr;
 pub mod validate;
+#[macro_use]
+pub mod checks;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod export;
+pub mod visit;
+pub mod serializer;
+pub mod diff;
+pub mod attachment;
+#[cfg(feature = "std")]
+mod zip;
+pub mod store;
+pub mod batch;
+pub mod geometry;
+#[cfg(feature = "std")]
+pub mod timing;
+pub mod lookup;
+#[macro_use]
+mod quantities;
+pub mod convert;
+#[cfg(feature = "std")]
+pub mod uncertain;
+#[cfg(feature = "std")]
+pub mod montecarlo;
+pub mod interval;
+#[cfg(feature = "std")]
+pub mod rounding;
+pub mod units;
+#[cfg(feature = "std")]
+pub mod report;
+pub mod template;
+#[cfg(feature = "publish")]
+pub mod publish;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "legacy-json")]
+pub mod legacy;
 
 #[cfg(test)]
 mod tests {
@@ -61,6 +174,7 @@ mod tests {
     use uom::si::length::{meter, millimeter};
 
     #[test]
+    #[allow(clippy::float_cmp)]
     fn basic_tests() {
         let x = Leaf::new()
             .name("x")
@@ -117,6 +231,7 @@ mod tests {
 mod label_tests {
     use crate::node::{Cherries, Leaf};
     #[test]
+    #[allow(clippy::many_single_char_names)]
     fn it_works() {
         // labeling
         let node = Leaf::new().value(1).name("node").build();
@@ -147,6 +262,21 @@ mod validate_tests {
 
         assert_eq!(validated, Ok(Leaf::new().value(2).name("node").build()));
     }
+    #[test]
+    fn chain_works() {
+        let node = Leaf::new().value(3).name("node").build();
+        let chain = node
+            .validate("must be even", |v| v % 2 == 0)
+            .validate("must be positive", |v| v > &0)
+            .validate("must be less than 2", |v| v < &2);
+
+        assert_eq!(chain.error_count(), 2);
+        assert_eq!(
+            chain.errors(),
+            &["must be even".to_string(), "must be less than 2".to_string()]
+        );
+        assert_eq!(chain.cherry().quantity(), &3);
+    }
 }
 #[cfg(test)]
 mod fold_tests {
@@ -163,11 +293,38 @@ mod fold_tests {
         assert_eq!(&Length::new::<meter>(4), res.quantity());
         println!("{}", res.to_json());
     }
+    #[test]
+    fn median_works() {
+        use uom::si::f32::Length;
+        let a = Leaf::new().value(Length::new::<meter>(5.0)).name("a").build();
+        let b = Leaf::new().value(Length::new::<meter>(1.0)).name("b").build();
+        let c = Leaf::new().value(Length::new::<meter>(4.0)).name("c").build();
+        let d = Leaf::new().value(Length::new::<meter>(2.0)).name("d").build();
+        let res = median!(a, b, c, d);
+        assert_eq!(&Length::new::<meter>(3.0), res.quantity());
+        assert_eq!(res.symbol(), "m^1".to_string());
+        println!("{}", res.to_json());
+    }
+    #[test]
+    fn mean_of_narrow_integers_counts_exactly() {
+        use crate::fold::DivCount;
+        assert_eq!(1000_i16.div_count(200), 5);
+        assert_eq!(250_u8.div_count(250), 1);
+        let a = Leaf::new().value(-8_i8).name("a").build();
+        let b = Leaf::new().value(2_i8).name("b").build();
+        assert_eq!(mean!(a, b).quantity(), &-3);
+    }
+    #[test]
+    #[should_panic(expected = "count is not representable in storage type")]
+    fn mean_of_more_integers_than_storable_panics() {
+        use crate::fold::DivCount;
+        // `256 as u8` would divide by zero
+        let _ = 0_u8.div_count(256);
+    }
 }
 
 #[cfg(test)]
 mod serialize_tests {
-    extern crate serde_json;
     #[test]
     fn it_works() {
         use crate::node::{Cherry, Leaf};
@@ -184,4 +341,746 @@ mod serialize_tests {
         // Prints deserialized = Point { x: 1, y: 2 }
         println!("deserialized = {:?}", deserialized);
     }
+    #[test]
+    fn op_is_always_a_field() {
+        use crate::node::{Cherry, Leaf, Op};
+        let x = Leaf::new().value(2).name("x").build();
+        let y = (x.clone() + x.clone()).labeled("y");
+        let leaf = serde_json::to_value(&x).unwrap();
+        assert_eq!(leaf["op"], serde_json::Value::Null);
+        assert_eq!(serde_json::to_value(&y).unwrap().as_object().unwrap().len(), 4);
+
+        // formats without field names read the fields in order
+        let fields = |value: serde_json::Value| {
+            let object = value.as_object().unwrap().clone();
+            ["label", "value", "previous", "op"]
+                .iter()
+                .map(|field| object[*field].clone())
+                .collect::<Vec<_>>()
+        };
+        let seq = serde_json::to_string(&fields(serde_json::to_value(&y).unwrap())).unwrap();
+        let restored: Cherry<i32> = serde_json::from_str(&seq).unwrap();
+        assert_eq!(restored.op(), &Op::Add);
+        let seq = serde_json::to_string(&fields(leaf)).unwrap();
+        let restored: Cherry<i32> = serde_json::from_str(&seq).unwrap();
+        assert_eq!(restored.op(), &Op::Leaf);
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use crate::node::Leaf;
+    use crate::session::Session;
+    use crate::validate::Validate;
+    #[test]
+    fn it_works() {
+        let a = Leaf::new().value(2).name("a").build();
+        let b = Leaf::new().value(3).name("b").build();
+        let mut session = Session::new();
+        session.input(&a).input_from(&b, "test report");
+        let c = (a * b).labeled("c");
+        session.output(&c);
+        session.check(&c.validate("must be even", |v| v % 2 == 0).into_result());
+
+        let manifest = session.manifest();
+        assert_eq!(manifest.outputs[0].label, "c".to_string());
+        assert!(manifest.checks[0].passed);
+        assert_eq!(session.trees().len(), 1);
+        println!("{}", manifest.to_json());
+    }
+    #[test]
+    fn observe_works() {
+        use crate::node::Cherries;
+        use crate::session::Observer;
+        use std::{cell::RefCell, rc::Rc};
+
+        struct Count(usize);
+        impl Observer for Count {
+            fn on_build(&mut self, _: &dyn Cherries) {
+                self.0 += 1;
+            }
+        }
+
+        let outer = Rc::new(RefCell::new(Count(0)));
+        let inner = Rc::new(RefCell::new(Count(0)));
+        let mut session = Session::new();
+        session.observe(outer.clone());
+        let _ = session.run(|_| {
+            let a = Leaf::new().value(2).name("a").build();
+            let mut nested = Session::new();
+            nested.observe(inner.clone());
+            nested.run(|_| a * Leaf::new().value(3).name("b").build())
+        });
+        // not active outside of `run`
+        let _ = Leaf::new().value(4).name("c").build();
+
+        assert_eq!(outer.borrow().0, 3);
+        assert_eq!(inner.borrow().0, 2);
+    }
+    #[test]
+    fn observers_survive_panic() {
+        use crate::node::Cherries;
+        use crate::session::Observer;
+        use std::{cell::RefCell, panic, rc::Rc};
+
+        struct Count(usize);
+        impl Observer for Count {
+            fn on_build(&mut self, _: &dyn Cherries) {
+                self.0 += 1;
+            }
+        }
+
+        let count = Rc::new(RefCell::new(Count(0)));
+        let mut session = Session::new();
+        session.observe(count.clone());
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            session.run(|_| {
+                let _ = Leaf::new().value(1).name("a").build();
+                panic!("calculation failed");
+            })
+        }));
+        assert!(res.is_err());
+        assert_eq!(count.borrow().0, 1);
+
+        let _ = session.run(|_| Leaf::new().value(2).name("b").build());
+        assert_eq!(count.borrow().0, 2);
+    }
+    #[test]
+    fn invariants_check_leaves_folds_and_maps() {
+        use uom::si::{f32::Length, length::meter};
+
+        let mut session = Session::new();
+        session.invariant("must be non-negative", |l: &Length| l.value >= 0.0);
+        let total = session.run(|_| {
+            let bad = Leaf::new().value(Length::new::<meter>(-1.0)).name("bad").build();
+            let good = Leaf::new().value(Length::new::<meter>(3.0)).name("good").build();
+            let sum = (bad.clone() + good.clone()).labeled("sum");
+            let negated = good.map(|l| Length::new::<meter>(-l.value));
+            sum_all!(bad, sum, negated)
+        });
+        assert_eq!(total.quantity(), &Length::new::<meter>(-2.0));
+        let labels = session
+            .manifest()
+            .checks
+            .into_iter()
+            .map(|check| check.label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["bad", "(map)", "foldl"]);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod stream_tests {
+    use crate::node::Leaf;
+    use crate::session::Session;
+    use crate::stream::JsonStream;
+    use std::{cell::{Cell, RefCell}, io, rc::Rc};
+
+    // Writer failing while `broken` is set, as a closed socket does.
+    struct Socket {
+        broken: Rc<Cell<bool>>,
+        written: Vec<u8>,
+    }
+
+    impl io::Write for Socket {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.broken.get() {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+            }
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn nodes_are_not_buffered_after_failed_write() {
+        let broken = Rc::new(Cell::new(true));
+        let socket = Socket { broken: broken.clone(), written: vec![] };
+        let stream = Rc::new(RefCell::new(JsonStream::new(socket)));
+        let mut session = Session::new();
+        session.observe(stream.clone());
+        let _ = session.run(|_| {
+            let x = Leaf::new().value(2).name("x").build();
+            let y = Leaf::new().value(3).name("y").build();
+            x + y
+        });
+        broken.set(false);
+        let err = stream.borrow_mut().flush().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        // nodes built after the failure were dropped
+        stream.borrow_mut().flush().unwrap();
+        assert!(stream.borrow().get_ref().written.is_empty());
+
+        let _ = session.run(|_| Leaf::new().value(4).name("z").build());
+        assert_eq!(String::from_utf8_lossy(&stream.borrow().get_ref().written).lines().count(), 1);
+    }
+}
+
+#[cfg(test)]
+mod export_tests {
+    use crate::export::{Tree, Value};
+    use crate::node::{Cherries, Leaf};
+    #[test]
+    fn it_works() {
+        let a = Leaf::new().value(2).name("a").build();
+        let b = Leaf::new().value(3).name("b").build();
+        let res = all!(|v: &i32| v > &0, a, b);
+        let tree = Tree::from_json(&res.to_json()).unwrap();
+        assert_eq!(tree.value, Value::Bool(true));
+        assert_eq!(tree.subexpr.len(), 2);
+        assert_eq!(tree.subexpr[0].value, Value::Number(2.0));
+    }
+    #[test]
+    fn deep_trees_work() {
+        use crate::export::Outcome;
+        use crate::validate::Validate;
+        use std::convert::TryFrom;
+
+        let mut sum = Leaf::new().value(0).name("x0").build();
+        for i in 1..300 {
+            sum = sum + Leaf::new().value(i).name(format!("x{}", i)).build();
+        }
+        let tree = Tree::try_from(&sum).unwrap();
+        assert_eq!(tree, Tree::from_json(&sum.to_json()).unwrap());
+        assert_eq!(tree.value, Value::Number(44850.0));
+        let mut node = &tree;
+        for _ in 1..300 {
+            assert_eq!(node.subexpr.len(), 2);
+            node = &node.subexpr[0];
+        }
+        assert_eq!(node.label, "x0".to_string());
+
+        let res = sum.validate("must be odd", |v| v % 2 == 1).into_result();
+        match Outcome::try_from(&res).unwrap() {
+            Outcome::Error { error } => assert_eq!(error.tree, tree),
+            Outcome::Ok { .. } => panic!("44850 is even"),
+        }
+    }
+    #[test]
+    fn very_deep_trees_are_read_and_dropped() {
+        use std::convert::TryFrom;
+
+        let depth = |tree: &Tree| {
+            let (mut node, mut depth) = (tree, 0);
+            while let Some(sub) = node.subexpr.first() {
+                node = sub;
+                depth += 1;
+            }
+            depth
+        };
+        let one = Leaf::new().value(1).name("x").build();
+        let mut sum = one.clone();
+        for _ in 0..100_000 {
+            sum = sum + one.clone();
+        }
+        let json = sum.to_json();
+        let read = Tree::from_json(&json).unwrap();
+        assert_eq!(depth(&read), 100_000);
+        assert_eq!(read.value, Value::Number(100_001.0));
+        assert_eq!(depth(&Tree::try_from(&sum).unwrap()), 100_000);
+        drop(read);
+
+        let leaf = Tree::from_json("{\"label\":\"x\",\"value\":1,\"unit\":\"dimensionless\"}").unwrap();
+        let mut tree = leaf.clone();
+        for _ in 0..100_000 {
+            let mut parent = leaf.clone();
+            parent.subexpr.push(tree);
+            tree = parent;
+        }
+        assert_eq!(depth(&tree), 100_000);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod zip_tests {
+    use crate::zip::{crc32, ZipWriter};
+    #[test]
+    fn crc32_matches_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+    #[test]
+    fn archive_layout() {
+        let mut zip = ZipWriter::new(Vec::new());
+        zip.add("a.txt", b"abc").unwrap();
+        let bytes = zip.finish().unwrap();
+        // local header + name + content, central directory + name, end of central directory
+        assert_eq!(bytes.len(), (30 + 5 + 3) + (46 + 5) + 22);
+        let end = &bytes[bytes.len() - 22..];
+        assert_eq!(&end[..4], b"PK\x05\x06");
+        assert_eq!(&end[10..12], &1_u16.to_le_bytes());
+        assert_eq!(&end[16..20], &38_u32.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod untrusted_input_tests {
+    use crate::export::{Tree, Value};
+    use crate::node::{Cherries, Cherry, Leaf};
+
+    // Deterministic pseudo random strings made of json-significant characters.
+    fn samples(count: usize) -> Vec<String> {
+        const ALPHABET: &[char] = &[
+            '{', '}', '[', ']', '"', '\\', ':', ',', ' ', '\n', '\u{0}', 'a', 'l', '1', '.', 'e', '-',
+            'π', 'n', 'u', 't',
+        ];
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        (0..count)
+            .map(|_| {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                let len = (state >> 59) as usize;
+                (0..len)
+                    .map(|_| {
+                        state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                        ALPHABET[(state >> 33) as usize % ALPHABET.len()]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn from_json_never_panics() {
+        let json = (Leaf::new().value(1.5).name("x").build() * Leaf::new().value(2.0).name("y").build())
+            .to_json();
+        let truncated = json.char_indices().map(|(i, _)| json[..i].to_string());
+        for input in samples(2000).into_iter().chain(truncated) {
+            let _ = Tree::from_json(&input);
+            let _ = serde_json::from_str::<Cherry<f32>>(&input);
+        }
+        assert!(serde_json::from_str::<Cherry<f32>>("[1]").is_err());
+        assert!(serde_json::from_str::<Cherry<f32>>("{\"label\":1}").is_err());
+    }
+
+    #[test]
+    fn previous_must_be_trees() {
+        let json = "{\"label\":\"x\",\"value\":1.0,\"previous\":\"}]}{not json\"}";
+        assert!(serde_json::from_str::<Cherry<f64>>(json).is_err());
+        let sum = Leaf::new().value(1.0).name("x").build() + Leaf::new().value(2.0).name("y").build();
+        let node = serde_json::from_str::<Cherry<f64>>(&serde_json::to_string(&sum).unwrap()).unwrap();
+        assert_eq!(Tree::from_json(&node.to_json()).unwrap().subexpr.len(), 2);
+    }
+
+    #[test]
+    fn to_json_is_always_valid() {
+        for label in samples(500) {
+            let node = Leaf::new().value(std::f32::NAN).name(label.clone()).build()
+                + Leaf::new().value(1.0).name(label.clone()).build();
+            let tree = Tree::from_json(&node.labeled(label.clone()).to_json()).unwrap();
+            assert_eq!(tree.label, label);
+            assert_eq!(tree.value, Value::Text("NaN".to_string()));
+            assert_eq!(tree.subexpr[1].label, label);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn parse_never_panics() {
+        use crate::lazy::Env;
+        use crate::parser::parse;
+        let env = Env::new().bind("a", 2.0).bind("l", 3.0);
+        for formula in samples(2000) {
+            if let Ok(expr) = parse::<f64>(&formula) {
+                let _ = expr.evaluate(&env);
+            }
+        }
+        let nested = format!("{}a{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(parse::<f64>(&nested).is_err());
+        let flat = format!("{}a", "a+".repeat(100_000));
+        assert!(parse::<f64>(&flat).is_err());
+        let longest = format!("{}a", "a+".repeat(1024));
+        assert_eq!(parse::<f64>(&longest).unwrap().evaluate(&env).unwrap().quantity(), &2050.0);
+        assert!(parse::<f64>("1e5 * a").is_ok());
+        assert!(parse::<f64>("1e").is_err());
+        assert!(parse::<f64>("a b").is_err());
+    }
+
+    #[test]
+    fn symbol_never_panics() {
+        #[derive(Clone)]
+        struct Odd(String);
+        impl std::fmt::Debug for Odd {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+        for formats in samples(500) {
+            let node = Leaf::new().value(Odd(formats)).name("odd").build();
+            let _ = node.value();
+            node.symbol();
+            let _ = Tree::from_json(&node.to_json()).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod rounding_tests {
+    use crate::node::{Cherries, Leaf};
+    use uom::si::{f64::*, length::{meter, millimeter}, thermodynamic_temperature::degree_celsius};
+    #[test]
+    fn round_in_works() {
+        let l = Leaf::new().value(Length::new::<meter>(1.23456)).name("l").build();
+        let floor = l.floor_in::<millimeter>();
+        assert!((floor.quantity().get::<millimeter>() - 1234.0).abs() < 1e-9);
+        assert_eq!(floor.name(), &"(floor: mm)".to_string());
+        assert_eq!(floor.symbol(), "m^1".to_string());
+        assert!((l.ceil_in::<millimeter>().quantity().get::<millimeter>() - 1235.0).abs() < 1e-9);
+        assert!((l.round_in::<meter>().quantity().get::<meter>() - 1.0).abs() < 1e-9);
+
+        let t = Leaf::new()
+            .value(ThermodynamicTemperature::new::<degree_celsius>(20.6))
+            .name("t")
+            .build();
+        let rounded = t.round_in::<degree_celsius>();
+        assert!((rounded.quantity().get::<degree_celsius>() - 21.0).abs() < 1e-9);
+        assert_eq!(rounded.name(), &"(round: °C)".to_string());
+    }
+}
+
+#[cfg(test)]
+mod units_tests {
+    use crate::export::{Tree, Value};
+    use crate::node::{Cherries, Leaf};
+    use uom::si::{f64::*, length::{meter, millimeter}};
+    #[test]
+    fn convert_to_works() {
+        let l = Leaf::new().value(Length::new::<meter>(1.2345)).name("l").build();
+        let converted = l.convert_to::<millimeter>();
+        assert_eq!(converted.quantity(), l.quantity());
+        assert_eq!(converted.name(), &"(convert)".to_string());
+        let tree = Tree::from_json(&converted.to_json()).unwrap();
+        assert_eq!(tree.unit, "m^1".to_string());
+        assert_eq!(tree.subexpr[0].label, "l".to_string());
+        assert_eq!(tree.subexpr[1].label, "l [mm]".to_string());
+        assert_eq!(tree.subexpr[1].value, Value::Number(1234.5));
+        assert_eq!(tree.subexpr[1].unit, "mm".to_string());
+    }
+
+    #[test]
+    fn display_units_keep_metadata() {
+        use crate::node::Origin;
+        use crate::units::{set_display_units, DisplayUnits};
+        let t = Leaf::new()
+            .value(Length::new::<millimeter>(12.0))
+            .name("t")
+            .build()
+            .with_origin(Origin::Measured)
+            .attach("calipers.jpg", "image/jpeg");
+        let plain = Tree::from_json(&t.to_json()).unwrap();
+        let previous = set_display_units(Some(DisplayUnits::new().prefer::<Length, millimeter>()));
+        let shown = Tree::from_json(&t.to_json()).unwrap();
+        set_display_units(previous);
+        assert_eq!(shown.unit, "mm".to_string());
+        assert_eq!(shown.origin, Some(Origin::Measured));
+        assert_eq!(shown.attachments, plain.attachments);
+    }
+}
+
+#[cfg(all(test, feature = "track-caller"))]
+mod location_tests {
+    use crate::node::{set_record_locations, Cherries, Leaf};
+    #[test]
+    fn nodes_record_where_they_are_built() {
+        assert_eq!(Leaf::new().value(2).name("x").build().location(), None);
+        let previous = set_record_locations(true);
+        let at = |line: u32, column: u32| format!("{}:{}:{}", file!(), line, column);
+        let x = Leaf::new().value(2).name("x").build();
+        assert_eq!(x.location(), Some(at(line!() - 1, 48).as_str()));
+        let y = x.clone() * x;
+        assert_eq!(y.location(), Some(at(line!() - 1, 17).as_str()));
+        assert_eq!(y.clone().labeled("y").location(), y.location());
+        assert!(y.to_json().contains(&format!("\"location\":\"{}\"", at(line!() - 3, 17))));
+        set_record_locations(previous);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod montecarlo_tests {
+    use crate::export::{Tree, Value};
+    use crate::lazy::symbol;
+    use crate::montecarlo::{Distribution, MonteCarlo, MonteCarloError};
+    use core::convert::TryFrom;
+
+    #[test]
+    fn non_finite_samples_are_left_out() {
+        // magnitudes above `f32::MAX` are infinite as `f32`
+        let x = symbol::<f32>("a").labeled("x");
+        let result = MonteCarlo::new(100)
+            .leaf::<f32>("a", Distribution::Uniform { low: 0.0, high: 1e39 })
+            .run(&x)
+            .unwrap();
+        let tree = Tree::try_from(&result).unwrap();
+        assert_eq!(tree.subexpr[1].label, "non_finite".to_string());
+        let non_finite = match tree.subexpr[1].value {
+            Value::Number(count) => count,
+            _ => unreachable!(),
+        };
+        assert!(non_finite > 0.0 && non_finite < 100.0);
+        for sub in tree.subexpr.iter().skip(5) {
+            match sub.value {
+                Value::Number(value) => assert!(value.is_finite()),
+                _ => unreachable!(),
+            }
+        }
+        let infinite = MonteCarlo::new(10).leaf::<f32>("a", Distribution::Fixed(1e39));
+        assert_eq!(infinite.run(&x).unwrap_err(), MonteCarloError::NonFinite);
+    }
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use crate::batch::evaluate_batch;
+    use crate::node::Leaf;
+    use crate::validate::{Severity, Validate};
+
+    #[test]
+    fn failures_are_written_as_errors() {
+        let report = evaluate_batch(vec![2, 3], |v| {
+            Leaf::new()
+                .value(v)
+                .name("x")
+                .build()
+                .check(Severity::Warning, "should be small", |v| v < &2)
+                .validate("must be even", |v| v % 2 == 0)
+                .into_result()
+        });
+        let json = serde_json::from_str::<serde_json::Value>(&report.to_json()).unwrap();
+        let mut failure = serde_json::to_value(&report.failures[0].1).unwrap();
+        failure["index"] = 1.into();
+        assert_eq!(json["failures"][0], failure);
+        assert_eq!(json["failures"][0]["tree"]["findings"][0]["msg"], "should be small");
+        assert_eq!(json["successes"][0]["tree"]["findings"][0]["severity"], "warning");
+    }
+}
+
+#[cfg(test)]
+mod store_tests {
+    use crate::store::Store;
+
+    // Store of `n + 1` nodes, each referencing the previous one twice (`2^(n + 1) - 1` nodes rebuilt).
+    fn doubling(n: usize) -> Store {
+        let mut objects = vec!["\"h0\":{\"label\":\"x\",\"value\":1,\"unit\":\"dimensionless\"}".to_string()];
+        for i in 1..=n {
+            objects.push(format!(
+                "\"h{}\":{{\"label\":\"x\",\"value\":1,\"unit\":\"dimensionless\",\"subexpr\":[\"h{1}\",\"h{1}\"]}}",
+                i,
+                i - 1
+            ));
+        }
+        Store::from_json(&format!("{{\"objects\":{{{}}}}}", objects.join(","))).unwrap()
+    }
+
+    #[test]
+    fn rebuilt_nodes_are_bounded() {
+        let store = doubling(2);
+        assert_eq!(store.get("h2").map(|tree| tree.subexpr[1].subexpr.len()), Some(2));
+        assert!(store.get_with_limit("h2", 7).is_some());
+        assert!(store.get_with_limit("h2", 6).is_none());
+        assert!(doubling(64).get("h64").is_none());
+    }
+}
+
+#[cfg(all(test, feature = "timestamps"))]
+mod timestamp_tests {
+    use crate::node::{rfc3339, set_record_timestamps, Cherries, Leaf};
+    use std::time::Duration;
+    #[test]
+    fn timestamps_are_rfc3339_in_utc() {
+        assert_eq!(rfc3339(Duration::from_secs(0)), "1970-01-01T00:00:00.000Z");
+        let leap_day = Duration::from_millis(1_582_977_600_250);
+        assert_eq!(rfc3339(leap_day), "2020-02-29T12:00:00.250Z");
+        let end_of_century = Duration::from_secs(4_107_542_399);
+        assert_eq!(rfc3339(end_of_century), "2100-02-28T23:59:59.000Z");
+    }
+    #[test]
+    fn nodes_record_when_they_are_built() {
+        assert_eq!(Leaf::new().value(2).name("x").build().timestamp(), None);
+        let previous = set_record_timestamps(true);
+        let x = Leaf::new().value(2).name("x").build();
+        let y = x.clone() * x.clone();
+        set_record_timestamps(previous);
+        let built = x.timestamp().unwrap();
+        assert_eq!(built.len(), "2020-02-29T12:00:00.250Z".len());
+        assert!(y.timestamp().unwrap() >= built);
+        assert_eq!(y.clone().labeled("y").timestamp(), y.timestamp());
+        assert!(y
+            .to_json()
+            .contains(&format!("\"timestamp\":\"{}\"", y.timestamp().unwrap())));
+    }
+}
+
+#[cfg(test)]
+mod visit_tests {
+    use crate::export::Tree;
+    use crate::node::{Cherries, Leaf, Node};
+    use crate::visit::Visitor;
+    use alloc::{format, string::String, vec::Vec};
+
+    struct Labels(Vec<String>);
+
+    impl Visitor for Labels {
+        fn enter(&mut self, node: &Tree, depth: usize) {
+            self.0.push(format!("{}{}", depth, node.label));
+        }
+        fn leave(&mut self, node: &Tree, depth: usize) {
+            self.0.push(format!("/{}{}", depth, node.label));
+        }
+    }
+
+    #[test]
+    fn visitors_walk_subexpressions_recorded_as_json() {
+        let archived = Node::new()
+            .name("y")
+            .value(5.0)
+            .prev(
+                "{\"label\":\"(add)\",\"value\":5,\"unit\":\"dimensionless\",\"subexpr\":[\
+                 {\"label\":\"a\",\"value\":2,\"unit\":\"dimensionless\"},\
+                 {\"label\":\"b\",\"value\":3,\"unit\":\"dimensionless\"}]}",
+            )
+            .build();
+        let z = archived * Leaf::new().value(2.0).name("c").build();
+        let mut labels = Labels(Vec::new());
+        z.accept(&mut labels).unwrap();
+        assert_eq!(
+            labels.0.join(" "),
+            "0(mul) 1y 2(add) 3a /3a 3b /3b /2(add) /1y 1c /1c /0(mul)"
+        );
+        assert_eq!((z.node_count(), z.depth()), (labels.0.len() / 2, 3));
+        let add = z.subtree("(add)").unwrap();
+        assert_eq!(add.tree().unwrap().subexpr[1].label, "b");
+        assert_eq!(
+            z.subtree("b").unwrap().to_json(),
+            "{\"label\":\"b\",\"value\":3,\"unit\":\"dimensionless\"}"
+        );
+        let broken = Node::new().name("x").value(1.0).prev("{\"label\":").build();
+        assert!(broken.accept(&mut Labels(Vec::new())).is_err());
+    }
+}
+
+#[cfg(test)]
+mod replace_tests {
+    use crate::export::Value;
+    use crate::node::{Cherries, Leaf, Node};
+
+    #[test]
+    fn replacing_subexpressions_recorded_as_json() {
+        let archived = Node::new()
+            .name("y")
+            .value(5.0)
+            .prev(
+                "{\"label\":\"a\",\"value\":2,\"unit\":\"dimensionless\"},\
+                 {\"label\":\"(add)\",\"value\":5,\"unit\":\"dimensionless\",\"subexpr\":[\
+                 {\"label\":\"b\",\"value\":2,\"unit\":\"dimensionless\"},\
+                 {\"label\":\"c\",\"value\":3,\"unit\":\"dimensionless\"}]}",
+            )
+            .build();
+        let z = Leaf::new().value(1.0).name("k").build() * archived;
+        let c = Leaf::new().value(4.0).name("c").build();
+        let replaced = z.replace("c", &c);
+        assert_eq!(replaced.subtree("c").unwrap().value(), Ok(4.0));
+        let add = replaced.subtree("(add)").unwrap().tree().unwrap();
+        assert_eq!(add.subexpr[0].label, "b");
+        // `y` (made by an unknown operation) cannot be recomputed
+        assert!(z.replace_reevaluated("c", &c).is_err());
+        let k = Leaf::new().value(3.0).name("k").build();
+        let tree = z.replace_reevaluated("k", &k).unwrap();
+        assert_eq!(tree.value, Value::Number(15.0));
+    }
+}
+
+#[cfg(test)]
+mod truncate_tests {
+    use crate::node::{Cherries, Leaf, Node};
+
+    #[test]
+    fn truncating_subexpressions_recorded_as_json() {
+        let archived = Node::new()
+            .name("y")
+            .value(5.0)
+            .prev(
+                "{\"label\":\"a\",\"value\":2,\"unit\":\"dimensionless\"},\
+                 {\"label\":\"(add)\",\"value\":3,\"unit\":\"dimensionless\",\"subexpr\":[\
+                 {\"label\":\"b\",\"value\":1,\"unit\":\"dimensionless\"},\
+                 {\"label\":\"c\",\"value\":2,\"unit\":\"dimensionless\"}]}",
+            )
+            .build();
+        let z = Leaf::new().value(1.0).name("k").build() * archived;
+        assert_eq!(
+            z.to_json_with_depth(2),
+            "{\"label\":\"(mul)\",\"value\":5,\"unit\":\"dimensionless\",\"op\":\"mul\",\"subexpr\":[\
+             {\"label\":\"k\",\"value\":1,\"unit\":\"dimensionless\"},\
+             {\"label\":\"y\",\"value\":5,\"unit\":\"dimensionless\",\"subexpr\":[\
+             {\"label\":\"a\",\"value\":2,\"unit\":\"dimensionless\"},\
+             {\"label\":\"(add)\",\"value\":3,\"unit\":\"dimensionless\",\"collapsed\":2}]}]}"
+        );
+        assert!(z.to_json_with_depth(1).ends_with("\"unit\":\"dimensionless\",\"collapsed\":2}]}"));
+        assert_eq!(z.to_json_with_depth(3), z.to_json());
+    }
+}
+
+#[cfg(all(test, feature = "graphql"))]
+mod graphql_tests {
+    use crate::export::Tree;
+    use crate::graphql::Query;
+    use crate::node::Leaf;
+    use crate::validate::{Severity, Validate};
+    use core::convert::TryFrom;
+
+    fn tree() -> Tree {
+        let eta = Leaf::new().value(0.95).name("eta").build();
+        let load = Leaf::new().value(12.0).name("F").build();
+        let node = (eta * load)
+            .labeled("F_d")
+            .check(Severity::Warning, "close to limit", |v| v < &10.0)
+            .into_result()
+            .unwrap();
+        Tree::try_from(&node).unwrap()
+    }
+
+    #[test]
+    fn resolving_checks_and_children() {
+        let tree = tree();
+        let root = Query::new(&tree).root();
+        assert_eq!(root.op(), Some("mul"));
+        assert_eq!(root.checks().len(), 1);
+        assert_eq!(root.checks()[0].severity, Severity::Warning);
+        assert_eq!(root.checks()[0].msg, "close to limit".to_string());
+        let load = root.child("F").unwrap();
+        assert_eq!((load.value(), load.text()), (Some(12.0), "12".to_string()));
+        assert!(load.children().is_empty());
+        assert!(root.child("G").is_none());
+    }
+
+    #[test]
+    fn schema_has_a_field_per_resolver() {
+        use crate::graphql::SCHEMA;
+        // field names of `ty` in `SCHEMA`
+        fn fields(ty: &str) -> Vec<&'static str> {
+            SCHEMA
+                .split(&format!("type {} {{", ty)[..])
+                .nth(1)
+                .and_then(|rest| rest.split('}').next())
+                .unwrap()
+                .lines()
+                .filter_map(|line| line.trim().split(|c| c == ':' || c == '(').next())
+                .filter(|name| !name.is_empty())
+                .collect()
+        }
+        let tree = tree();
+        let query = Query::new(&tree);
+        let (root, _, _) = (query.root(), query.node(&[]), query.find("F"));
+        assert_eq!(fields("Query"), vec!["root", "node", "find"]);
+        let _ = (root.label(), root.value(), root.text(), root.unit(), root.op(), root.comment());
+        let _ = (root.checks(), root.collapsed(), root.children(), root.child("F"));
+        assert_eq!(
+            fields("Node"),
+            vec!["label", "value", "text", "unit", "op", "comment", "checks", "collapsed", "children", "child"]
+        );
+        let finding = &root.checks()[0];
+        let _ = (finding.severity.as_str(), &finding.msg);
+        assert_eq!(fields("Finding"), vec!["severity", "msg"]);
+    }
 }
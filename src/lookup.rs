@@ -0,0 +1,216 @@
+use super::node::{Cherries, Cherry, Leaf, Node, Subexpr};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Debug;
+use core::ops::{Add, Div, Mul, Sub};
+
+///
+/// How a `LookupTable` evaluates between breakpoints.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Takes value of the breakpoint at or below input.
+    Step,
+    /// Interpolates linearly between neighbouring breakpoints.
+    Linear,
+}
+
+///
+/// Named table of breakpoints `(x, y)` mapping input node to output node.
+///
+/// Inputs outside the table take value of the nearest end breakpoint.
+/// Result is labeled `({name})` and records the input and the breakpoints used
+/// (as leaves `{name}.x[i]`, `{name}.y[i]`), so the chosen interval shows in the tree.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::lookup::{Interpolation, LookupTable};
+///
+/// let table = LookupTable::new(
+///     "k_mod",
+///     vec![(0.0, 0.6), (10.0, 0.8), (20.0, 1.0)],
+///     Interpolation::Step,
+/// ).unwrap();
+/// let duration = Leaf::new().value(15.0_f64).name("t").build();
+/// let k = table.lookup(&duration);
+/// assert_eq!(k.quantity(), &0.8);
+/// assert_eq!(
+///     k.to_json(),
+///     "{\"label\":\"(k_mod)\",\"value\":0.8,\"unit\":\"dimensionless\",\"op\":\"lookup\",\"subexpr\":[\
+///         {\"label\":\"t\",\"value\":15,\"unit\":\"dimensionless\"},\
+///         {\"label\":\"k_mod.x[1]\",\"value\":10,\"unit\":\"dimensionless\"},\
+///         {\"label\":\"k_mod.y[1]\",\"value\":0.8,\"unit\":\"dimensionless\"}\
+///     ]}".to_string()
+/// );
+///
+/// let table = LookupTable::new(
+///     "k_mod",
+///     vec![(0.0, 0.6), (10.0, 0.8), (20.0, 1.0)],
+///     Interpolation::Linear,
+/// ).unwrap();
+/// assert!((table.lookup(&duration).quantity() - 0.9).abs() < 1e-12);
+/// assert!(LookupTable::new("bad", vec![(1.0, 0.0), (0.0, 1.0)], Interpolation::Step).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct LookupTable<X, Y> {
+    name: String,
+    breakpoints: Vec<(X, Y)>,
+    interpolation: Interpolation,
+}
+
+impl<X: Clone + Debug + PartialOrd, Y: Clone + Debug> LookupTable<X, Y> {
+    ///
+    /// Makes table from breakpoints.
+    ///
+    /// Returns `Err` if `breakpoints` is empty or its `x` is not strictly increasing.
+    ///
+    pub fn new<S: Into<String>>(
+        name: S,
+        breakpoints: Vec<(X, Y)>,
+        interpolation: Interpolation,
+    ) -> Result<Self, String> {
+        let name = name.into();
+        if breakpoints.is_empty() {
+            return Err(format!("lookup table `{}` has no breakpoints.", name));
+        }
+        if breakpoints
+            .windows(2)
+            .any(|w| w[0].0.partial_cmp(&w[1].0) != Some(core::cmp::Ordering::Less))
+        {
+            return Err(format!(
+                "breakpoints of lookup table `{}` are not strictly increasing.",
+                name
+            ));
+        }
+        Ok(LookupTable {
+            name,
+            breakpoints,
+            interpolation,
+        })
+    }
+    ///
+    /// Returns table name.
+    ///
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    ///
+    /// Returns breakpoints.
+    ///
+    pub fn breakpoints(&self) -> &[(X, Y)] {
+        &self.breakpoints
+    }
+    // Index of the last breakpoint at or below `x` (0 if below the table).
+    fn interval(&self, x: &X) -> usize {
+        self.breakpoints
+            .iter()
+            .rposition(|(bx, _)| bx <= x)
+            .unwrap_or(0)
+    }
+    fn breakpoint(&self, i: usize) -> Vec<Subexpr> {
+        let (x, y) = &self.breakpoints[i];
+        vec![
+            Leaf::new()
+                .name(format!("{}.x[{}]", self.name, i))
+                .value(x.clone())
+                .build()
+                .as_subexpr(),
+            Leaf::new()
+                .name(format!("{}.y[{}]", self.name, i))
+                .value(y.clone())
+                .build()
+                .as_subexpr(),
+        ]
+    }
+    fn node(&self, input: &Cherry<X>, value: Y, used: &[usize]) -> Cherry<Y> {
+        let mut subexpr = vec![input.as_subexpr()];
+        for &i in used {
+            subexpr.append(&mut self.breakpoint(i));
+        }
+        Node::new()
+            .name(format!("({})", self.name))
+            .op("lookup")
+            .value(value)
+            .children(subexpr)
+            .build()
+    }
+}
+
+impl<X, Y> LookupTable<X, Y>
+where
+    X: Clone + Debug + PartialOrd + Sub<Output = X> + Div<X>,
+    Y: Clone + Debug + Add<Output = Y> + Sub<Output = Y> + Mul<<X as Div<X>>::Output, Output = Y>,
+{
+    ///
+    /// Returns node of table value at `input`.
+    ///
+    pub fn lookup(&self, input: &Cherry<X>) -> Cherry<Y> {
+        let x = input.quantity();
+        let i = self.interval(x);
+        let (x0, y0) = &self.breakpoints[i];
+        match (self.interpolation, self.breakpoints.get(i + 1)) {
+            (Interpolation::Linear, Some((x1, y1))) if x0 <= x => {
+                let ratio = (x.clone() - x0.clone()) / (x1.clone() - x0.clone());
+                let value = y0.clone() + (y1.clone() - y0.clone()) * ratio;
+                self.node(input, value, &[i, i + 1])
+            }
+            _ => self.node(input, y0.clone(), &[i]),
+        }
+    }
+}
+
+///
+/// Interpolates linearly between support points `(x0, y0)` and `(x1, y1)` at `x`.
+///
+/// Slope is computed as `(y1 - y0) / (x1 - x0)`, so units of `x` and `y` may differ.
+/// Result is labeled `(interp)` and records `x` and both support points.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::lookup::lerp;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::meter, pressure::kilopascal};
+///
+/// let depth = |v| Leaf::new().value(Length::new::<meter>(v));
+/// let stress = |v| Leaf::new().value(Pressure::new::<kilopascal>(v));
+/// let x = depth(1.5).name("z").build();
+/// let p = lerp(
+///     &x,
+///     (&depth(1.0).name("z_0").build(), &stress(20.0).name("p_0").build()),
+///     (&depth(2.0).name("z_1").build(), &stress(40.0).name("p_1").build()),
+/// );
+/// assert!((p.quantity().get::<kilopascal>() - 30.0).abs() < 1e-9);
+/// assert_eq!(p.name(), &"(interp)".to_string());
+/// ```
+pub fn lerp<X, Y>(
+    x: &Cherry<X>,
+    (x0, y0): (&Cherry<X>, &Cherry<Y>),
+    (x1, y1): (&Cherry<X>, &Cherry<Y>),
+) -> Cherry<Y>
+where
+    X: Clone + Debug + Sub<Output = X>,
+    Y: Clone + Debug + Sub<Output = Y> + Div<X>,
+    <Y as Div<X>>::Output: Mul<X>,
+    Y: Add<<<Y as Div<X>>::Output as Mul<X>>::Output, Output = Y>,
+{
+    let slope = (y1.quantity().clone() - y0.quantity().clone())
+        / (x1.quantity().clone() - x0.quantity().clone());
+    let value = y0.quantity().clone() + slope * (x.quantity().clone() - x0.quantity().clone());
+    Node::new()
+        .name("(interp)")
+        .op("interp")
+        .value(value)
+        .children(vec![
+            x.as_subexpr(),
+            x0.as_subexpr(),
+            y0.as_subexpr(),
+            x1.as_subexpr(),
+            y1.as_subexpr(),
+        ])
+        .build()
+}
@@ -0,0 +1,339 @@
+use super::lazy::{Env, LazyCherry};
+use super::node::{Cherries, Cherry, Leaf, Node};
+use super::uncertain::Magnitude;
+use std::fmt::{self, Debug, Display};
+use std::marker::PhantomData;
+
+///
+/// Numbers (`f32`, `f64` and uom quantities) made from sampled magnitudes in base units.
+///
+pub trait Sampled: 'static + Clone + Debug + Magnitude {
+    /// Returns the value of magnitude `magnitude` (in base units).
+    fn from_magnitude(magnitude: f64) -> Self;
+}
+
+impl Sampled for f32 {
+    fn from_magnitude(magnitude: f64) -> f32 {
+        magnitude as f32
+    }
+}
+
+impl Sampled for f64 {
+    fn from_magnitude(magnitude: f64) -> f64 {
+        magnitude
+    }
+}
+
+macro_rules! impl_sampled {
+    ( $v:ident; $( $q:ident :: $Q:ident ),* ) => {
+        $(
+            impl Sampled for uom::si::$v::$Q {
+                fn from_magnitude(magnitude: f64) -> Self {
+                    uom::si::$v::$Q {
+                        dimension: PhantomData,
+                        units: PhantomData,
+                        value: $v::from_magnitude(magnitude),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+for_each_quantity!(impl_sampled);
+
+///
+/// Probability distribution of a leaf (parameters in base units).
+///
+/// Written like `normal(mean=0.3, stddev=0.01)`, which is how leaves record it.
+///
+#[derive(Clone, Copy, PartialEq)]
+pub enum Distribution {
+    /// Always `value`.
+    Fixed(f64),
+    /// Uniform over `[low, high]`.
+    Uniform { low: f64, high: f64 },
+    /// Normal with `mean` and standard deviation `stddev`.
+    Normal { mean: f64, stddev: f64 },
+    /// Triangular over `[low, high]` with peak at `mode`.
+    Triangular { low: f64, mode: f64, high: f64 },
+}
+
+impl Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Distribution::Fixed(value) => write!(f, "fixed({})", value),
+            Distribution::Uniform { low, high } => write!(f, "uniform(low={}, high={})", low, high),
+            Distribution::Normal { mean, stddev } => {
+                write!(f, "normal(mean={}, stddev={})", mean, stddev)
+            }
+            Distribution::Triangular { low, mode, high } => {
+                write!(f, "triangular(low={}, mode={}, high={})", low, mode, high)
+            }
+        }
+    }
+}
+
+impl Debug for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Distribution {
+    fn sample(&self, rng: &mut Rng) -> f64 {
+        match *self {
+            Distribution::Fixed(value) => value,
+            Distribution::Uniform { low, high } => low + (high - low) * rng.next_f64(),
+            Distribution::Normal { mean, stddev } => {
+                // Box-Muller; `1 - u` keeps the logarithm finite
+                let (u, v) = (1.0 - rng.next_f64(), rng.next_f64());
+                mean + stddev * (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+            }
+            Distribution::Triangular { low, mode, high } => {
+                let u = rng.next_f64();
+                let split = if high > low { (mode - low) / (high - low) } else { 0.0 };
+                if u < split {
+                    low + (u * (high - low) * (mode - low)).sqrt()
+                } else {
+                    high - ((1.0 - u) * (high - low) * (high - mode)).sqrt()
+                }
+            }
+        }
+    }
+}
+
+// SplitMix64, so runs are reproducible from the recorded seed on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    // Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+///
+/// Why a simulation could not be run.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MonteCarloError {
+    /// Expression has leaves without a declared distribution (or declared with another type).
+    Unbound(Vec<String>),
+    /// No samples were requested.
+    NoSamples,
+    /// Every sample was non-finite (e.g. divided by zero).
+    NonFinite,
+}
+
+impl Display for MonteCarloError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonteCarloError::Unbound(names) => {
+                write!(f, "no distribution for leaves {}", names.join(", "))
+            }
+            MonteCarloError::NoSamples => write!(f, "no samples requested"),
+            MonteCarloError::NonFinite => write!(f, "no finite samples"),
+        }
+    }
+}
+
+impl std::error::Error for MonteCarloError {}
+
+type Binder = Box<dyn Fn(&mut Env, f64)>;
+
+///
+/// Monte Carlo propagation of leaf distributions through a `LazyCherry` expression.
+///
+/// Every sample binds each declared leaf to a value drawn from its distribution and evaluates
+/// the expression. `run` returns node `(monte_carlo)` whose value is the mean of the samples,
+/// recording the sampling configuration (the number of samples, the seed and the distribution
+/// of every leaf), the tree evaluated at the first sample, and the `mean`, `stddev` and
+/// percentiles (e.g. `p95`) of the samples.
+/// Non-finite samples (e.g. divided by zero) are left out of the statistics
+/// and their count is recorded as `non_finite` (after `samples`, if there are any).
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Cherries;
+/// use cherries::lazy::symbol;
+/// use cherries::montecarlo::{Distribution, MonteCarlo};
+/// use cherries::export::{Tree, Value};
+/// use std::convert::TryFrom;
+/// extern crate uom;
+/// use uom::si::f64::*;
+///
+/// let area = (symbol::<Length>("b") * symbol::<Length>("h")).labeled("A");
+/// let result = MonteCarlo::new(2000)
+///     .seed(7)
+///     .leaf::<Length>("b", Distribution::Normal { mean: 0.3, stddev: 0.01 })
+///     .leaf::<Length>("h", Distribution::Uniform { low: 0.45, high: 0.55 })
+///     .run(&area)
+///     .unwrap();
+/// assert_eq!(result.name(), "(monte_carlo)");
+/// assert!((result.quantity().value - 0.15).abs() < 1e-3);
+///
+/// let tree = Tree::try_from(&result).unwrap();
+/// let labels = tree.subexpr.iter().map(|sub| sub.label.as_str()).collect::<Vec<_>>();
+/// assert_eq!(labels, vec!["samples", "seed", "b", "h", "A", "mean", "stddev", "p5", "p50", "p95"]);
+/// assert_eq!(tree.subexpr[0].value, Value::Number(2000.0));
+/// assert_eq!(tree.subexpr[2].value, Value::Text("normal(mean=0.3, stddev=0.01)".to_string()));
+/// assert_eq!(tree.subexpr[5].unit, "m^2".to_string());
+///
+/// // same seed, same samples
+/// let again = MonteCarlo::new(2000)
+///     .seed(7)
+///     .leaf::<Length>("b", Distribution::Normal { mean: 0.3, stddev: 0.01 })
+///     .leaf::<Length>("h", Distribution::Uniform { low: 0.45, high: 0.55 })
+///     .run(&area)
+///     .unwrap();
+/// assert_eq!(again.to_json(), result.to_json());
+/// assert!(MonteCarlo::new(10).leaf::<Length>("b", Distribution::Fixed(0.3)).run(&area).is_err());
+/// ```
+pub struct MonteCarlo {
+    samples: usize,
+    seed: u64,
+    percentiles: Vec<f64>,
+    leaves: Vec<(String, Distribution, Binder)>,
+}
+
+impl Debug for MonteCarlo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MonteCarlo")
+            .field("samples", &self.samples)
+            .field("seed", &self.seed)
+            .field("percentiles", &self.percentiles)
+            .field(
+                "leaves",
+                &self
+                    .leaves
+                    .iter()
+                    .map(|(name, distribution, _)| (name, distribution))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl MonteCarlo {
+    ///
+    /// Makes simulation of `samples` samples with seed `0` and percentiles 5, 50 and 95.
+    ///
+    pub fn new(samples: usize) -> Self {
+        MonteCarlo {
+            samples,
+            seed: 0,
+            percentiles: vec![5.0, 50.0, 95.0],
+            leaves: vec![],
+        }
+    }
+    ///
+    /// Returns simulation drawing samples from seed `seed`.
+    ///
+    pub fn seed(self, seed: u64) -> Self {
+        MonteCarlo { seed, ..self }
+    }
+    ///
+    /// Returns simulation recording `percentiles` (in percent) of the samples.
+    ///
+    pub fn percentiles(self, percentiles: &[f64]) -> Self {
+        MonteCarlo {
+            percentiles: percentiles.to_vec(),
+            ..self
+        }
+    }
+    ///
+    /// Returns simulation binding leaf `name` (of type `T`) to samples of `distribution`.
+    ///
+    pub fn leaf<T: Sampled>(mut self, name: &str, distribution: Distribution) -> Self {
+        let key = String::from(name);
+        let bind: Binder = Box::new(move |env, magnitude| {
+            env.set(key.clone(), T::from_magnitude(magnitude));
+        });
+        self.leaves.push((String::from(name), distribution, bind));
+        self
+    }
+    ///
+    /// Samples `expr` and returns node `(monte_carlo)` recording the statistics of the samples.
+    ///
+    pub fn run<T: Sampled>(&self, expr: &LazyCherry<T>) -> Result<Cherry<T>, MonteCarloError> {
+        if self.samples == 0 {
+            return Err(MonteCarloError::NoSamples);
+        }
+        let mut rng = Rng(self.seed);
+        let mut env = Env::new();
+        let mut first = None;
+        let mut values = Vec::with_capacity(self.samples);
+        for _ in 0..self.samples {
+            for (_, distribution, bind) in &self.leaves {
+                bind(&mut env, distribution.sample(&mut rng));
+            }
+            let node = match expr.evaluate(&env) {
+                Some(node) => node,
+                None => {
+                    let unbound = expr.unbound(&env).into_iter().map(String::from).collect();
+                    return Err(MonteCarloError::Unbound(unbound));
+                }
+            };
+            values.push(node.quantity().magnitude());
+            if first.is_none() {
+                first = Some(node);
+            }
+        }
+        let drawn = values.len();
+        values.retain(|value| value.is_finite());
+        let non_finite = drawn - values.len();
+        if values.is_empty() {
+            return Err(MonteCarloError::NonFinite);
+        }
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = if values.len() > 1 {
+            values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (count - 1.0)
+        } else {
+            0.0
+        };
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let statistic = |name: String, magnitude: f64| {
+            Leaf::new()
+                .name(name)
+                .value(T::from_magnitude(magnitude))
+                .build()
+                .as_subexpr()
+        };
+        let mut children = vec![
+            Leaf::new().name("samples").value(self.samples).build().as_subexpr(),
+        ];
+        if non_finite > 0 {
+            children.push(Leaf::new().name("non_finite").value(non_finite).build().as_subexpr());
+        }
+        children.push(Leaf::new().name("seed").value(self.seed).build().as_subexpr());
+        for (name, distribution, _) in &self.leaves {
+            children.push(Leaf::new().name(name.clone()).value(*distribution).build().as_subexpr());
+        }
+        if let Some(first) = first {
+            children.push(first.as_subexpr());
+        }
+        children.push(statistic(String::from("mean"), mean));
+        children.push(statistic(String::from("stddev"), variance.sqrt()));
+        for &percentile in &self.percentiles {
+            // nearest rank
+            let rank = (percentile / 100.0 * count).ceil().max(1.0).min(count) as usize;
+            children.push(statistic(format!("p{}", percentile), values[rank - 1]));
+        }
+        Ok(Node::new()
+            .name("(monte_carlo)")
+            .op("monte_carlo")
+            .value(T::from_magnitude(mean))
+            .children(children)
+            .build())
+    }
+}
@@ -1,36 +1,902 @@
 extern crate uom;
 extern crate serde;
-use std::fmt;
+use alloc::{borrow::Cow, collections::BTreeMap, format, string::{String, ToString}, sync::Arc, vec, vec::Vec};
+use core::fmt;
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use serde::de::{self, Deserialize, Deserializer, Visitor, SeqAccess, MapAccess};
-use regex::Regex;
-use std::fmt::Debug;
+use core::fmt::Debug;
+use crate::attachment::{json_attachments, Attachment, Source};
+use crate::export::Tree;
+use crate::validate::Finding;
+#[cfg(feature = "std")]
+use crate::session::notify;
+#[cfg(feature = "std")]
+pub(crate) use crate::session::check_invariants;
+
+// Without `std` there is no session to notify.
+#[cfg(not(feature = "std"))]
+fn notify<T: Clone + Debug>(_: &Cherry<T>) {}
+
+// Without `std` there is no session to check invariants of.
+#[cfg(not(feature = "std"))]
+pub(crate) fn check_invariants<T: Clone + Debug>(_: &Cherry<T>) {}
+
+///
+/// Node label.
+///
+/// String literals are borrowed, so labeling nodes with them does not allocate.
+/// Other borrowed strings are passed as owned `String`s (e.g. `label.to_string()`).
+///
+pub type Label = Cow<'static, str>;
+
+// Returns `s` as json string literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+// Returns `parts` joined by `sep` (slices are joined by `std` only, so `join` is unavailable without it).
+pub(crate) fn join<S: AsRef<str>>(parts: &[S], sep: &str) -> String {
+    let mut ret = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            ret.push_str(sep);
+        }
+        ret.push_str(part.as_ref());
+    }
+    ret
+}
+
+// Returns `v` as json number, or as string if not finite.
+pub(crate) fn raw_json_number(v: f32) -> String {
+    if v.is_finite() {
+        v.to_string()
+    } else {
+        json_string(&v.to_string())
+    }
+}
+
+///
+/// Exponents of the ISQ base quantities (length, mass, time, electric current,
+/// thermodynamic temperature, amount of substance and luminous intensity).
+///
+/// Written in json as object with non-zero exponents only, e.g. `{"L":-1,"M":1,"T":-2}`.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Dimensions {
+    #[serde(rename = "L", default, skip_serializing_if = "is_zero")]
+    pub length: i8,
+    #[serde(rename = "M", default, skip_serializing_if = "is_zero")]
+    pub mass: i8,
+    #[serde(rename = "T", default, skip_serializing_if = "is_zero")]
+    pub time: i8,
+    #[serde(rename = "I", default, skip_serializing_if = "is_zero")]
+    pub electric_current: i8,
+    #[serde(rename = "Th", default, skip_serializing_if = "is_zero")]
+    pub thermodynamic_temperature: i8,
+    #[serde(rename = "N", default, skip_serializing_if = "is_zero")]
+    pub amount_of_substance: i8,
+    #[serde(rename = "J", default, skip_serializing_if = "is_zero")]
+    pub luminous_intensity: i8,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(exponent: &i8) -> bool {
+    *exponent == 0
+}
+
+impl Dimensions {
+    ///
+    /// Parses dimensions from unit symbol written by `Cherries::symbol` (e.g. `m^-1 kg^1 s^-2`).
+    ///
+    /// Returns `None` if `symbol` is not made of base unit exponents.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Dimensions;
+    ///
+    /// let pressure = Dimensions::from_symbol("m^-1 kg^1 s^-2").unwrap();
+    /// assert_eq!((pressure.length, pressure.mass, pressure.time), (-1, 1, -2));
+    /// assert!(Dimensions::from_symbol("dimensionless").unwrap().is_dimensionless());
+    /// assert_eq!(Dimensions::from_symbol("mm"), None);
+    /// ```
+    pub fn from_symbol(symbol: &str) -> Option<Dimensions> {
+        let mut dimensions = Dimensions::default();
+        if symbol == "dimensionless" {
+            return Some(dimensions);
+        }
+        for token in symbol.split(' ') {
+            let mut parts = token.splitn(2, '^');
+            let (base, exponent) = (parts.next()?, parts.next()?.parse::<i8>().ok()?);
+            let slot = match base {
+                "m" => &mut dimensions.length,
+                "kg" => &mut dimensions.mass,
+                "s" => &mut dimensions.time,
+                "A" => &mut dimensions.electric_current,
+                "K" => &mut dimensions.thermodynamic_temperature,
+                "mol" => &mut dimensions.amount_of_substance,
+                "cd" => &mut dimensions.luminous_intensity,
+                _ => return None,
+            };
+            *slot = exponent;
+        }
+        Some(dimensions)
+    }
+    ///
+    /// Returns true if all exponents are zero.
+    ///
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Dimensions::default()
+    }
+    // Unit symbol in base units, as written by `Cherries::symbol` (inverse of `from_symbol`).
+    pub(crate) fn symbol(&self) -> String {
+        let exponents = [
+            ("m", self.length),
+            ("kg", self.mass),
+            ("s", self.time),
+            ("A", self.electric_current),
+            ("K", self.thermodynamic_temperature),
+            ("mol", self.amount_of_substance),
+            ("cd", self.luminous_intensity),
+        ];
+        if self.is_dimensionless() {
+            return "dimensionless".to_string();
+        }
+        let exponents = exponents
+            .iter()
+            .filter(|(_, exponent)| *exponent != 0)
+            .map(|(base, exponent)| format!("{}^{}", base, exponent))
+            .collect::<Vec<_>>();
+        join(&exponents, " ")
+    }
+    // Json object of non-zero exponents.
+    pub(crate) fn to_json(&self) -> String {
+        let exponents = [
+            ("L", self.length),
+            ("M", self.mass),
+            ("T", self.time),
+            ("I", self.electric_current),
+            ("Th", self.thermodynamic_temperature),
+            ("N", self.amount_of_substance),
+            ("J", self.luminous_intensity),
+        ];
+        let exponents = exponents
+            .iter()
+            .filter(|(_, exponent)| *exponent != 0)
+            .map(|(name, exponent)| format!("\"{}\":{}", name, exponent))
+            .collect::<Vec<_>>();
+        format!("{{{}}}", join(&exponents, ","))
+    }
+}
+
+///
+/// Types whose values have fixed dimensions (uom quantities, and plain numbers as dimensionless).
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Dimensional, Dimensions};
+/// extern crate uom;
+/// use uom::si::f32::*;
+///
+/// assert_eq!(Pressure::dimensions(), Dimensions::from_symbol("m^-1 kg^1 s^-2").unwrap());
+/// assert!(Ratio::dimensions().is_dimensionless());
+/// ```
+pub trait Dimensional {
+    fn dimensions() -> Dimensions;
+}
+
+impl<D, U, V> Dimensional for uom::si::Quantity<D, U, V>
+where
+    D: uom::si::Dimension + ?Sized,
+    U: uom::si::Units<V> + ?Sized,
+    V: uom::num::Num + uom::Conversion<V>,
+{
+    fn dimensions() -> Dimensions {
+        use uom::typenum::Integer;
+        Dimensions {
+            length: D::L::to_i8(),
+            mass: D::M::to_i8(),
+            time: D::T::to_i8(),
+            electric_current: D::I::to_i8(),
+            thermodynamic_temperature: D::Th::to_i8(),
+            amount_of_substance: D::N::to_i8(),
+            luminous_intensity: D::J::to_i8(),
+        }
+    }
+}
+
+macro_rules! impl_dimensionless {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl Dimensional for $t {
+                fn dimensions() -> Dimensions {
+                    Dimensions::default()
+                }
+            }
+        )*
+    };
+}
+
+impl_dimensionless!(f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// `,"dimensions":{..}` for dimensional `symbol`, otherwise empty.
+pub(crate) fn json_dimensions(dimensions: Option<Dimensions>) -> String {
+    match dimensions {
+        Some(dimensions) if !dimensions.is_dimensionless() => {
+            format!(",\"dimensions\":{}", dimensions.to_json())
+        }
+        _ => String::new(),
+    }
+}
 
 ///
 /// Trait for active expression node.
 ///
 pub trait Cherries {
-    fn name(&self) -> &String;
-    fn value(&self) -> std::result::Result<f32, String>;
+    fn name(&self) -> &str;
+    fn value(&self) -> core::result::Result<f32, String>;
     fn symbol(&self) -> String;
     fn to_json(&self) -> String;
+    ///
+    /// Returns exponents of base quantities (`None` if the unit symbol is not in base units).
+    ///
+    fn dimensions(&self) -> Option<Dimensions> {
+        Dimensions::from_symbol(&self.symbol())
+    }
+    ///
+    /// Returns json of the tree down to depth `max_depth`, as `Cherry::to_json_with_depth` does.
+    ///
+    fn to_json_with_depth(&self, max_depth: usize) -> String {
+        Tree::from_json(&self.to_json())
+            .map(|tree| crate::units::render(&tree.truncated(max_depth)))
+            .unwrap_or_else(|_| self.to_json())
+    }
+    #[doc(hidden)]
+    fn as_subexpr(&self) -> Subexpr {
+        Subexpr(Part::Json(Arc::from(self.to_json())))
+    }
+}
+
+///
+/// Subexpression recorded in a node, shared with the node it was made from.
+///
+#[doc(hidden)]
+#[derive(Clone, Debug)]
+pub struct Subexpr(Part);
+
+#[derive(Clone, Debug)]
+enum Part {
+    // Json of subexpressions joined by commas.
+    Json(Arc<str>),
+    Node(Arc<Json>),
+}
+
+impl Subexpr {
+    // Json of the subexpression (with all its subexpressions).
+    pub(crate) fn to_json(&self) -> String {
+        match &self.0 {
+            Part::Json(json) => json.to_string(),
+            Part::Node(node) => {
+                let mut out = String::new();
+                node.render(&mut out);
+                out
+            }
+        }
+    }
+}
+
+// Part of a tree visited by `Cherry::walk`.
+pub(crate) enum Visit<'a> {
+    // Node with json before its subexpressions, attachments and count of subexpressions
+    // visited (`None` for leaves and nodes at the deepest level).
+    Node {
+        depth: usize,
+        head: &'a str,
+        // Read by bundles only, which need `std`.
+        #[cfg_attr(not(feature = "std"), allow(dead_code))]
+        attachments: &'a [Attachment],
+        subexpr: Option<usize>,
+    },
+    // Subexpressions recorded as json text (joined by commas) starting at `depth`.
+    Json { depth: usize, json: &'a str },
+}
+
+// Step of `Cherry::traverse`.
+pub(crate) enum Step<'a> {
+    // Node with json before its subexpressions, entered before them.
+    Enter(&'a str),
+    // Subexpressions recorded as json text (joined by commas).
+    Json(&'a str),
+    // Leaves the node entered last.
+    Leave,
+}
+
+// Json of a node, sharing its subexpressions with other nodes.
+#[derive(Debug)]
+struct Json {
+    // `{"label":..,"value":..,"unit":..` without subexpressions and closing brace.
+    head: String,
+    meta: Meta,
+    subexpr: Option<Vec<Subexpr>>,
+}
+
+// Metadata written in the head of a node after its unit, kept when relabeled.
+#[derive(Clone, Debug, Default)]
+struct Meta {
+    op: Op,
+    origin: Option<Origin>,
+    comment: Option<String>,
+    description: Option<String>,
+    sig_figs: Option<u32>,
+    tags: BTreeMap<String, String>,
+    // `file:line:column` the node was built at (with feature `track-caller`).
+    location: Option<String>,
+    // RFC 3339 time (UTC) the node was built at (with feature `timestamps`).
+    timestamp: Option<String>,
+    // Unique id of the node in the process.
+    id: Option<u64>,
+    // Key telling the node (and its clones and relabeled copies) apart from other nodes.
+    key: usize,
+    // Clauses of standards the node follows, in the order recorded.
+    references: Vec<String>,
+    // Whether subexpressions may be collapsed in output.
+    detail: bool,
+    attachments: Vec<Attachment>,
+    // Warnings and notes of validation checks, in the order checked.
+    findings: Vec<Finding>,
+}
+
+// `["..",..]` of `strings`.
+pub(crate) fn json_strings(strings: &[String]) -> String {
+    let strings = strings.iter().map(|s| json_string(s)).collect::<Vec<_>>();
+    format!("[{}]", join(&strings, ","))
+}
+
+// `[{"severity":"..","msg":".."},..]` of `findings`.
+pub(crate) fn json_findings(findings: &[Finding]) -> String {
+    let findings = findings
+        .iter()
+        .map(|finding| {
+            format!(
+                "{{\"severity\":\"{}\",\"msg\":{}}}",
+                finding.severity.as_str(),
+                json_string(&finding.msg)
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", join(&findings, ","))
+}
+
+// `{"key":"value",..}` of `tags`.
+pub(crate) fn json_tags(tags: &BTreeMap<String, String>) -> String {
+    let pairs = tags
+        .iter()
+        .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", join(&pairs, ","))
+}
+
+#[cfg(feature = "track-caller")]
+std::thread_local! {
+    static RECORD_LOCATIONS: core::cell::Cell<bool> = core::cell::Cell::new(false);
+}
+
+#[cfg(feature = "track-caller")]
+///
+/// Sets whether nodes built on the current thread record where they are built
+/// (see `Cherry::location`), and returns the previous setting.
+///
+/// Locations are not recorded by default, so json of unchanged calculations stays the same
+/// when code around them moves.
+///
+pub fn set_record_locations(record: bool) -> bool {
+    RECORD_LOCATIONS.with(|current| current.replace(record))
+}
+
+#[cfg(feature = "timestamps")]
+std::thread_local! {
+    static RECORD_TIMESTAMPS: core::cell::Cell<bool> = core::cell::Cell::new(false);
+}
+
+#[cfg(feature = "timestamps")]
+///
+/// Sets whether nodes built on the current thread record when they are built
+/// (see `Cherry::timestamp`), and returns the previous setting.
+///
+/// Timestamps are not recorded by default, so json of repeated calculations stays the same.
+///
+pub fn set_record_timestamps(record: bool) -> bool {
+    RECORD_TIMESTAMPS.with(|current| current.replace(record))
+}
+
+// Time `since` the Unix epoch as RFC 3339 in UTC with milliseconds, e.g. `2020-03-01T12:00:00.000Z`.
+#[cfg(feature = "timestamps")]
+pub(crate) fn rfc3339(since: core::time::Duration) -> String {
+    let (days, seconds) = (since.as_secs() / 86400, since.as_secs() % 86400);
+    // civil date of days since 1970-01-01 in eras of 400 years from 0000-03-01
+    let z = days + 719_468;
+    let (era, day_of_era) = (z / 146_097, z % 146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since.subsec_millis()
+    )
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static RECORD_IDS: core::cell::Cell<bool> = core::cell::Cell::new(false);
+}
+
+// Last key (and id) given to a node, shared by all threads.
+#[cfg(feature = "std")]
+static LAST_ID: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+#[cfg(feature = "std")]
+///
+/// Sets whether nodes built on the current thread are given ids (see `Cherry::id`),
+/// and returns the previous setting.
+///
+/// Ids are not given by default, so json of repeated calculations stays the same.
+///
+pub fn set_record_ids(record: bool) -> bool {
+    RECORD_IDS.with(|current| current.replace(record))
+}
+
+// Key of a new node, unique in the process.
+#[cfg(feature = "std")]
+fn next_key() -> usize {
+    LAST_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed) + 1
+}
+
+// Without `std` there is no session to tell nodes apart for.
+#[cfg(not(feature = "std"))]
+fn next_key() -> usize {
+    0
+}
+
+// Id of the node of `key`, if recorded.
+fn id_of(key: usize) -> Option<u64> {
+    #[cfg(feature = "std")]
+    {
+        if RECORD_IDS.with(|record| record.get()) {
+            return Some(key as u64);
+        }
+    }
+    #[cfg(not(feature = "std"))]
+    let _ = key;
+    None
+}
+
+// `file:line:column` of the caller, if recorded.
+#[cfg_attr(feature = "track-caller", track_caller)]
+fn caller_location() -> Option<String> {
+    #[cfg(feature = "track-caller")]
+    {
+        if RECORD_LOCATIONS.with(|record| record.get()) {
+            let location = core::panic::Location::caller();
+            return Some(format!(
+                "{}:{}:{}",
+                location.file(),
+                location.line(),
+                location.column()
+            ));
+        }
+    }
+    None
+}
+
+// Current time, if recorded.
+fn now() -> Option<String> {
+    #[cfg(feature = "timestamps")]
+    {
+        if RECORD_TIMESTAMPS.with(|record| record.get()) {
+            return Some(rfc3339(crate::timing::wall_now()));
+        }
+    }
+    None
+}
+
+impl Meta {
+    // Metadata of a node built by the caller (recording where with feature `track-caller`
+    // and when with feature `timestamps`).
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    fn created() -> Meta {
+        let key = next_key();
+        Meta {
+            location: caller_location(),
+            timestamp: now(),
+            id: id_of(key),
+            key,
+            ..Meta::default()
+        }
+    }
+    fn to_json(&self) -> String {
+        let mut json = String::new();
+        if self.op != Op::Leaf {
+            json.push_str(&format!(",\"op\":{}", json_string(self.op.as_str())));
+        }
+        if let Some(origin) = self.origin {
+            json.push_str(&format!(",\"origin\":\"{}\"", origin.as_str()));
+        }
+        if let Some(comment) = &self.comment {
+            json.push_str(&format!(",\"comment\":{}", json_string(comment)));
+        }
+        if let Some(description) = &self.description {
+            json.push_str(&format!(",\"description\":{}", json_string(description)));
+        }
+        if let Some(sig_figs) = self.sig_figs {
+            json.push_str(&format!(",\"sig_figs\":{}", sig_figs));
+        }
+        if !self.tags.is_empty() {
+            json.push_str(&format!(",\"meta\":{}", json_tags(&self.tags)));
+        }
+        if let Some(location) = &self.location {
+            json.push_str(&format!(",\"location\":{}", json_string(location)));
+        }
+        if let Some(timestamp) = &self.timestamp {
+            json.push_str(&format!(",\"timestamp\":{}", json_string(timestamp)));
+        }
+        if let Some(id) = self.id {
+            json.push_str(&format!(",\"id\":{}", id));
+        }
+        if !self.references.is_empty() {
+            json.push_str(&format!(",\"references\":{}", json_strings(&self.references)));
+        }
+        if self.detail {
+            json.push_str(",\"detail\":true");
+        }
+        json.push_str(&json_attachments(&self.attachments));
+        if !self.findings.is_empty() {
+            json.push_str(&format!(",\"findings\":{}", json_findings(&self.findings)));
+        }
+        json
+    }
+}
+
+///
+/// Operation a node was made by, so tools can interpret trees without parsing labels
+/// (which relabeled nodes no longer show).
+///
+/// Recorded by the operation building the node (and kept when relabeled).
+/// Written in json as `"op":"mul"` (`add`, `sub`, `div`, `fold`, `map` or the name of
+/// another operation, e.g. `clamp` or `mean`); leaves, and nodes built without an operation
+/// (e.g. from archived json), have no `op`.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate cherries;
+/// extern crate serde_json;
+/// use cherries::node::{Cherries, Cherry, Leaf, Op};
+/// use cherries::export::Tree;
+/// use std::convert::TryFrom;
+///
+/// # fn main() {
+/// let a = Leaf::new().value(2.0).name("a").build();
+/// let b = Leaf::new().value(3.0).name("b").build();
+/// let area = (a.clone() * b.clone()).labeled("area");
+/// assert_eq!(area.op(), &Op::Mul);
+/// assert_eq!(a.op(), &Op::Leaf);
+/// assert_eq!(sum_all!(a.clone(), b.clone()).op(), &Op::Fold);
+/// assert_eq!(mean!(a.clone(), b.clone()).labeled("b_avg").op(), &Op::Other(String::from("mean")));
+/// assert_eq!(a.map(|a| a * 2.0).op(), &Op::Map);
+/// assert_eq!(
+///     area.to_json(),
+///     "{\"label\":\"area\",\"value\":6,\"unit\":\"dimensionless\",\"op\":\"mul\",\"subexpr\":[\
+///         {\"label\":\"a\",\"value\":2,\"unit\":\"dimensionless\"},\
+///         {\"label\":\"b\",\"value\":3,\"unit\":\"dimensionless\"}]}"
+/// );
+/// let tree = Tree::try_from(&area).unwrap();
+/// assert_eq!(tree.op, Some(Op::Mul));
+/// assert_eq!(tree.subexpr[0].op, None);
+/// let restored: Cherry<f64> = serde_json::from_str(&serde_json::to_string(&area).unwrap()).unwrap();
+/// assert_eq!(restored.op(), &Op::Mul);
+/// assert_eq!(Op::from("clamp"), Op::Other(String::from("clamp")));
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Op {
+    /// Leaf (input) of the tree.
+    Leaf,
+    /// `+`.
+    Add,
+    /// `-`.
+    Sub,
+    /// `*`.
+    Mul,
+    /// `/`.
+    Div,
+    /// Fold of several nodes by `sum_all!`, `prod_all!`, `minimum!`, `maximum!`, `fold_all!` or `fold_cherries`.
+    Fold,
+    /// `Cherry::map`.
+    Map,
+    /// Other operation, by name.
+    Other(String),
+}
+
+impl Default for Op {
+    fn default() -> Self {
+        Op::Leaf
+    }
+}
+
+impl Op {
+    ///
+    /// Returns name written in json.
+    ///
+    pub fn as_str(&self) -> &str {
+        match self {
+            Op::Leaf => "leaf",
+            Op::Add => "add",
+            Op::Sub => "sub",
+            Op::Mul => "mul",
+            Op::Div => "div",
+            Op::Fold => "fold",
+            Op::Map => "map",
+            Op::Other(name) => name,
+        }
+    }
+}
+
+impl From<String> for Op {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "leaf" => Op::Leaf,
+            "add" => Op::Add,
+            "sub" => Op::Sub,
+            "mul" => Op::Mul,
+            "div" => Op::Div,
+            "fold" => Op::Fold,
+            "map" => Op::Map,
+            _ => Op::Other(name),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Op {
+    fn from(name: &'a str) -> Self {
+        Op::from(String::from(name))
+    }
+}
+
+impl From<Op> for String {
+    fn from(op: Op) -> Self {
+        op.as_str().to_string()
+    }
+}
+
+///
+/// Where the value of a node comes from, shown by exports (e.g. `Tree::to_dot`)
+/// so reviewers see how much of a result rests on assumptions.
+///
+/// Written in json as `"origin":"measured"` (`assumed`, `standard`, `computed`).
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    /// Measured or tested.
+    Measured,
+    /// Assumed by the engineer.
+    Assumed,
+    /// Taken from a standard or code of practice.
+    Standard,
+    /// Result of another calculation.
+    Computed,
+}
+
+impl Origin {
+    ///
+    /// Returns name written in json.
+    ///
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Origin::Measured => "measured",
+            Origin::Assumed => "assumed",
+            Origin::Standard => "standard",
+            Origin::Computed => "computed",
+        }
+    }
+    ///
+    /// Returns fill color of nodes from this origin in exported graphs.
+    ///
+    pub fn color(self) -> &'static str {
+        match self {
+            Origin::Measured => "#b3e2cd",
+            Origin::Assumed => "#fdcdac",
+            Origin::Standard => "#cbd5e8",
+            Origin::Computed => "#e6e6e6",
+        }
+    }
+}
+
+// Count of trees recorded in `subexpr` (json text may hold several).
+fn count_trees(subexpr: &[Subexpr]) -> usize {
+    subexpr
+        .iter()
+        .map(|Subexpr(part)| match part {
+            Part::Json(json) => serde_json::from_str::<Vec<Tree>>(&format!("[{}]", json))
+                .ok()
+                .map_or(0, |trees| trees.len()),
+            Part::Node(_) => 1,
+        })
+        .sum()
+}
+
+impl Json {
+    // Writes json of the node with all its subexpressions to `out`.
+    fn render(&self, out: &mut String) {
+        self.render_to_depth(out, usize::max_value())
+    }
+    // Writes json of the node with subexpressions down to depth `max_depth` to `out`,
+    // nodes at that depth with count of their subexpressions as `"collapsed"` instead.
+    // Deep trees are walked without recursion.
+    fn render_to_depth(&self, out: &mut String, max_depth: usize) {
+        enum Step<'a> {
+            Node(&'a Json, usize),
+            Text(&'a str),
+            Owned(String),
+        }
+        let mut stack = vec![Step::Node(self, 0)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Text(text) => out.push_str(text),
+                Step::Owned(text) => out.push_str(&text),
+                Step::Node(node, depth) => {
+                    out.push_str(&node.head);
+                    match &node.subexpr {
+                        Some(subexpr) if depth >= max_depth => {
+                            out.push_str(&format!(",\"collapsed\":{}}}", count_trees(subexpr)));
+                        }
+                        Some(subexpr) => {
+                            out.push_str(",\"subexpr\":[");
+                            stack.push(Step::Text("]}"));
+                            for (i, Subexpr(part)) in subexpr.iter().enumerate().rev() {
+                                stack.push(match part {
+                                    Part::Json(json) if max_depth == usize::max_value() => {
+                                        Step::Text(json)
+                                    }
+                                    Part::Json(json) => {
+                                        Step::Owned(truncate(json, max_depth - depth - 1))
+                                    }
+                                    Part::Node(node) => Step::Node(node, depth + 1),
+                                });
+                                if i > 0 {
+                                    stack.push(Step::Text(","));
+                                }
+                            }
+                        }
+                        None => out.push('}'),
+                    }
+                }
+            }
+        }
+    }
+    // Enters and leaves every node (without recursion).
+    fn traverse<F: FnMut(Step<'_>)>(&self, mut f: F) {
+        enum Todo<'a> {
+            Enter(&'a Json),
+            Json(&'a str),
+            Leave,
+        }
+        let mut stack = vec![Todo::Enter(self)];
+        while let Some(todo) = stack.pop() {
+            match todo {
+                Todo::Enter(node) => {
+                    f(Step::Enter(&node.head));
+                    stack.push(Todo::Leave);
+                    for Subexpr(part) in node.subexpr.iter().flatten().rev() {
+                        stack.push(match part {
+                            Part::Json(json) => Todo::Json(json),
+                            Part::Node(node) => Todo::Enter(node),
+                        });
+                    }
+                }
+                Todo::Json(json) => f(Step::Json(json)),
+                Todo::Leave => f(Step::Leave),
+            }
+        }
+    }
+    // Visits nodes up to `max_depth` (without recursion).
+    fn walk<F: FnMut(Visit<'_>)>(&self, max_depth: usize, mut f: F) {
+        let mut stack = vec![(0, self)];
+        while let Some((depth, node)) = stack.pop() {
+            let subexpr = node.subexpr.as_ref().filter(|_| depth < max_depth);
+            f(Visit::Node {
+                depth,
+                head: &node.head,
+                attachments: &node.meta.attachments,
+                subexpr: subexpr.map(Vec::len),
+            });
+            for Subexpr(part) in subexpr.into_iter().flatten().rev() {
+                match part {
+                    Part::Json(json) => f(Visit::Json { depth: depth + 1, json }),
+                    Part::Node(node) => stack.push((depth + 1, node)),
+                }
+            }
+        }
+    }
+}
+
+// Json text of subexpressions (joined by commas) down to depth `max_depth` below them.
+fn truncate(json: &str, max_depth: usize) -> String {
+    match serde_json::from_str::<Vec<Tree>>(&format!("[{}]", json)) {
+        Ok(trees) => join(
+            &trees
+                .iter()
+                .map(|tree| crate::units::render(&tree.truncated(max_depth)))
+                .collect::<Vec<_>>(),
+            ",",
+        ),
+        // kept as recorded
+        Err(_) => String::from(json),
+    }
+}
+
+// Drops deep trees without recursion.
+impl Drop for Json {
+    fn drop(&mut self) {
+        let mut stack = core::mem::replace(&mut self.subexpr, None).unwrap_or_default();
+        while let Some(Subexpr(part)) = stack.pop() {
+            if let Part::Node(node) = part {
+                if let Ok(mut node) = Arc::try_unwrap(node) {
+                    stack.extend(core::mem::replace(&mut node.subexpr, None).unwrap_or_default());
+                }
+            }
+        }
+    }
 }
 
 ///
 /// Expression node.
+/// Subexpressions are shared (not copied) with the nodes they were made from,
+/// so cloning a node or using a big subtree in several expressions is cheap.
+/// Json of each node is rendered once when it is made.
 ///
+#[must_use]
 #[derive(Clone, Debug)]
 pub struct Cherry<T: Clone + Debug> {
-    label: String,
+    label: Label,
     value: T,
-    previous: Option<String>,
+    json: Arc<Json>,
 }
 
 impl<T: Clone + Debug + PartialEq> PartialEq for Cherry<T> {
     fn eq(&self, other: &Self) -> bool {
         (self.label == other.label)
             && (self.value == other.value)
-            && (self.previous == other.previous)
+            && (Arc::ptr_eq(&self.json, &other.json) || self.previous() == other.previous())
     }
 }
 
@@ -39,30 +905,44 @@ impl<T: Clone + Debug + Serialize> Serialize for Cherry<T> {
     where
         S: Serializer
     {
-        let mut state = serializer.serialize_struct("Cherry", 3)?;
+        // `op` is `None` for leaves, but always written so formats without field names
+        // (e.g. bincode) read back the same number of fields.
+        let op = Some(self.op()).filter(|op| **op != Op::Leaf);
+        let mut state = serializer.serialize_struct("Cherry", 4)?;
         state.serialize_field("label", &self.label)?;
         state.serialize_field("value", &self.value)?;
-        state.serialize_field("previous", &self.previous)?;
+        state.serialize_field("previous", &self.previous())?;
+        state.serialize_field("op", &op)?;
         state.end()
     }
 }
 
 #[derive(Clone, Debug)]
 struct CherryVisitor<T: Clone + Debug> {
-    value_type: std::marker::PhantomData<T>,
+    value_type: core::marker::PhantomData<T>,
 }
 
 impl<'de, T: Clone + Debug + Deserialize<'de>> CherryVisitor<T> {
     fn new() -> Self {
-        CherryVisitor { value_type: std::marker::PhantomData }
+        CherryVisitor { value_type: core::marker::PhantomData }
+    }
+}
+
+// `previous` is written into `to_json` as is, so it must be trees joined by commas.
+fn check_previous<E: de::Error>(previous: &Option<String>) -> Result<(), E> {
+    match previous {
+        Some(previous) => Tree::list_from_json(previous)
+            .map(|_| ())
+            .map_err(|err| de::Error::custom(format!("invalid previous: {}", err))),
+        None => Ok(()),
     }
 }
 
 impl<'de, T: Clone + Debug + Deserialize<'de>> serde::de::Visitor<'de> for CherryVisitor<T> {
     type Value = Cherry<T>;
 
-    fn expecting(&self, _: &mut std::fmt::Formatter<'_>) -> fmt::Result {
-        unimplemented!()
+    fn expecting(&self, formatter: &mut core::fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("struct Cherry")
     }
 
     fn visit_seq<V>(self, mut seq: V) -> Result<Cherry<T>, V::Error>
@@ -75,14 +955,16 @@ impl<'de, T: Clone + Debug + Deserialize<'de>> serde::de::Visitor<'de> for Cherr
             .ok_or_else(|| de::Error::invalid_length(1, &self))?;
         let previous = seq.next_element()?
             .ok_or_else(|| de::Error::invalid_length(2, &self))?;
-        Ok(Cherry{label, value, previous})
+        let op = seq.next_element()?.unwrap_or(None);
+        check_previous(&previous)?;
+        Ok(Cherry::from_previous(label, value, op, previous))
     }
 
     fn visit_map<V>(self, mut map: V) -> Result<Cherry<T>, V::Error>
     where
         V: MapAccess<'de>,
     {
-        enum Field { Label, Value, Previous };
+        enum Field { Label, Value, Previous, Op };
         impl<'de> Deserialize<'de> for Field {
             fn deserialize<D>(deserializer: D) -> Result<Field, D::Error>
             where
@@ -94,7 +976,7 @@ impl<'de, T: Clone + Debug + Deserialize<'de>> serde::de::Visitor<'de> for Cherr
                     type Value = Field;
 
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("`label`, `value`, or `previous`")
+                        formatter.write_str("`label`, `value`, `previous` or `op`")
                     }
 
                     fn visit_str<E>(self, value: &str) -> Result<Field, E>
@@ -105,7 +987,8 @@ impl<'de, T: Clone + Debug + Deserialize<'de>> serde::de::Visitor<'de> for Cherr
                             "label" => Ok(Field::Label),
                             "value" => Ok(Field::Value),
                             "previous" => Ok(Field::Previous),
-                            _ => Err(de::Error::unknown_field(value, &["label", "value", "previous"])),
+                            "op" => Ok(Field::Op),
+                            _ => Err(de::Error::unknown_field(value, &["label", "value", "previous", "op"])),
                         }
                     }
                 }
@@ -116,6 +999,7 @@ impl<'de, T: Clone + Debug + Deserialize<'de>> serde::de::Visitor<'de> for Cherr
         let mut label = None;
         let mut value = None;
         let mut previous = None;
+        let mut op = None;
         while let Some(key) = map.next_key()? {
             match key {
                 Field::Label => {
@@ -136,12 +1020,19 @@ impl<'de, T: Clone + Debug + Deserialize<'de>> serde::de::Visitor<'de> for Cherr
                     }
                     previous = Some(map.next_value()?);
                 }
+                Field::Op => {
+                    if op.is_some() {
+                        return Err(de::Error::duplicate_field("op"));
+                    }
+                    op = Some(map.next_value::<Option<Op>>()?);
+                }
             }
         }
         let label = label.ok_or_else(|| de::Error::missing_field("label"))?;
         let value = value.ok_or_else(|| de::Error::missing_field("value"))?;
         let previous = previous.ok_or_else(|| de::Error::missing_field("previous"))?;
-        Ok(Cherry{label, value, previous})
+        check_previous(&previous)?;
+        Ok(Cherry::from_previous(label, value, op.and_then(|op| op), previous))
     }
 }
 
@@ -150,13 +1041,536 @@ impl<'de, T: Clone + Debug + Deserialize<'de>> Deserialize<'de> for Cherry<T> {
     where
         D: Deserializer<'de>,
     {
-        const FIELDS: &[&str] = &["label", "value", "previous"];
+        // `op` may be left out by self-describing formats (e.g. json written before it was recorded).
+        const FIELDS: &[&str] = &["label", "value", "previous", "op"];
         let visitor: CherryVisitor<T> = CherryVisitor::new();
-        deserializer.deserialize_struct("Duration", FIELDS, visitor)
+        deserializer.deserialize_struct("Cherry", FIELDS, visitor)
+    }
+}
+
+// Splits a line of debug format `{value} {symbol}` at its first space.
+fn split_quantity(formats: &str) -> Option<(&str, &str)> {
+    formats
+        .find(' ')
+        .map(|space| (&formats[..space], &formats[space + 1..]))
+}
+
+// Debug format of `value` without uncertainty, and the uncertainty
+// (written by values like `crate::uncertain::Uncertain` as `{value} ± {uncertainty} {symbol}`).
+fn debug_of<T: Debug>(value: &T) -> (String, Option<f32>) {
+    const MARK: &str = " ± ";
+    let formats = format!("{:?}", value);
+    let mark = match formats.find(MARK) {
+        Some(mark) if !formats.contains('\n') => mark,
+        _ => return (formats, None),
+    };
+    let rest = &formats[mark + MARK.len()..];
+    let (uncertainty, symbol) = match split_quantity(rest) {
+        Some((uncertainty, symbol)) => (uncertainty, Some(symbol)),
+        None => (rest, None),
+    };
+    match uncertainty.parse::<f32>() {
+        Ok(uncertainty) => {
+            let value = &formats[..mark];
+            let formats = match symbol {
+                Some(symbol) => format!("{} {}", value, symbol),
+                None => value.to_string(),
+            };
+            (formats, Some(uncertainty))
+        }
+        Err(_) => (formats, None),
+    }
+}
+
+// Number in debug format of `value`, or the debug format itself.
+fn value_of<T: Debug>(value: &T) -> core::result::Result<f32, String> {
+    let (formats, _) = debug_of(value);
+    match formats.parse::<f32>() {
+        Ok(value) => Ok(value),
+        Err(_) if formats.contains('\n') => Err(formats),
+        Err(_) => match split_quantity(&formats) {
+            Some((value, _)) => value.parse::<f32>().map_err(|_| formats.clone()),
+            None => Err(formats),
+        },
+    }
+}
+
+// Unit symbol in debug format of `value`, or `dimensionless`.
+fn symbol_of<T: Debug>(value: &T) -> String {
+    let (formats, _) = debug_of(value);
+    formats
+        .split('\n')
+        .filter_map(split_quantity)
+        .last()
+        .map_or_else(|| "dimensionless".to_string(), |(_, symbol)| symbol.to_string())
+}
+
+// Finite bounds in debug format `[{lower},{upper}] {symbol}`
+// (written by values like `crate::interval::Interval`).
+fn bounds_of(formats: &str) -> Option<(f32, f32)> {
+    if formats.contains('\n') {
+        return None;
+    }
+    let value = split_quantity(formats).map_or(formats, |(value, _)| value);
+    if !value.starts_with('[') || !value.ends_with(']') {
+        return None;
+    }
+    let mut bounds = value[1..value.len() - 1].splitn(2, ',');
+    match (bounds.next()?.parse::<f32>(), bounds.next()?.parse::<f32>()) {
+        (Ok(lower), Ok(upper)) if lower.is_finite() && upper.is_finite() => Some((lower, upper)),
+        _ => None,
+    }
+}
+
+// Json of `value`: number, boolean, bounds or string (debug format).
+fn json_value<T: Debug>(value: &T) -> String {
+    match value_of(value) {
+        Ok(value) => raw_json_number(value),
+        Err(formats) => match formats.as_str() {
+            "true" | "false" => formats,
+            _ => match bounds_of(&formats) {
+                Some((lower, upper)) => {
+                    format!("[{},{}]", raw_json_number(lower), raw_json_number(upper))
+                }
+                None => json_string(&formats),
+            },
+        },
+    }
+}
+
+impl<T: Clone + Debug> Cherries for Cherry<T> {
+    ///
+    /// Returns reference of node name .
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let node = Leaf::new().value(1).name("node").build();
+    /// assert_eq!(node.name(), &"node".to_string());
+    /// ```
+    fn name(&self) -> &str {
+        self.name()
+    }
+    ///
+    /// Returns node value or error string.
+    ///
+    /// This method try to parse value from format string for uom support.
+    /// There should be some other better way (help me, please!).
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    /// extern crate uom;
+    /// use uom::si::{f32::*, length::meter};
+    ///
+    /// let node = Leaf::new().value(1).name("node").build();
+    /// assert_eq!(node.value(), Ok(1.0));
+    /// let node = Leaf::new().value(Length::new::<meter>(2.0)).name("node").build();
+    /// assert_eq!(node.value(), Ok(2.0));
+    /// ```
+    fn value(&self) -> core::result::Result<f32, String> {
+        value_of(self.quantity())
+    }
+    ///
+    /// Returns units symbol.
+    ///
+    /// Returns node qunatity units symbol string (if has quantity) or `dimensionless`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    /// extern crate uom;
+    /// use uom::si::{f32::*, length::meter};
+    ///
+    /// let node = Leaf::new().value(1).name("node").build();
+    /// assert_eq!(node.symbol(), "dimensionless".to_string());
+    /// let node = Leaf::new().value(Length::new::<meter>(2.0)).name("node").build();
+    /// assert_eq!(node.symbol(), "m^1".to_string());
+    /// ```
+    fn symbol(&self) -> String {
+        symbol_of(self.quantity())
+    }
+    ///
+    /// Returns expression log as json string.
+    ///
+    /// The json has `label (string)`, `value (number)`, `units (string)`,
+    /// `dimensions (object, for dimensional quantity)`, `op (string, for non-leaf)`
+    /// and `subexpr (array of object)`.
+    /// Non-numeric value is written as boolean (`true`/`false`) or string (debug format).
+    /// Non-finite value is written as string (e.g. `"NaN"`), so the output is always valid json.
+    /// Display units (`units::set_display_units`) and print policy (`rounding::set_print_policy`)
+    /// of the current thread are applied to every node of the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    /// extern crate uom;
+    /// use uom::si::{f32::*, length::meter};
+    ///
+    /// let x = Leaf::new().value(1.0).name("x").build();
+    /// let y = Leaf::new().value(Length::new::<meter>(2.0)).name("y").build();
+    /// let res = x * y;
+    /// assert_eq!(
+    ///     res.to_json(),
+    ///     "{\
+    ///         \"label\":\"(mul)\",\
+    ///         \"value\":2,\
+    ///         \"unit\":\"m^1\",\
+    ///         \"dimensions\":{\"L\":1},\
+    ///         \"op\":\"mul\",\
+    ///         \"subexpr\":[\
+    ///             {\
+    ///                 \"label\":\"x\",\
+    ///                 \"value\":1,\
+    ///                 \"unit\":\"dimensionless\"\
+    ///             },\
+    ///             {\
+    ///                 \"label\":\"y\",\
+    ///                 \"value\":2,\
+    ///                 \"unit\":\"m^1\",\
+    ///                 \"dimensions\":{\"L\":1}\
+    ///             }\
+    ///         ]\
+    ///     }".to_string()
+    /// );
+    /// ```
+    fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.json.render(&mut json);
+        crate::units::present(json)
+    }
+    #[doc(hidden)]
+    fn as_subexpr(&self) -> Subexpr {
+        Subexpr(Part::Node(self.json.clone()))
+    }
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    // Json of subexpressions joined by commas, if any.
+    pub(crate) fn previous(&self) -> Option<String> {
+        self.json.subexpr.as_ref().map(|subexpr| {
+            let mut json = String::new();
+            for (i, Subexpr(part)) in subexpr.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                match part {
+                    Part::Json(text) => json.push_str(text),
+                    Part::Node(node) => node.render(&mut json),
+                }
+            }
+            json
+        })
+    }
+    // Visits nodes of the tree up to `max_depth` in pre-order.
+    pub(crate) fn walk<F: FnMut(Visit<'_>)>(&self, max_depth: usize, f: F) {
+        self.json.walk(max_depth, f)
+    }
+    // Enters and leaves every node of the tree in pre-order.
+    pub(crate) fn traverse<F: FnMut(Step<'_>)>(&self, f: F) {
+        self.json.traverse(f)
+    }
+    // First node labeled `label` in pre-order (including the root), sharing its subexpressions.
+    pub(crate) fn find(&self, label: &str) -> Option<Subexpr> {
+        enum Todo<'a> {
+            Node(&'a Arc<Json>),
+            Json(&'a str),
+        }
+        let head = format!("{{\"label\":{},", json_string(label));
+        let mut stack = vec![Todo::Node(&self.json)];
+        while let Some(todo) = stack.pop() {
+            match todo {
+                Todo::Node(node) if node.head.starts_with(&head) => {
+                    return Some(Subexpr(Part::Node(Arc::clone(node))));
+                }
+                Todo::Node(node) => {
+                    for Subexpr(part) in node.subexpr.iter().flatten().rev() {
+                        stack.push(match part {
+                            Part::Json(json) => Todo::Json(json),
+                            Part::Node(node) => Todo::Node(node),
+                        });
+                    }
+                }
+                Todo::Json(json) => {
+                    let trees = serde_json::from_str::<Vec<Tree>>(&format!("[{}]", json));
+                    let found = trees.iter().flatten().find_map(|tree| tree.find_label(label));
+                    if let Some(tree) = found {
+                        let json = crate::units::render(tree);
+                        return Some(Subexpr(Part::Json(Arc::from(json))));
+                    }
+                }
+            }
+        }
+        None
+    }
+    // Tree with the first subexpression labeled `label` (in pre-order, excluding the root)
+    // swapped for `replacement`, and the indices of the subexpressions leading to it.
+    // Other subexpressions are shared; nodes on the way are copied with their recorded values.
+    pub(crate) fn replaced(
+        &self,
+        label: &str,
+        replacement: Subexpr,
+    ) -> Option<(Cherry<T>, Vec<usize>)> {
+        enum Todo<'a> {
+            Node(usize),
+            Json(usize, usize, &'a str),
+        }
+        // Part found in the subexpressions of node `parent`, with the trees read from
+        // json text, the index of the tree holding the node and the path to it in the tree.
+        struct Found {
+            parent: usize,
+            part: usize,
+            within: Option<(Vec<Tree>, usize, Vec<usize>)>,
+        }
+        // Trees recorded in the parts of `node` before part `part`.
+        fn offset(node: &Json, part: usize) -> usize {
+            node.subexpr.as_ref().map_or(0, |subexpr| count_trees(&subexpr[..part]))
+        }
+        fn path_to(tree: &Tree, label: &str) -> Option<Vec<usize>> {
+            if tree.label == label {
+                return Some(vec![]);
+            }
+            tree.subexpr.iter().enumerate().find_map(|(i, sub)| {
+                path_to(sub, label).map(|mut path| {
+                    path.insert(0, i);
+                    path
+                })
+            })
+        }
+        let head = format!("{{\"label\":{},", json_string(label));
+        // Visited nodes with their parent and the index of their part in the parent.
+        let mut nodes = vec![(&self.json, 0, 0)];
+        let mut stack = vec![Todo::Node(0)];
+        let mut found = None;
+        while let Some(todo) = stack.pop() {
+            match todo {
+                Todo::Node(at) if at > 0 && nodes[at].0.head.starts_with(&head) => {
+                    let (_, parent, part) = nodes[at];
+                    found = Some(Found {
+                        parent,
+                        part,
+                        within: None,
+                    });
+                    break;
+                }
+                Todo::Node(at) => {
+                    let node = nodes[at].0;
+                    let subexpr = node.subexpr.as_ref().map_or(&[][..], Vec::as_slice);
+                    for (part, Subexpr(sub)) in subexpr.iter().enumerate().rev() {
+                        stack.push(match sub {
+                            Part::Json(json) => Todo::Json(at, part, json),
+                            Part::Node(sub) => {
+                                nodes.push((sub, at, part));
+                                Todo::Node(nodes.len() - 1)
+                            }
+                        });
+                    }
+                }
+                Todo::Json(parent, part, json) => {
+                    let trees = serde_json::from_str::<Vec<Tree>>(&format!("[{}]", json)).ok();
+                    let path = trees
+                        .iter()
+                        .flatten()
+                        .enumerate()
+                        .find_map(|(i, tree)| path_to(tree, label).map(|path| (i, path)));
+                    if let (Some(trees), Some((i, path))) = (trees, path) {
+                        found = Some(Found {
+                            parent,
+                            part,
+                            within: Some((trees, i, path)),
+                        });
+                        break;
+                    }
+                }
+            }
+        }
+        let Found {
+            parent,
+            part: index,
+            within,
+        } = found?;
+        // Indices from the node found up to the root, reversed at the end.
+        let mut path = Vec::new();
+        let mut sub = match within {
+            None => {
+                path.push(offset(nodes[parent].0, index));
+                replacement
+            }
+            Some((mut trees, i, inner)) => {
+                path.extend(inner.iter().rev().cloned());
+                path.push(offset(nodes[parent].0, index) + i);
+                let mut tree = &mut trees[i];
+                for &j in &inner {
+                    tree = &mut tree.subexpr[j];
+                }
+                *tree = Tree::from_json(&replacement.to_json()).ok()?;
+                let json = trees.iter().map(crate::units::render).collect::<Vec<_>>();
+                Subexpr(Part::Json(Arc::from(join(&json, ","))))
+            }
+        };
+        let (mut at, mut index) = (parent, index);
+        let json = loop {
+            let node = nodes[at].0;
+            let mut subexpr = node.subexpr.clone().unwrap_or_default();
+            subexpr[index] = sub;
+            let json = Arc::new(Json {
+                head: node.head.clone(),
+                meta: node.meta.clone(),
+                subexpr: Some(subexpr),
+            });
+            if at == 0 {
+                break json;
+            }
+            let (_, parent, part) = nodes[at];
+            path.push(offset(nodes[parent].0, part));
+            sub = Subexpr(Part::Node(json));
+            at = parent;
+            index = part;
+        };
+        path.reverse();
+        let node = Cherry {
+            label: self.label.clone(),
+            value: self.value.clone(),
+            json,
+        };
+        Some((node, path))
+    }
+    // Makes node rendering json of its head.
+    fn from_parts(label: Label, value: T, meta: Meta, subexpr: Option<Vec<Subexpr>>) -> Cherry<T> {
+        let symbol = symbol_of(&value);
+        let uncertainty = debug_of(&value).1.map_or_else(String::new, |uncertainty| {
+            format!(",\"uncertainty\":{}", raw_json_number(uncertainty))
+        });
+        let json = Json {
+            head: format!(
+                "{{\"label\":{label},\"value\":{value}{uncertainty},\"unit\":{unit}{dimensions}{meta}",
+                label = json_string(&label),
+                value = json_value(&value),
+                uncertainty = uncertainty,
+                unit = json_string(&symbol),
+                dimensions = json_dimensions(Dimensions::from_symbol(&symbol)),
+                meta = meta.to_json()
+            ),
+            meta,
+            subexpr,
+        };
+        Cherry {
+            label,
+            value,
+            json: Arc::new(json),
+        }
+    }
+    fn from_previous(label: String, value: T, op: Option<Op>, previous: Option<String>) -> Cherry<T> {
+        let meta = Meta {
+            op: op.unwrap_or_default(),
+            key: next_key(),
+            ..Meta::default()
+        };
+        let subexpr = previous.map(|previous| vec![Subexpr(Part::Json(Arc::from(previous)))]);
+        Cherry::from_parts(label.into(), value, meta, subexpr)
+    }
+    ///
+    /// Returns json of the tree down to depth `max_depth` (`0` for the root only), as `to_json` does,
+    /// for huge trees of which only the top matters.
+    ///
+    /// Nodes at depth `max_depth` are written without subexpressions,
+    /// with their count as `"collapsed"` instead (see `export::Tree::truncated`).
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let b = Leaf::new().value(0.3).name("b").build();
+    /// let h = Leaf::new().value(0.5).name("h").build();
+    /// let fy = Leaf::new().value(355.0).name("f_y").build();
+    /// let resistance = ((b * h).labeled("A") * fy).labeled("N_Rd");
+    /// assert_eq!(
+    ///     resistance.to_json_with_depth(1),
+    ///     "{\"label\":\"N_Rd\",\"value\":53.25,\"unit\":\"dimensionless\",\"op\":\"mul\",\"subexpr\":[\
+    ///         {\"label\":\"A\",\"value\":0.15,\"unit\":\"dimensionless\",\"op\":\"mul\",\"collapsed\":2},\
+    ///         {\"label\":\"f_y\",\"value\":355,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// assert_eq!(resistance.to_json_with_depth(2), resistance.to_json());
+    /// ```
+    pub fn to_json_with_depth(&self, max_depth: usize) -> String {
+        let mut json = String::new();
+        self.json.render_to_depth(&mut json, max_depth);
+        crate::units::present(json)
+    }
+    ///
+    /// Returns json of the tree, as `to_json` does, without the subexpressions for which
+    /// `keep` returns `false` (and everything below them), e.g. to leave out dimensionless
+    /// constants or nodes tagged `internal` from logs for reviewers.
+    ///
+    /// `keep` is given every node (in base units) with its subexpressions; the root is always kept.
+    /// Fails only for subexpressions recorded as invalid json text.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    /// use cherries::export::Tree;
+    /// extern crate uom;
+    /// use uom::si::{f64::*, length::meter};
+    ///
+    /// let b = Leaf::new().value(Length::new::<meter>(0.3)).name("b").build();
+    /// let h = Leaf::new().value(Length::new::<meter>(0.6)).name("h").build();
+    /// let six = Leaf::new().value(6.0).name("6").build();
+    /// let w = (b * h.clone() * h / six).labeled("W").tag("internal", "yes");
+    /// let fy = Leaf::new().value(355.0).name("f_y").build();
+    /// let m = (w * fy).labeled("M_Rd");
+    ///
+    /// let constant = |node: &Tree| node.op.is_none() && node.unit == "dimensionless";
+    /// assert_eq!(
+    ///     m.to_json_filtered(|node| !constant(node)).unwrap(),
+    ///     m.to_json()
+    ///         .replace(",{\"label\":\"6\",\"value\":6,\"unit\":\"dimensionless\"}", "")
+    ///         .replace(",{\"label\":\"f_y\",\"value\":355,\"unit\":\"dimensionless\"}", "")
+    /// );
+    /// assert_eq!(
+    ///     m.to_json_filtered(|node| !node.meta.contains_key("internal")).unwrap(),
+    ///     "{\"label\":\"M_Rd\",\"value\":6.39,\"unit\":\"m^3\",\"dimensions\":{\"L\":3},\"op\":\"mul\",\"subexpr\":[\
+    ///         {\"label\":\"f_y\",\"value\":355,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// ```
+    pub fn to_json_filtered<F: FnMut(&Tree) -> bool>(
+        &self,
+        keep: F,
+    ) -> serde_json::Result<String> {
+        let mut json = String::new();
+        self.json.render(&mut json);
+        let tree = Tree::from_json(&json)?.filtered(keep);
+        Ok(crate::units::present(crate::units::render(&tree)))
+    }
+    ///
+    /// Returns reference of quantity which node has.
+    ///
+    /// Returns node qunatity (if has quantity) or value (if dimensionless).
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    /// extern crate uom;
+    /// use uom::si::{f32::*, length::meter};
+    ///
+    /// let node = Leaf::new().value(1).name("node").build();
+    /// assert_eq!(node.quantity(), &1);
+    /// let node = Leaf::new().value(Length::new::<meter>(2.0)).name("y").build();
+    /// assert_eq!(node.quantity(), &Length::new::<meter>(2.0));
+    ///
+    /// ```
+    pub fn quantity(&self) -> &T {
+        &self.value
     }
-}
-
-impl<T: Clone + Debug> Cherries for Cherry<T> {
     ///
     /// Returns reference of node name .
     ///
@@ -168,184 +1582,408 @@ impl<T: Clone + Debug> Cherries for Cherry<T> {
     /// let node = Leaf::new().value(1).name("node").build();
     /// assert_eq!(node.name(), &"node".to_string());
     /// ```
-    fn name(&self) -> &String {
-        self.name()
+    pub fn name(&self) -> &str {
+        &self.label
     }
     ///
-    /// Returns node value or error string.
-    ///
-    /// This method try to parse value from format string for uom support.
-    /// There should be some other better way (help me, please!).
+    /// Returns node which renamed (and sonsuming self).
     ///
     /// # Examples
     /// ```
     /// extern crate cherries;
     /// use cherries::node::{Leaf, Cherries};
-    /// extern crate uom;
-    /// use uom::si::{f32::*, length::meter};
     ///
     /// let node = Leaf::new().value(1).name("node").build();
-    /// assert_eq!(node.value(), Ok(1.0));
-    /// let node = Leaf::new().value(Length::new::<meter>(2.0)).name("node").build();
-    /// assert_eq!(node.value(), Ok(2.0));
+    /// assert_eq!(node.name(), &"node".to_string());
+    /// let node = node.labeled("renamed");
+    /// assert_eq!(node.name(), &"renamed".to_string());
     /// ```
-    fn value(&self) -> std::result::Result<f32, String> {
-        let re = Regex::new(r#"^(.*?) .*$"#).unwrap();
-        let formats = format!("{:?}", self.quantity());
-        match formats.parse::<f32>() {
-            Ok(value) => Ok(value),
-            Err(_) => re.captures_iter(formats.clone().as_str()).last().map_or(
-                Err(formats.clone()),
-                |x| {
-                    x.get(1).map_or(Err(formats.clone()), |x| {
-                        x.as_str().parse::<f32>().map_err(|_| formats)
-                    })
-                },
-            ),
-        }
+    pub fn labeled<S: Into<Label>>(self, name: S) -> Cherry<T> {
+        let json = &self.json;
+        let (meta, subexpr) = (json.meta.clone(), json.subexpr.clone());
+        Cherry::from_parts(name.into(), self.value, meta, subexpr)
     }
     ///
-    /// Returns units symbol.
+    /// Attaches external evidence (a file path or in-memory content) of type `mime` to the node.
     ///
-    /// Returns node qunatity units symbol string (if has quantity) or `dimensionless`.
+    /// Files are referenced by path and content by its digest and size in `to_json`.
     ///
     /// # Examples
     /// ```
     /// extern crate cherries;
     /// use cherries::node::{Leaf, Cherries};
-    /// extern crate uom;
-    /// use uom::si::{f32::*, length::meter};
+    /// use cherries::export::Tree;
+    /// use cherries::attachment::Reference;
+    /// use std::convert::TryFrom;
     ///
-    /// let node = Leaf::new().value(1).name("node").build();
-    /// assert_eq!(node.symbol(), "dimensionless".to_string());
-    /// let node = Leaf::new().value(Length::new::<meter>(2.0)).name("node").build();
-    /// assert_eq!(node.symbol(), "m^1".to_string());
+    /// let fc = Leaf::new()
+    ///     .value(24.5)
+    ///     .name("fc")
+    ///     .build()
+    ///     .attach("photos/cylinder-test.jpg", "image/jpeg")
+    ///     .attach(b"specimen,strength\n1,24.1\n2,24.9\n".to_vec(), "text/csv");
+    /// assert_eq!(fc.attachments().len(), 2);
+    /// assert!(fc.to_json().starts_with(
+    ///     "{\"label\":\"fc\",\"value\":24.5,\"unit\":\"dimensionless\",\"attachments\":[\
+    ///         {\"path\":\"photos/cylinder-test.jpg\",\"mime\":\"image/jpeg\"},\
+    ///         {\"digest\":"
+    /// ));
+    ///
+    /// let tree = Tree::try_from(&(fc * Leaf::new().value(0.85).name("k").build())).unwrap();
+    /// let evidence = &tree.subexpr[0].attachments;
+    /// assert_eq!(
+    ///     evidence[0],
+    ///     Reference::Path { path: "photos/cylinder-test.jpg".to_string(), mime: "image/jpeg".to_string() }
+    /// );
+    /// match &evidence[1] {
+    ///     Reference::Bytes { size, mime, .. } => assert_eq!((*size, mime.as_str()), (32, "text/csv")),
+    ///     other => panic!("unexpected {:?}", other),
+    /// }
     /// ```
-    fn symbol(&self) -> String {
-        let re = Regex::new(r#".*? (.*)"#).unwrap();
-        let formats = format!("{:?}", self.quantity());
-        re.captures_iter(formats.as_str())
-            .last()
-            .map(|x| {
-                x.get(1)
-                    .map(|x| x.as_str().to_string())
-                    .unwrap_or_else(|| "dimensionless".to_string())
-            })
-            .unwrap_or_else(|| "dimensionless".to_string())
+    pub fn attach<S: Into<Source>, M: Into<String>>(self, source: S, mime: M) -> Cherry<T> {
+        let attachment = Attachment {
+            source: source.into(),
+            mime: mime.into(),
+        };
+        self.with_meta(|meta| meta.attachments.push(attachment))
+    }
+    // Same node with metadata changed by `f`.
+    fn with_meta<F: FnOnce(&mut Meta)>(self, f: F) -> Cherry<T> {
+        let mut meta = self.json.meta.clone();
+        f(&mut meta);
+        let subexpr = self.json.subexpr.clone();
+        Cherry::from_parts(self.label, self.value, meta, subexpr)
     }
     ///
-    /// Returns expression log as json string.
+    /// Returns external evidence attached to the node.
+    ///
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.json.meta.attachments
+    }
+    ///
+    /// Records where the value of the node comes from.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries, Origin};
+    ///
+    /// let fy = Leaf::new().value(355.0).name("fy").build().with_origin(Origin::Standard);
+    /// assert_eq!(fy.origin(), Some(Origin::Standard));
+    /// assert_eq!(
+    ///     fy.labeled("f_y").to_json(),
+    ///     "{\"label\":\"f_y\",\"value\":355,\"unit\":\"dimensionless\",\"origin\":\"standard\"}".to_string()
+    /// );
+    /// ```
+    pub fn with_origin(self, origin: Origin) -> Cherry<T> {
+        self.with_meta(|meta| meta.origin = Some(origin))
+    }
     ///
-    /// The json has `label (string)`, `value (number)`, `units (string)`, and `subexpr (array of object)`.
+    /// Returns where the value of the node comes from, if recorded.
+    ///
+    pub fn origin(&self) -> Option<Origin> {
+        self.json.meta.origin
+    }
+    ///
+    /// Records short explanatory note on the node (replacing the previous one),
+    /// shown as footnote or tooltip by textual exports.
     ///
     /// # Examples
     /// ```
     /// extern crate cherries;
     /// use cherries::node::{Leaf, Cherries};
-    /// extern crate uom;
-    /// use uom::si::{f32::*, length::meter};
     ///
-    /// let x = Leaf::new().value(1.0).name("x").build();
-    /// let y = Leaf::new().value(Length::new::<meter>(2.0)).name("y").build();
-    /// let res = x * y;
+    /// let fc = Leaf::new().value(24.5).name("fc").build().comment("per test report TR-1289");
+    /// assert_eq!(fc.note(), Some("per test report TR-1289"));
     /// assert_eq!(
-    ///     res.to_json(),
-    ///     "{\
-    ///         \"label\":\"(mul)\",\
-    ///         \"value\":2,\
-    ///         \"unit\":\"m^1\",\
-    ///         \"subexpr\":[\
-    ///             {\
-    ///                 \"label\":\"x\",\
-    ///                 \"value\":1,\
-    ///                 \"unit\":\"dimensionless\"\
-    ///             },\
-    ///             {\
-    ///                 \"label\":\"y\",\
-    ///                 \"value\":2,\
-    ///                 \"unit\":\"m^1\"\
-    ///             }\
-    ///         ]\
-    ///     }".to_string()
+    ///     fc.to_json(),
+    ///     "{\"label\":\"fc\",\"value\":24.5,\"unit\":\"dimensionless\",\"comment\":\"per test report TR-1289\"}".to_string()
     /// );
     /// ```
-    fn to_json(&self) -> String {
-        match &self.previous {
-            Some(prev) => {
-                format!(
-                    "{{\"label\":\"{label}\",\"value\":{value},\"unit\":\"{unit}\",\"subexpr\":[{subexpr}]}}",
-                    label = self.label,
-                    unit = self.symbol(),
-                    value = self.value().unwrap(),
-                    subexpr = prev)
-            },
-            None => {
-                format!(
-                    "{{\"label\":\"{label}\",\"value\":{value},\"unit\":\"{unit}\"}}",
-                    label = self.label,
-                    unit = self.symbol(),
-                    value = self.value().unwrap()
-                )
-            }
+    pub fn comment<S: Into<String>>(self, comment: S) -> Cherry<T> {
+        let comment = comment.into();
+        self.with_meta(|meta| meta.comment = Some(comment))
+    }
+    ///
+    /// Returns note recorded by `comment`.
+    ///
+    pub fn note(&self) -> Option<&str> {
+        self.json.meta.comment.as_ref().map(|comment| &comment[..])
+    }
+    ///
+    /// Records long-form description of the node (replacing the previous one),
+    /// distinct from its short label, so calculation logs document themselves.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let m = Leaf::new()
+    ///     .value(180.0)
+    ///     .name("M_c,Rd")
+    ///     .build()
+    ///     .describe("design bending resistance per clause 6.2.5");
+    /// assert_eq!(m.description(), Some("design bending resistance per clause 6.2.5"));
+    /// assert_eq!(
+    ///     m.labeled("M_Rd").to_json(),
+    ///     "{\"label\":\"M_Rd\",\"value\":180,\"unit\":\"dimensionless\",\
+    ///         \"description\":\"design bending resistance per clause 6.2.5\"}".to_string()
+    /// );
+    /// ```
+    pub fn describe<S: Into<String>>(self, description: S) -> Cherry<T> {
+        let description = description.into();
+        self.with_meta(|meta| meta.description = Some(description))
+    }
+    ///
+    /// Returns description recorded by `describe`.
+    ///
+    pub fn description(&self) -> Option<&str> {
+        self.json.meta.description.as_ref().map(|description| &description[..])
+    }
+    ///
+    /// Records that the node follows clause `clause` of a standard or regulation
+    /// (written in json as `"references"`, each clause once).
+    ///
+    /// Reports list the clauses cited by the nodes of a tree in a bibliography
+    /// (see `report::Renderer`).
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let n = Leaf::new()
+    ///     .value(0.9)
+    ///     .name("k")
+    ///     .build()
+    ///     .per("EN 1993-1-1 §6.2.6")
+    ///     .per("EN 1993-1-1 §6.2.6(2)")
+    ///     .per("EN 1993-1-1 §6.2.6");
+    /// assert_eq!(n.references().len(), 2);
+    /// assert_eq!(
+    ///     n.labeled("k_v").to_json(),
+    ///     "{\"label\":\"k_v\",\"value\":0.9,\"unit\":\"dimensionless\",\
+    ///         \"references\":[\"EN 1993-1-1 §6.2.6\",\"EN 1993-1-1 §6.2.6(2)\"]}".to_string()
+    /// );
+    /// ```
+    pub fn per<S: Into<String>>(self, clause: S) -> Cherry<T> {
+        let clause = clause.into();
+        if self.json.meta.references.contains(&clause) {
+            return self;
         }
+        self.with_meta(|meta| meta.references.push(clause))
     }
-}
-
-impl<T: Clone + Debug> Cherry<T> {
     ///
-    /// Returns reference of quantity which node has.
+    /// Returns clauses recorded by `per`.
     ///
-    /// Returns node qunatity (if has quantity) or value (if dimensionless).
+    pub fn references(&self) -> &[String] {
+        &self.json.meta.references
+    }
+    ///
+    /// Marks the node as detail (written in json as `"detail":true`), whose subexpressions
+    /// serializers may collapse into a summary of its value and count of subexpressions,
+    /// so top-level reports stay readable (see `export::Tree::collapsed`).
     ///
     /// # Examples
     /// ```
     /// extern crate cherries;
     /// use cherries::node::{Leaf, Cherries};
-    /// extern crate uom;
-    /// use uom::si::{f32::*, length::meter};
     ///
-    /// let node = Leaf::new().value(1).name("node").build();
-    /// assert_eq!(node.quantity(), &1);
-    /// let node = Leaf::new().value(Length::new::<meter>(2.0)).name("y").build();
-    /// assert_eq!(node.quantity(), &Length::new::<meter>(2.0));
+    /// let b = Leaf::new().value(0.3).name("b").build();
+    /// let h = Leaf::new().value(0.5).name("h").build();
+    /// let area = (b * h).labeled("A").detail();
+    /// assert!(area.is_detail());
+    /// assert_eq!(
+    ///     area.to_json(),
+    ///     "{\"label\":\"A\",\"value\":0.15,\"unit\":\"dimensionless\",\"op\":\"mul\",\"detail\":true,\"subexpr\":[\
+    ///         {\"label\":\"b\",\"value\":0.3,\"unit\":\"dimensionless\"},\
+    ///         {\"label\":\"h\",\"value\":0.5,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// ```
+    pub fn detail(self) -> Cherry<T> {
+        self.with_meta(|meta| meta.detail = true)
+    }
+    ///
+    /// Returns whether the node is marked as detail by `detail`.
+    ///
+    pub fn is_detail(&self) -> bool {
+        self.json.meta.detail
+    }
+    ///
+    /// Returns warnings and notes recorded by validation checks (see `validate::Severity`).
+    ///
+    pub fn findings(&self) -> &[Finding] {
+        &self.json.meta.findings
+    }
+    // Node recording `findings` after those recorded before.
+    pub(crate) fn with_findings(self, findings: Vec<Finding>) -> Cherry<T> {
+        if findings.is_empty() {
+            return self;
+        }
+        self.with_meta(|meta| meta.findings.extend(findings))
+    }
+    ///
+    /// Records number of significant figures of the value (e.g. of a measurement).
     ///
+    /// Nodes made by `+`, `-`, `*`, `/` and `map` from nodes with significant figures
+    /// record their effective precision: products and quotients have the fewest significant figures
+    /// of their operands, sums and differences keep the last decimal place known in all operands.
+    /// Operands without significant figures are taken as exact.
+    ///
+    /// # Examples
     /// ```
-    pub fn quantity(&self) -> &T {
-        &self.value
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let l = Leaf::new().value(12.3).name("l").build().with_sig_figs(3);
+    /// let w = Leaf::new().value(4.5).name("w").build().with_sig_figs(2);
+    /// let two = Leaf::new().value(2.0).name("2").build();
+    /// let area = (l.clone() * w.clone()).labeled("area");
+    /// assert_eq!(area.sig_figs(), Some(2));
+    /// assert!(area.to_json().starts_with("{\"label\":\"area\",\"value\":55.35,\"unit\":\"dimensionless\",\"op\":\"mul\",\"sig_figs\":2,"));
+    /// // last known decimal place is tenths: 16.8
+    /// assert_eq!((l.clone() + w.clone()).sig_figs(), Some(3));
+    /// // 12.3 - 4.5 = 7.8
+    /// assert_eq!((l.clone() - w).sig_figs(), Some(2));
+    /// assert_eq!((two * l).sig_figs(), Some(3));
+    /// ```
+    pub fn with_sig_figs(self, sig_figs: u32) -> Cherry<T> {
+        self.with_meta(|meta| meta.sig_figs = Some(sig_figs))
     }
     ///
-    /// Returns reference of node name .
+    /// Returns number of significant figures of the value, if recorded or propagated.
+    ///
+    pub fn sig_figs(&self) -> Option<u32> {
+        self.json.meta.sig_figs
+    }
+    // Same node with `sig_figs` recorded if known.
+    pub(crate) fn with_sig_figs_of(self, sig_figs: Option<u32>) -> Cherry<T> {
+        match sig_figs {
+            Some(sig_figs) => self.with_sig_figs(sig_figs),
+            None => self,
+        }
+    }
+    ///
+    /// Records metadata `value` under `key` (replacing the previous value of `key`),
+    /// e.g. drawing numbers or revision ids.
+    ///
+    /// Tags are kept when the node is relabeled or mapped, and written in json
+    /// as `"meta":{"key":"value",..}` (sorted by key).
     ///
     /// # Examples
     /// ```
     /// extern crate cherries;
     /// use cherries::node::{Leaf, Cherries};
     ///
-    /// let node = Leaf::new().value(1).name("node").build();
-    /// assert_eq!(node.name(), &"node".to_string());
+    /// let span = Leaf::new()
+    ///     .value(6.0)
+    ///     .name("span")
+    ///     .build()
+    ///     .tag("drawing", "S-201")
+    ///     .tag("revision", "C");
+    /// assert_eq!(span.tags().get("drawing"), Some(&"S-201".to_string()));
+    /// let doubled = span.map(|v| v * 2.0).labeled("2 span");
+    /// assert_eq!(
+    ///     doubled.to_json(),
+    ///     "{\"label\":\"2 span\",\"value\":12,\"unit\":\"dimensionless\",\"op\":\"map\",\"meta\":{\"drawing\":\"S-201\",\"revision\":\"C\"},\"subexpr\":[\
+    ///         {\"label\":\"span\",\"value\":6,\"unit\":\"dimensionless\",\"meta\":{\"drawing\":\"S-201\",\"revision\":\"C\"}}\
+    ///     ]}".to_string()
+    /// );
     /// ```
-    pub fn name(&self) -> &String {
-        &self.label
+    pub fn tag<K: Into<String>, V: Into<String>>(self, key: K, value: V) -> Cherry<T> {
+        let (key, value) = (key.into(), value.into());
+        self.with_meta(|meta| {
+            meta.tags.insert(key, value);
+        })
     }
     ///
-    /// Returns node which renamed (and sonsuming self).
+    /// Returns metadata recorded by `tag`.
+    ///
+    pub fn tags(&self) -> &BTreeMap<String, String> {
+        &self.json.meta.tags
+    }
+    ///
+    /// Returns `file:line:column` of the code which built the node (written as `"location"` in json),
+    /// recorded by `build`, operators, `map` and folds with feature `track-caller`
+    /// (which requires rustc 1.46) while enabled by `node::set_record_locations`.
+    ///
+    /// Relabeled nodes keep the location they were built at.
     ///
     /// # Examples
     /// ```
     /// extern crate cherries;
     /// use cherries::node::{Leaf, Cherries};
     ///
-    /// let node = Leaf::new().value(1).name("node").build();
-    /// assert_eq!(node.name(), &"node".to_string());
-    /// let node = node.labeled("renamed");
-    /// assert_eq!(node.name(), &"renamed".to_string());
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// assert_eq!(x.location(), None);
     /// ```
-    pub fn labeled<S: Into<String>>(self, name: S) -> Cherry<T> {
-        Cherry {
-            label: name.into(),
-            value: self.value,
-            previous: self.previous,
+    pub fn location(&self) -> Option<&str> {
+        self.json.meta.location.as_ref().map(|location| &location[..])
+    }
+    ///
+    /// Returns the time (RFC 3339 in UTC, e.g. `2020-03-01T12:00:00.000Z`) the node was built at
+    /// (written as `"timestamp"` in json), recorded by `build`, operators, `map` and folds
+    /// with feature `timestamps` while enabled by `node::set_record_timestamps`,
+    /// read from the wall clock set by `timing::set_wall_clock`.
+    ///
+    /// Relabeled nodes keep the time they were built at.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// assert_eq!(x.timestamp(), None);
+    /// ```
+    pub fn timestamp(&self) -> Option<&str> {
+        self.json.meta.timestamp.as_ref().map(|timestamp| &timestamp[..])
+    }
+    ///
+    /// Returns the id of the node (written as `"id"` in json), given by `build`, operators, `map`
+    /// and folds while enabled by `node::set_record_ids`.
+    ///
+    /// Ids count up from `1` and are unique among the nodes built by the process,
+    /// so tools reading the json can refer to a node (e.g. by `export::Tree::find_id`).
+    /// Clones and relabeled nodes keep the id of the node.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{set_record_ids, Leaf, Cherries};
+    ///
+    /// let previous = set_record_ids(true);
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// let y = Leaf::new().value(3).name("y").build();
+    /// let z = (x.clone() + y.clone()).labeled("z");
+    /// set_record_ids(previous);
+    /// assert!(x.id().unwrap() < y.id().unwrap() && y.id().unwrap() < z.id().unwrap());
+    /// assert!(z.to_json().contains(&format!("\"id\":{}", z.id().unwrap())));
+    /// assert_eq!(Leaf::new().value(2).name("w").build().id(), None);
+    /// ```
+    pub fn id(&self) -> Option<u64> {
+        self.json.meta.id
+    }
+    ///
+    /// Returns the operation the node was made by (kept when relabeled).
+    ///
+    pub fn op(&self) -> &Op {
+        &self.json.meta.op
+    }
+    // Key shared by clones and relabeled copies of the node only.
+    #[cfg(feature = "std")]
+    pub(crate) fn key(&self) -> usize {
+        self.json.meta.key
+    }
+    // Same node also tagged with `tags`.
+    fn with_tags_of(self, tags: &BTreeMap<String, String>) -> Cherry<T> {
+        if tags.is_empty() {
+            return self;
         }
+        let tags = tags.clone();
+        self.with_meta(|meta| meta.tags.extend(tags))
     }
     ///
     /// Maps a `Cherry<T>` to `Cherry<U>` by applying a function to a contained quantity.
@@ -364,12 +2002,22 @@ impl<T: Clone + Debug> Cherry<T> {
     /// let res = x.map(|x| x.floor::<meter>()).labeled("floor");
     /// assert_eq!(&Length::new::<meter>(2.0), res.quantity());
     /// ```
-    pub fn map<F: FnOnce(&T) -> U, U: Clone + Debug>(&self, f: F) -> Cherry<U> {
-        Node::new()
+    #[cfg_attr(feature = "track-caller", track_caller)]
+    pub fn map<F: FnOnce(&T) -> U, U: 'static + Clone + Debug>(&self, f: F) -> Cherry<U>
+    where
+        T: 'static,
+    {
+        check_invariants(self);
+        let node = Node::new()
             .name("(map)")
+            .op(Op::Map)
             .value(f(self.quantity()))
-            .prev(self.to_json())
+            .children(vec![self.as_subexpr()])
             .build()
+            .with_sig_figs_of(self.sig_figs())
+            .with_tags_of(&self.json.meta.tags);
+        check_invariants(&node);
+        node
     }
     ///
     /// Returns `Ok(&self)` if `predicate(self.quantity())` is true, otherwise returns `Err(&self)`.
@@ -391,7 +2039,7 @@ impl<T: Clone + Debug> Cherry<T> {
     pub fn is_satisfy_with<Predicate: FnOnce(&T) -> bool>(
         &self,
         predicate: Predicate,
-    ) -> std::result::Result<&Self, &Self> {
+    ) -> core::result::Result<&Self, &Self> {
         if predicate(&self.value) {
             Ok(self)
         } else {
@@ -399,6 +2047,82 @@ impl<T: Clone + Debug> Cherry<T> {
         }
     }
     ///
+    /// Replaces quantity by `value` and returns node labeled `(override)`.
+    ///
+    /// The original node and the override (as leaf `override: {justification} (by {author})`)
+    /// are recorded, so engineering overrides are visible in the tree.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let k = Leaf::new().name("k").value(0.72).build();
+    /// let k = k.override_with(0.8, "site test report ST-12", "J. Doe");
+    /// assert_eq!(k.quantity(), &0.8);
+    /// assert_eq!(
+    ///     k.to_json(),
+    ///     "{\"label\":\"(override)\",\"value\":0.8,\"unit\":\"dimensionless\",\"op\":\"override\",\"subexpr\":[\
+    ///         {\"label\":\"k\",\"value\":0.72,\"unit\":\"dimensionless\"},\
+    ///         {\"label\":\"override: site test report ST-12 (by J. Doe)\",\"value\":0.8,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// ```
+    pub fn override_with<S: AsRef<str>, A: AsRef<str>>(
+        &self,
+        value: T,
+        justification: S,
+        author: A,
+    ) -> Cherry<T> {
+        let replacement = Leaf::new()
+            .name(format!(
+                "override: {} (by {})",
+                justification.as_ref(),
+                author.as_ref()
+            ))
+            .value(value.clone())
+            .build();
+        Node::new()
+            .name("(override)")
+            .op("override")
+            .value(value)
+            .children(vec![self.as_subexpr(), replacement.as_subexpr()])
+            .build()
+    }
+    ///
+    /// Returns tree whose first subexpression labeled `label` (in pre-order) is swapped for
+    /// `replacement`, or the same tree if no subexpression is labeled `label`.
+    ///
+    /// Nodes depending on the replaced one keep their recorded values;
+    /// `replace_reevaluated` recomputes them.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Cherries};
+    ///
+    /// let w = Leaf::new().value(2.0).name("w").build();
+    /// let h = Leaf::new().value(3.0).name("h").build();
+    /// let t = Leaf::new().value(0.5).name("t").build();
+    /// let volume = ((w * h).labeled("area") * t).labeled("volume");
+    ///
+    /// let measured = Leaf::new().value(6.4).name("area").build();
+    /// let edited = volume.replace("area", &measured);
+    /// assert_eq!(edited.quantity(), &3.0);
+    /// assert_eq!(
+    ///     edited.to_json(),
+    ///     "{\"label\":\"volume\",\"value\":3,\"unit\":\"dimensionless\",\"op\":\"mul\",\"subexpr\":[\
+    ///         {\"label\":\"area\",\"value\":6.4,\"unit\":\"dimensionless\"},\
+    ///         {\"label\":\"t\",\"value\":0.5,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// assert_eq!(volume.replace("depth", &measured).to_json(), volume.to_json());
+    /// ```
+    pub fn replace<R: Cherries + ?Sized>(&self, label: &str, replacement: &R) -> Cherry<T> {
+        self.replaced(label, replacement.as_subexpr())
+            .map_or_else(|| self.clone(), |(node, _)| node)
+    }
+    ///
     /// Applies `self.quantity()` to given function `f` and returns its result.
     ///
     /// # Examples
@@ -442,7 +2166,7 @@ impl Leaf<(), ()> {
     }
 }
 
-impl<T: Clone + Debug> Leaf<String, T> {
+impl<T: Clone + Debug> Leaf<Label, T> {
     ///
     /// Makes `Cherry<T>` from `self.label`and `self.value`.
     ///
@@ -458,12 +2182,11 @@ impl<T: Clone + Debug> Leaf<String, T> {
     /// assert_eq!(x.quantity(), &2);
     /// assert_eq!(x.name(), &"x".to_string());
     /// ```
+    #[cfg_attr(feature = "track-caller", track_caller)]
     pub fn build(self) -> Cherry<T> {
-        Cherry {
-            label: self.label,
-            value: self.value,
-            previous: None,
-        }
+        let node = Cherry::from_parts(self.label, self.value, Meta::created(), None);
+        notify(&node);
+        node
     }
 }
 
@@ -471,7 +2194,7 @@ impl<NameType, ValueType> Leaf<NameType, ValueType> {
     ///
     /// Sets field `label`.
     ///
-    pub fn name<S: Into<String>>(self, name: S) -> Leaf<String, ValueType> {
+    pub fn name<S: Into<Label>>(self, name: S) -> Leaf<Label, ValueType> {
         Leaf {
             label: name.into(),
             value: self.value,
@@ -494,6 +2217,8 @@ pub struct Node<NameType, ValueType, PrevType> {
     label: NameType,
     value: ValueType,
     previous: PrevType,
+    // Operation the node is made by (none recorded, as for leaves, if not set).
+    op: Option<Op>,
 }
 
 #[doc(hidden)]
@@ -503,28 +2228,33 @@ impl Node<(), (), ()> {
             label: (),
             value: (),
             previous: (),
+            op: None,
         }
     }
 }
 
 #[doc(hidden)]
-impl<T: Clone + Debug> Node<String, T, String> {
+impl<T: Clone + Debug> Node<Label, T, Vec<Subexpr>> {
+    #[cfg_attr(feature = "track-caller", track_caller)]
     pub fn build(self) -> Cherry<T> {
-        Cherry {
-            label: self.label,
-            value: self.value,
-            previous: Some(self.previous),
-        }
+        let meta = Meta {
+            op: self.op.unwrap_or_default(),
+            ..Meta::created()
+        };
+        let node = Cherry::from_parts(self.label, self.value, meta, Some(self.previous));
+        notify(&node);
+        node
     }
 }
 
 #[doc(hidden)]
 impl<NameType, ValueType, PrevType> Node<NameType, ValueType, PrevType> {
-    pub fn name<S: Into<String>>(self, name: S) -> Node<String, ValueType, PrevType> {
+    pub fn name<S: Into<Label>>(self, name: S) -> Node<Label, ValueType, PrevType> {
         Node {
             label: name.into(),
             value: self.value,
             previous: self.previous,
+            op: self.op,
         }
     }
     pub fn value<T: Clone + Debug>(self, val: T) -> Node<NameType, T, PrevType> {
@@ -532,13 +2262,24 @@ impl<NameType, ValueType, PrevType> Node<NameType, ValueType, PrevType> {
             label: self.label,
             value: val,
             previous: self.previous,
+            op: self.op,
         }
     }
-    pub fn prev<S: Into<String>>(self, prev: S) -> Node<NameType, ValueType, String> {
+    pub fn prev<S: Into<String>>(self, prev: S) -> Node<NameType, ValueType, Vec<Subexpr>> {
+        self.children(vec![Subexpr(Part::Json(Arc::from(prev.into())))])
+    }
+    pub fn children(self, children: Vec<Subexpr>) -> Node<NameType, ValueType, Vec<Subexpr>> {
         Node {
             label: self.label,
             value: self.value,
-            previous: prev.into(),
+            previous: children,
+            op: self.op,
+        }
+    }
+    pub fn op<O: Into<Op>>(self, op: O) -> Self {
+        Node {
+            op: Some(op.into()),
+            ..self
         }
     }
 }
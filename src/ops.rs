@@ -1,6 +1,7 @@
-use super::node::{Cherries, Cherry, Node};
-use std::fmt::Debug;
-use std::ops::{Add, Div, Mul, Sub};
+use super::node::{check_invariants, Cherries, Cherry, Node, Op};
+use alloc::{format, vec};
+use core::fmt::Debug;
+use core::ops::{Add, Div, Mul, Sub};
 
 ///
 /// impl Add<Cherry<U>> for Cherry<T>
@@ -28,16 +29,20 @@ impl<T, U> Add<Cherry<U>> for Cherry<T>
 where
     T: 'static + Clone + Debug + Add<U>,
     U: 'static + Clone + Debug,
-    <T as Add<U>>::Output: Clone + Debug,
+    <T as Add<U>>::Output: 'static + Clone + Debug,
 {
     type Output = Cherry<<T as Add<U>>::Output>;
 
+    #[cfg_attr(feature = "track-caller", track_caller)]
     fn add(self, other: Cherry<U>) -> Cherry<<T as Add<U>>::Output> {
-        Node::new()
+        let node = Node::new()
             .name("(add)")
+            .op(Op::Add)
             .value(self.quantity().clone() + other.quantity().clone())
-            .prev(vec![self.to_json(), other.to_json()].join(","))
-            .build()
+            .children(vec![self.as_subexpr(), other.as_subexpr()])
+            .build();
+        let sig_figs = sum_sig_figs(&self, &other, &node);
+        checked(&self, &other, node.with_sig_figs_of(sig_figs))
     }
 }
 
@@ -64,16 +69,20 @@ where
 impl<T: 'static + Clone + Debug, U: 'static + Clone + Debug> Sub<Cherry<U>> for Cherry<T>
 where
     T: Sub<U>,
-    <T as Sub<U>>::Output: Clone + Debug,
+    <T as Sub<U>>::Output: 'static + Clone + Debug,
 {
     type Output = Cherry<<T as Sub<U>>::Output>;
 
+    #[cfg_attr(feature = "track-caller", track_caller)]
     fn sub(self, other: Cherry<U>) -> Cherry<<T as Sub<U>>::Output> {
-        Node::new()
+        let node = Node::new()
             .name("(sub)")
+            .op(Op::Sub)
             .value(self.quantity().clone() - other.quantity().clone())
-            .prev(vec![self.to_json(), other.to_json()].join(","))
-            .build()
+            .children(vec![self.as_subexpr(), other.as_subexpr()])
+            .build();
+        let sig_figs = sum_sig_figs(&self, &other, &node);
+        checked(&self, &other, node.with_sig_figs_of(sig_figs))
     }
 }
 
@@ -100,16 +109,20 @@ where
 impl<T: 'static + Clone + Debug, U: 'static + Clone + Debug> Mul<Cherry<U>> for Cherry<T>
 where
     T: Mul<U>,
-    <T as Mul<U>>::Output: Clone + Debug,
+    <T as Mul<U>>::Output: 'static + Clone + Debug,
 {
     type Output = Cherry<<T as Mul<U>>::Output>;
 
+    #[cfg_attr(feature = "track-caller", track_caller)]
     fn mul(self, other: Cherry<U>) -> Cherry<<T as Mul<U>>::Output> {
-        Node::new()
+        let node = Node::new()
             .name("(mul)")
+            .op(Op::Mul)
             .value(self.quantity().clone() * other.quantity().clone())
-            .prev(vec![self.to_json(), other.to_json()].join(","))
+            .children(vec![self.as_subexpr(), other.as_subexpr()])
             .build()
+            .with_sig_figs_of(product_sig_figs(self.sig_figs(), other.sig_figs()));
+        checked(&self, &other, node)
     }
 }
 
@@ -136,15 +149,76 @@ where
 impl<T: 'static + Clone + Debug, U: 'static + Clone + Debug> Div<Cherry<U>> for Cherry<T>
 where
     T: Div<U>,
-    <T as Div<U>>::Output: Clone + Debug,
+    <T as Div<U>>::Output: 'static + Clone + Debug,
 {
     type Output = Cherry<<T as Div<U>>::Output>;
 
+    #[cfg_attr(feature = "track-caller", track_caller)]
     fn div(self, other: Cherry<U>) -> Cherry<<T as Div<U>>::Output> {
-        Node::new()
+        let node = Node::new()
             .name("(div)")
+            .op(Op::Div)
             .value(self.quantity().clone() / other.quantity().clone())
-            .prev(vec![self.to_json(), other.to_json()].join(","))
+            .children(vec![self.as_subexpr(), other.as_subexpr()])
             .build()
+            .with_sig_figs_of(product_sig_figs(self.sig_figs(), other.sig_figs()));
+        checked(&self, &other, node)
+    }
+}
+
+// Checks operands `lhs` and `rhs` (e.g. leaves, which are not checked when built) and then `node`
+// against invariants of running sessions (see `Session::invariant`).
+fn checked<T, U, V>(lhs: &Cherry<T>, rhs: &Cherry<U>, node: Cherry<V>) -> Cherry<V>
+where
+    T: 'static + Clone + Debug,
+    U: 'static + Clone + Debug,
+    V: 'static + Clone + Debug,
+{
+    check_invariants(lhs);
+    check_invariants(rhs);
+    check_invariants(&node);
+    node
+}
+
+// Significant figures of a product or quotient: the fewest of the operands (exact ones ignored).
+fn product_sig_figs(lhs: Option<u32>, rhs: Option<u32>) -> Option<u32> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(lhs.min(rhs)),
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
+// Decimal exponent of the leading digit of `value`.
+fn exponent(value: f32) -> i32 {
+    let formats = format!("{:e}", value);
+    formats
+        .rsplit('e')
+        .next()
+        .and_then(|exponent| exponent.parse().ok())
+        .unwrap_or(0)
+}
+
+// Significant figures of a sum or difference `node`, keeping the last decimal place
+// known in all operands (exact ones ignored).
+fn sum_sig_figs<T, U, V>(lhs: &Cherry<T>, rhs: &Cherry<U>, node: &Cherry<V>) -> Option<u32>
+where
+    T: Clone + Debug,
+    U: Clone + Debug,
+    V: Clone + Debug,
+{
+    let (lhs_figs, rhs_figs) = (lhs.sig_figs(), rhs.sig_figs());
+    if lhs_figs.is_none() && rhs_figs.is_none() {
+        return None;
     }
+    let (lhs_value, rhs_value, value) = match (lhs.value(), rhs.value(), node.value()) {
+        (Ok(lhs), Ok(rhs), Ok(value)) => (lhs, rhs, value),
+        _ => return product_sig_figs(lhs_figs, rhs_figs),
+    };
+    // position of the last significant digit
+    let last = |value: f32, figs: Option<u32>| figs.map(|figs| exponent(value) - figs as i32 + 1);
+    let place = match (last(lhs_value, lhs_figs), last(rhs_value, rhs_figs)) {
+        (Some(lhs), Some(rhs)) => lhs.max(rhs),
+        (lhs, rhs) => lhs.or(rhs)?,
+    };
+    Some((exponent(value) - place + 1).max(1) as u32)
 }
@@ -0,0 +1,201 @@
+use super::lazy::LazyCherry;
+use super::node::Leaf;
+use alloc::{borrow::ToOwned, string::String};
+use core::fmt::{self, Debug, Display};
+use core::ops::{Add, Div, Mul, Sub};
+use core::str::FromStr;
+
+///
+/// Why a formula could not be parsed.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset in the formula where parsing failed.
+    pub position: usize,
+    pub msg: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.msg, self.position)
+    }
+}
+
+// Deepest nesting of parentheses accepted, so untrusted formulas cannot exhaust the stack.
+const MAX_NESTING: usize = 128;
+
+// Most operators accepted, as evaluating nests one call per operator (e.g. `a + a + ..`).
+const MAX_OPERATORS: usize = 1024;
+
+///
+/// Parses formula like `0.9 * fy * A / gamma_m0` into an expression of symbolic leaves.
+///
+/// Formulas are made of variables (`[A-Za-z_][A-Za-z0-9_]*`), number literals
+/// (parsed by `T::from_str` and labeled as written), `+`, `-`, `*`, `/` with the usual
+/// precedence and parentheses.
+/// Variables are bound by the `Env` the expression is evaluated against,
+/// so evaluated trees have the same provenance as expressions written in code.
+///
+/// Formulas nesting parentheses deeper than 128 levels or having more than 1024 operators
+/// are rejected, so untrusted formulas cannot exhaust the stack when evaluated.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Cherries;
+/// use cherries::lazy::Env;
+/// use cherries::parser::{parse, ParseError};
+///
+/// let resistance = parse::<f64>("0.9 * fy * A / gamma_m0").unwrap().labeled("N_Rd");
+/// assert_eq!(resistance.symbols(), vec!["A", "fy", "gamma_m0"]);
+///
+/// let env = Env::new().bind("fy", 355.0).bind("A", 2000.0).bind("gamma_m0", 1.0);
+/// let node = resistance.evaluate(&env).unwrap();
+/// assert_eq!(node.quantity(), &639000.0);
+/// assert!(node.to_json().contains("{\"label\":\"0.9\",\"value\":0.9,\"unit\":\"dimensionless\"}"));
+///
+/// let env = Env::new().bind("a", 1.0).bind("b", 2.0).bind("c", 3.0);
+/// assert_eq!(parse::<f64>("(a + b) * c").unwrap().evaluate(&env).unwrap().quantity(), &9.0);
+/// assert_eq!(parse::<f64>("a + b * c").unwrap().evaluate(&env).unwrap().quantity(), &7.0);
+/// assert_eq!(
+///     parse::<f64>("fy * ").unwrap_err(),
+///     ParseError { position: 5, msg: "expected variable, number or `(`".to_string() }
+/// );
+/// ```
+pub fn parse<T>(formula: &str) -> Result<LazyCherry<T>, ParseError>
+where
+    T: 'static + Clone + Debug + Send + Sync + FromStr,
+    T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>,
+{
+    let mut parser = Parser {
+        formula,
+        position: 0,
+        nesting: 0,
+        operators: 0,
+    };
+    let expr = parser.expr()?;
+    parser.skip_whitespace();
+    if parser.position < formula.len() {
+        return Err(parser.error("expected operator"));
+    }
+    Ok(expr)
+}
+
+struct Parser<'a> {
+    formula: &'a str,
+    position: usize,
+    nesting: usize,
+    operators: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> ParseError {
+        ParseError {
+            position: self.position,
+            msg: msg.to_owned(),
+        }
+    }
+    fn rest(&self) -> &'a str {
+        &self.formula[self.position..]
+    }
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+    // Counts the operator just consumed.
+    fn count_operator(&mut self) -> Result<(), ParseError> {
+        if self.operators == MAX_OPERATORS {
+            return Err(self.error("too many operators"));
+        }
+        self.operators += 1;
+        Ok(())
+    }
+    // Returns next non-whitespace character, which is consumed if it is one of `expected`.
+    fn operator(&mut self, expected: &[char]) -> Option<char> {
+        self.skip_whitespace();
+        let next = self.rest().chars().next().filter(|c| expected.contains(c))?;
+        self.position += next.len_utf8();
+        Some(next)
+    }
+    // Consumes the longest prefix whose characters satisfy `pred`.
+    fn take_while<P: FnMut(usize, char) -> bool>(&mut self, mut pred: P) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| !pred(i, c))
+            .map_or(rest.len(), |(i, _)| i);
+        self.position += len;
+        &rest[..len]
+    }
+    fn expr<T>(&mut self) -> Result<LazyCherry<T>, ParseError>
+    where
+        T: 'static + Clone + Debug + Send + Sync + FromStr,
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>,
+    {
+        let mut lhs = self.term()?;
+        while let Some(op) = self.operator(&['+', '-']) {
+            self.count_operator()?;
+            let rhs = self.term()?;
+            lhs = if op == '+' { lhs + rhs } else { lhs - rhs };
+        }
+        Ok(lhs)
+    }
+    fn term<T>(&mut self) -> Result<LazyCherry<T>, ParseError>
+    where
+        T: 'static + Clone + Debug + Send + Sync + FromStr,
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>,
+    {
+        let mut lhs = self.factor()?;
+        while let Some(op) = self.operator(&['*', '/']) {
+            self.count_operator()?;
+            let rhs = self.factor()?;
+            lhs = if op == '*' { lhs * rhs } else { lhs / rhs };
+        }
+        Ok(lhs)
+    }
+    fn factor<T>(&mut self) -> Result<LazyCherry<T>, ParseError>
+    where
+        T: 'static + Clone + Debug + Send + Sync + FromStr,
+        T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>,
+    {
+        self.skip_whitespace();
+        let start = self.position;
+        match self.rest().chars().next() {
+            Some('(') => {
+                if self.nesting == MAX_NESTING {
+                    return Err(self.error("parentheses nested too deep"));
+                }
+                self.position += 1;
+                self.nesting += 1;
+                let expr = self.expr()?;
+                self.nesting -= 1;
+                match self.operator(&[')']) {
+                    Some(_) => Ok(expr),
+                    None => Err(self.error("expected `)`")),
+                }
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.take_while(|_, c| c.is_ascii_alphanumeric() || c == '_');
+                Ok(LazyCherry::leaf(String::from(name)))
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut exponent = false;
+                let literal = self.take_while(|i, c| {
+                    let sign = exponent && (c == '+' || c == '-');
+                    exponent = i > 0 && (c == 'e' || c == 'E');
+                    c.is_ascii_digit() || c == '.' || exponent || sign
+                });
+                match literal.parse::<T>() {
+                    Ok(value) => Ok(LazyCherry::constant(
+                        Leaf::new().name(String::from(literal)).value(value).build(),
+                    )),
+                    Err(_) => {
+                        self.position = start;
+                        Err(self.error("invalid number"))
+                    }
+                }
+            }
+            _ => Err(self.error("expected variable, number or `(`")),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::export::Tree;
+use super::node::Cherries;
+use super::session::Observer;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+///
+/// Node creation event sent by `Publisher` (requires feature `publish`).
+///
+/// `tree` is the node with its subexpressions one level deep: theirs are left out
+/// (counted as `collapsed`), so events stay small however deep the tree grows.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub seq: u64,
+    pub label: String,
+    pub value: Option<f32>,
+    pub unit: String,
+    pub tree: Tree,
+}
+
+impl Event {
+    ///
+    /// Returns event as json string (e.g. for a WebSocket text frame), as serialized by serde.
+    ///
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+    ///
+    /// Returns event formatted as a Server-Sent Events message.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::export::Tree;
+    /// use cherries::publish::Event;
+    ///
+    /// let event = Event {
+    ///     seq: 0,
+    ///     label: "x".to_string(),
+    ///     value: Some(1.0),
+    ///     unit: "dimensionless".to_string(),
+    ///     tree: Tree::from_json("{\"label\":\"x\",\"value\":1,\"unit\":\"dimensionless\"}").unwrap(),
+    /// };
+    /// assert_eq!(
+    ///     event.to_sse(),
+    ///     "id: 0\nevent: node\ndata: {\
+    ///         \"seq\":0,\"label\":\"x\",\"value\":1.0,\"unit\":\"dimensionless\",\
+    ///         \"tree\":{\"label\":\"x\",\"value\":1.0,\"unit\":\"dimensionless\"}\
+    ///     }\n\n"
+    /// );
+    /// ```
+    pub fn to_sse(&self) -> String {
+        format!("id: {}\nevent: node\ndata: {}\n\n", self.seq, self.to_json())
+    }
+}
+
+///
+/// Observer which publishes built nodes as `Event`s over a channel (requires feature `publish`).
+///
+/// The receiving end can be forwarded to any WebSocket or SSE endpoint.
+/// Events are silently dropped once the receiver is gone.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// use cherries::publish::Publisher;
+/// use cherries::session::Session;
+///
+/// let (publisher, events) = Publisher::channel();
+/// let mut session = Session::new();
+/// session.observe(publisher);
+/// session.run(|_| {
+///     let xy = Leaf::new().value(2).name("x").build() * Leaf::new().value(3).name("y").build();
+///     xy + Leaf::new().value(4).name("z").build()
+/// });
+/// let events = events.try_iter().collect::<Vec<_>>();
+/// assert_eq!(events.len(), 5);
+/// assert_eq!(events[2].seq, 2);
+/// assert_eq!(events[2].label, "(mul)".to_string());
+/// assert_eq!(events[2].value, Some(6.0));
+/// assert_eq!(events[2].tree.subexpr[1].label, "y".to_string());
+/// // subexpressions of subexpressions are only counted
+/// assert_eq!(events[4].tree.subexpr[0].label, "(mul)".to_string());
+/// assert_eq!(events[4].tree.subexpr[0].collapsed, Some(2));
+/// assert!(events[4].tree.subexpr[0].subexpr.is_empty());
+/// ```
+#[derive(Debug)]
+pub struct Publisher {
+    sender: Sender<Event>,
+    seq: u64,
+}
+
+impl Publisher {
+    ///
+    /// Makes new publisher and the receiving end of its channel.
+    ///
+    pub fn channel() -> (Publisher, Receiver<Event>) {
+        let (sender, receiver) = channel();
+        (Publisher { sender, seq: 0 }, receiver)
+    }
+}
+
+impl Observer for Publisher {
+    fn on_build(&mut self, node: &dyn Cherries) {
+        let event = Event {
+            seq: self.seq,
+            label: node.name().to_owned(),
+            value: node.value().ok(),
+            unit: node.symbol(),
+            tree: Tree::from_json(&node.to_json_with_depth(1))
+                .or_else(|_| Tree::from_json(&node.to_json_with_depth(0)))
+                .unwrap_or_else(|_| Tree::bare(node)),
+        };
+        self.seq += 1;
+        let _ = self.sender.send(event);
+    }
+}
@@ -0,0 +1,51 @@
+// Invokes `$m!(storage; module::Quantity, ...)` for every uom si quantity and float storage type.
+macro_rules! for_each_quantity {
+    ( $m:ident ) => {
+        for_each_quantity!($m; f32);
+        for_each_quantity!($m; f64);
+    };
+    ( $m:ident; $v:ident ) => {
+        $m!($v;
+            acceleration::Acceleration,
+            amount_of_substance::AmountOfSubstance,
+            angle::Angle,
+            angular_acceleration::AngularAcceleration,
+            angular_jerk::AngularJerk,
+            angular_velocity::AngularVelocity,
+            area::Area,
+            available_energy::AvailableEnergy,
+            capacitance::Capacitance,
+            electric_charge::ElectricCharge,
+            electric_current::ElectricCurrent,
+            electric_potential::ElectricPotential,
+            electrical_conductance::ElectricalConductance,
+            electrical_resistance::ElectricalResistance,
+            energy::Energy,
+            force::Force,
+            frequency::Frequency,
+            inductance::Inductance,
+            information::Information,
+            information_rate::InformationRate,
+            jerk::Jerk,
+            length::Length,
+            luminance::Luminance,
+            luminous_intensity::LuminousIntensity,
+            magnetic_flux::MagneticFlux,
+            magnetic_flux_density::MagneticFluxDensity,
+            mass::Mass,
+            mass_density::MassDensity,
+            mass_rate::MassRate,
+            momentum::Momentum,
+            power::Power,
+            pressure::Pressure,
+            ratio::Ratio,
+            temperature_interval::TemperatureInterval,
+            thermodynamic_temperature::ThermodynamicTemperature,
+            time::Time,
+            torque::Torque,
+            velocity::Velocity,
+            volume::Volume,
+            volume_rate::VolumeRate
+        );
+    };
+}
@@ -0,0 +1,346 @@
+use super::export::{Tree, Value};
+use super::node::{Cherries, Cherry, Leaf, Visit};
+use alloc::collections::BTreeMap;
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::{self, Debug, Display};
+
+///
+/// Why a tree could not be re-evaluated (or differentiated).
+///
+#[derive(Debug)]
+pub enum ReevaluateError {
+    /// Node `label` was made by operation `op`, which cannot be recomputed from the tree.
+    Unsupported { label: String, op: String },
+    /// Recorded json of a node could not be read.
+    Json(serde_json::Error),
+    /// Tree has no leaf labeled `label`.
+    UnknownLeaf { label: String },
+    /// Tree has no subexpression labeled `label`.
+    UnknownNode { label: String },
+}
+
+impl Display for ReevaluateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReevaluateError::Unsupported { label, op } => {
+                write!(f, "cannot recompute `{}` made by `{}`", label, op)
+            }
+            ReevaluateError::Json(err) => write!(f, "invalid recorded json: {}", err),
+            ReevaluateError::UnknownLeaf { label } => write!(f, "no leaf labeled `{}`", label),
+            ReevaluateError::UnknownNode { label } => {
+                write!(f, "no subexpression labeled `{}`", label)
+            }
+        }
+    }
+}
+
+impl From<serde_json::Error> for ReevaluateError {
+    fn from(err: serde_json::Error) -> Self {
+        ReevaluateError::Json(err)
+    }
+}
+
+///
+/// Tree with substituted leaf values, made by `Cherry::rebind`.
+///
+#[derive(Debug)]
+pub struct Rebind<'a, T: Clone + Debug> {
+    node: &'a Cherry<T>,
+    values: &'a BTreeMap<String, f32>,
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    ///
+    /// Returns tree whose leaves labeled as keys of `values` have the mapped values
+    /// (in base units), to be re-evaluated with `Rebind::reevaluate`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::export::Value;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let w = Leaf::new().value(2.0_f64).name("w").build();
+    /// let h = Leaf::new().value(3.0).name("h").build();
+    /// let t = Leaf::new().value(0.5).name("t").build();
+    /// let area = (w * h).labeled("area");
+    /// let volume = (area * t).labeled("volume");
+    ///
+    /// let mut what_if = BTreeMap::new();
+    /// what_if.insert("h".to_string(), 5.0);
+    /// let tree = volume.rebind(&what_if).reevaluate().unwrap();
+    /// assert_eq!(tree.value, Value::Number(5.0));
+    /// assert_eq!(tree.subexpr[0].label, "area".to_string());
+    /// assert_eq!(tree.subexpr[0].value, Value::Number(10.0));
+    /// assert_eq!(tree.subexpr[1].value, Value::Number(0.5));
+    /// // the recorded tree itself is not changed
+    /// assert_eq!(volume.value(), Ok(3.0));
+    ///
+    /// let rounded = volume.map(|v: &f64| v.round()).labeled("rounded");
+    /// assert!(rounded.rebind(&what_if).reevaluate().is_err());
+    /// ```
+    pub fn rebind<'a>(&'a self, values: &'a BTreeMap<String, f32>) -> Rebind<'a, T> {
+        Rebind { node: self, values }
+    }
+    ///
+    /// Returns normalized sensitivity of the node to leaf `leaf`
+    /// (relative change of the node over relative change `delta` of the leaf, e.g. `0.01` for 1%),
+    /// computed by re-evaluating the tree with the perturbed leaf as `Rebind::reevaluate` does.
+    ///
+    /// The result is recorded as `((perturbed - value) / value) / delta`, labeled
+    /// `sensitivity of {label} to {leaf}`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    ///
+    /// let b = Leaf::new().value(0.3).name("b").build();
+    /// let d = Leaf::new().value(0.5).name("d").build();
+    /// let six = Leaf::new().value(6.0).name("6").build();
+    /// let modulus = (b * d.clone() * d / six).labeled("W");
+    ///
+    /// let to_b = modulus.sensitivity("b", 0.01).unwrap();
+    /// let to_d = modulus.sensitivity("d", 0.01).unwrap();
+    /// assert_eq!(to_b.name(), "sensitivity of W to b");
+    /// assert!((to_b.quantity() - 1.0).abs() < 1e-3);
+    /// // squared, so `d` dominates
+    /// assert!((to_d.quantity() - 2.0).abs() < 2e-2);
+    /// assert!(modulus.sensitivity("h", 0.01).is_err());
+    /// ```
+    pub fn sensitivity(&self, leaf: &str, delta: f32) -> Result<Cherry<f32>, ReevaluateError> {
+        let expr = expr_of(self)?;
+        let number = |tree: &Tree| match tree.value {
+            Value::Number(value) => Ok(value),
+            _ => Err(ReevaluateError::Unsupported {
+                label: tree.label.clone(),
+                op: String::from("sensitivity"),
+            }),
+        };
+        let value = number(&expr.tree)?;
+        let original = find_leaf(&expr, leaf).ok_or_else(|| ReevaluateError::UnknownLeaf {
+            label: String::from(leaf),
+        })?;
+        let mut values = BTreeMap::new();
+        values.insert(String::from(leaf), number(original)? * (1.0 + delta));
+        let label = expr.tree.label.clone();
+        let perturbed = number(&evaluate(expr, &values, None)?.0)?;
+        let value = Leaf::new().value(value).name(label.clone()).build();
+        let perturbed = Leaf::new()
+            .value(perturbed)
+            .name(format!("{} with {} perturbed", label, leaf))
+            .build();
+        let delta = Leaf::new().value(delta).name("delta").build();
+        Ok((((perturbed - value.clone()) / value) / delta)
+            .labeled(format!("sensitivity of {} to {}", label, leaf)))
+    }
+
+    ///
+    /// Returns tree whose first subexpression labeled `label` is swapped for `replacement`
+    /// (as `replace` does) and every node depending on it recomputed
+    /// (as `Rebind::reevaluate` does), for what-if edits of calculations.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::export::Value;
+    ///
+    /// let w = Leaf::new().value(2.0).name("w").build();
+    /// let h = Leaf::new().value(3.0).name("h").build();
+    /// let t = Leaf::new().value(0.5).name("t").build();
+    /// let volume = ((w * h).labeled("area") * t).labeled("volume");
+    ///
+    /// let measured = Leaf::new().value(6.4).name("area").build();
+    /// let tree = volume.replace_reevaluated("area", &measured).unwrap();
+    /// assert_eq!(tree.value, Value::Number(3.2));
+    /// assert_eq!(tree.subexpr[0].value, Value::Number(6.4));
+    /// assert!(tree.subexpr[0].subexpr.is_empty());
+    ///
+    /// let w = Leaf::new().value(2.5).name("w").build();
+    /// let tree = volume.replace_reevaluated("w", &w).unwrap();
+    /// assert_eq!(tree.subexpr[0].value, Value::Number(7.5));
+    /// assert_eq!(tree.value, Value::Number(3.75));
+    /// assert!(volume.replace_reevaluated("depth", &w).is_err());
+    /// ```
+    pub fn replace_reevaluated<R: Cherries + ?Sized>(
+        &self,
+        label: &str,
+        replacement: &R,
+    ) -> Result<Tree, ReevaluateError> {
+        let (node, path) = self
+            .replaced(label, replacement.as_subexpr())
+            .ok_or_else(|| ReevaluateError::UnknownNode {
+                label: String::from(label),
+            })?;
+        evaluate(expr_of(&node)?, &BTreeMap::new(), Some(&path)).map(|(tree, _)| tree)
+    }
+}
+
+fn find_leaf<'a>(expr: &'a Expr, label: &str) -> Option<&'a Tree> {
+    if expr.subexpr.is_empty() {
+        Some(&expr.tree).filter(|tree| tree.label == label)
+    } else {
+        expr.subexpr.iter().find_map(|sub| find_leaf(sub, label))
+    }
+}
+
+impl<'a, T: Clone + Debug> Rebind<'a, T> {
+    ///
+    /// Returns tree with substituted leaves and every node depending on them recomputed.
+    ///
+    /// Nodes made by `+`, `-`, `*`, `/`, `mean!`, `weighted_sum!`, `clamp` and `override_with`
+    /// are recomputed (also after being relabeled);
+    /// other nodes depending on a substituted leaf, and nodes with recorded uncertainty
+    /// (which is not recomputed), are reported as `Unsupported`.
+    ///
+    pub fn reevaluate(&self) -> Result<Tree, ReevaluateError> {
+        evaluate(expr_of(self.node)?, self.values, None).map(|(tree, _)| tree)
+    }
+}
+
+// Recorded node with the name of the operation it was made by (empty for leaves).
+pub(crate) struct Expr {
+    pub(crate) tree: Tree,
+    pub(crate) op: String,
+    pub(crate) subexpr: Vec<Expr>,
+}
+
+impl Expr {
+    // Expression of a tree read from json.
+    fn from_tree(mut tree: Tree) -> Expr {
+        let subexpr = core::mem::replace(&mut tree.subexpr, Vec::new());
+        Expr {
+            op: op_of(&tree),
+            tree,
+            subexpr: subexpr.into_iter().map(Expr::from_tree).collect(),
+        }
+    }
+}
+
+pub(crate) fn expr_of<T: Clone + Debug>(node: &Cherry<T>) -> Result<Expr, ReevaluateError> {
+    // Nodes whose subexpressions are still being visited, innermost last.
+    fn close(stack: &mut Vec<Expr>, depth: usize, root: &mut Option<Expr>) {
+        while stack.len() > depth {
+            if let Some(expr) = stack.pop() {
+                match stack.last_mut() {
+                    Some(parent) => parent.subexpr.push(expr),
+                    None => *root = Some(expr),
+                }
+            }
+        }
+    }
+    let (mut stack, mut root, mut error) = (Vec::new(), None, None);
+    node.walk(usize::max_value(), |visit| match visit {
+        Visit::Node { depth, head, .. } => {
+            close(&mut stack, depth, &mut root);
+            match serde_json::from_str::<Tree>(&format!("{}}}", head)) {
+                Ok(tree) => stack.push(Expr {
+                    op: op_of(&tree),
+                    tree,
+                    subexpr: Vec::new(),
+                }),
+                Err(err) => error = error.take().or(Some(err)),
+            }
+        }
+        Visit::Json { depth, json } => {
+            close(&mut stack, depth, &mut root);
+            match serde_json::from_str::<Vec<Tree>>(&format!("[{}]", json)) {
+                Ok(trees) => {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.subexpr.extend(trees.into_iter().map(Expr::from_tree));
+                    }
+                }
+                Err(err) => error = error.take().or(Some(err)),
+            }
+        }
+    });
+    close(&mut stack, 0, &mut root);
+    match (error, root) {
+        (Some(err), _) => Err(err.into()),
+        (None, Some(root)) => Ok(root),
+        (None, None) => Err(ReevaluateError::Unsupported {
+            label: String::from(node.name()),
+            op: String::from("root"),
+        }),
+    }
+}
+
+// Returns recomputed tree and whether its value changed
+// (always for the node at path `replaced`, given as indices of subexpressions).
+fn evaluate(
+    expr: Expr,
+    values: &BTreeMap<String, f32>,
+    replaced: Option<&[usize]>,
+) -> Result<(Tree, bool), ReevaluateError> {
+    if replaced.map_or(false, <[usize]>::is_empty) {
+        return evaluate(expr, values, None).map(|(tree, _)| (tree, true));
+    }
+    let Expr { mut tree, op, subexpr } = expr;
+    if subexpr.is_empty() {
+        let value = values.get(&tree.label).map(|&value| Value::Number(value));
+        let changed = value.as_ref().map_or(false, |value| *value != tree.value);
+        if let Some(value) = value {
+            tree.value = value;
+        }
+        return Ok((tree, changed));
+    }
+    let mut changed = false;
+    for (i, sub) in subexpr.into_iter().enumerate() {
+        let path = replaced.filter(|path| path[0] == i).map(|path| &path[1..]);
+        let (sub, sub_changed) = evaluate(sub, values, path)?;
+        changed |= sub_changed;
+        tree.subexpr.push(sub);
+    }
+    if changed {
+        let numbers = tree
+            .subexpr
+            .iter()
+            .map(|sub| match sub.value {
+                Value::Number(number) => Some(number),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let value = numbers.as_ref().and_then(|numbers| apply(&op, numbers));
+        match value.filter(|_| tree.uncertainty.is_none()) {
+            Some(value) => tree.value = Value::Number(value),
+            None => {
+                return Err(ReevaluateError::Unsupported {
+                    label: tree.label.clone(),
+                    op,
+                })
+            }
+        }
+    }
+    Ok((tree, changed))
+}
+
+// Name of the operation recorded for `tree` (empty for leaves).
+fn op_of(tree: &Tree) -> String {
+    tree.op.as_ref().map_or_else(String::new, |op| String::from(op.as_str()))
+}
+
+// Value of operation `op` applied to values of subexpressions.
+fn apply(op: &str, args: &[f32]) -> Option<f32> {
+    match (op, args) {
+        ("add", [lhs, rhs]) => Some(lhs + rhs),
+        ("sub", [lhs, rhs]) => Some(lhs - rhs),
+        ("mul", [lhs, rhs]) => Some(lhs * rhs),
+        ("div", [lhs, rhs]) => Some(lhs / rhs),
+        ("mean", _) => Some(args.iter().sum::<f32>() / args.len() as f32),
+        ("weighted_sum", _) if args.len() % 2 == 0 => {
+            Some(args.chunks(2).map(|pair| pair[0] * pair[1]).sum())
+        }
+        ("clamp", [value, lo, hi]) => Some(if value < lo {
+            *lo
+        } else if value > hi {
+            *hi
+        } else {
+            *value
+        }),
+        ("override", [_, replacement]) => Some(*replacement),
+        ("to_f64", [value]) | ("to_f32", [value]) | ("to_decimal", [value]) => Some(*value),
+        _ => None,
+    }
+}
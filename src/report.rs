@@ -0,0 +1,366 @@
+use super::export::{Tree, Value};
+use super::node::Cherries;
+use super::units::DisplayUnits;
+
+///
+/// Renders expression trees as Markdown or HTML reports.
+///
+/// Values are written in `units` (base units by default) and, in dual-unit mode,
+/// also in a second unit system side by side, e.g. `12.7 mm (0.5 in)`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// use cherries::report::Renderer;
+/// use cherries::units::DisplayUnits;
+/// extern crate uom;
+/// use uom::si::{f64::*, length::{meter, millimeter}};
+///
+/// let t = Leaf::new().value(Length::new::<millimeter>(12.7)).name("t").build();
+/// let n = Leaf::new().value(2.0).name("n").build();
+/// let renderer = Renderer::new()
+///     .units(DisplayUnits::new().prefer::<Length, millimeter>())
+///     .dual(DisplayUnits::us_customary());
+/// assert_eq!(
+///     renderer.to_markdown(&(n * t).labeled("2t")).unwrap(),
+///     "- **2t** = 25.4 mm (1 in)\n  - **n** = 2\n  - **t** = 12.7 mm (0.5 in)\n"
+/// );
+/// assert_eq!(
+///     renderer.to_html(&Leaf::new().value(Length::new::<meter>(0.0254)).name("<t>").build()).unwrap(),
+///     "<ul><li><b>&lt;t&gt;</b> = 25.4 mm (1 in)</li></ul>"
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Renderer {
+    primary: DisplayUnits,
+    secondary: Option<DisplayUnits>,
+    collapse: bool,
+}
+
+impl Renderer {
+    ///
+    /// Makes renderer writing values in base units only.
+    ///
+    pub fn new() -> Self {
+        Renderer::default()
+    }
+    ///
+    /// Writes values in `units`.
+    ///
+    pub fn units(self, units: DisplayUnits) -> Self {
+        Renderer {
+            primary: units,
+            ..self
+        }
+    }
+    ///
+    /// Also writes every value in `units` in parentheses.
+    ///
+    pub fn dual(self, units: DisplayUnits) -> Self {
+        Renderer {
+            secondary: Some(units),
+            ..self
+        }
+    }
+    ///
+    /// Writes nodes marked by `Cherry::detail` with their value and count of subexpressions only
+    /// (see `export::Tree::collapsed`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Leaf;
+    /// use cherries::report::Renderer;
+    ///
+    /// let b = Leaf::new().value(0.3).name("b").build();
+    /// let h = Leaf::new().value(0.5).name("h").build();
+    /// let fy = Leaf::new().value(355.0).name("f_y").build();
+    /// let resistance = ((b * h).labeled("A").detail() * fy).labeled("N_Rd");
+    /// assert_eq!(
+    ///     Renderer::new().collapse_details().to_markdown(&resistance).unwrap(),
+    ///     "- **N\\_Rd** = 53.25\n  - **A** = 0.15 (2 subexpressions collapsed)\n  - **f\\_y** = 355\n"
+    /// );
+    /// assert_eq!(
+    ///     Renderer::new().collapse_details().to_html(&resistance).unwrap(),
+    ///     "<ul><li><b>N_Rd</b> = 53.25<ul><li><b>A</b> = 0.15 <small>(2 subexpressions collapsed)</small></li>\
+    ///         <li><b>f_y</b> = 355</li></ul></li></ul>"
+    /// );
+    /// assert_eq!(Renderer::new().to_markdown(&resistance).unwrap().lines().count(), 5);
+    /// ```
+    pub fn collapse_details(self) -> Self {
+        Renderer {
+            collapse: true,
+            ..self
+        }
+    }
+    ///
+    /// Returns tree of `node` as nested Markdown list.
+    ///
+    /// Comments are written as footnotes after the list.
+    /// Clauses recorded by `Cherry::per` are cited by number (e.g. `[1]`)
+    /// and listed once each under **References** at the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Leaf;
+    /// use cherries::report::Renderer;
+    ///
+    /// let fc = Leaf::new().value(24.5).name("fc").build().comment("per test report TR-1289");
+    /// let k = Leaf::new().value(0.85).name("k").build();
+    /// assert_eq!(
+    ///     Renderer::new().to_markdown(&(fc * k).comment("reduced strength")).unwrap(),
+    ///     "- **(mul)** = 20.825[^1]\n  - **fc** = 24.5[^2]\n  - **k** = 0.85\n\
+    ///      \n\
+    ///      [^1]: reduced strength\n\
+    ///      [^2]: per test report TR-1289\n"
+    /// );
+    ///
+    /// let fy = Leaf::new().value(355.0).name("f_y").build().per("EN 1993-1-1 §3.2.1");
+    /// let gamma = Leaf::new().value(1.0).name("gamma_M0").build().per("EN 1993-1-1 §6.1");
+    /// let fyd = (fy / gamma).labeled("f_yd").per("EN 1993-1-1 §6.1").per("EN 1993-1-1 §6.2.1");
+    /// assert_eq!(
+    ///     Renderer::new().to_markdown(&fyd).unwrap(),
+    ///     "- **f\\_yd** = 355 [1][2]\n  - **f\\_y** = 355 [3]\n  - **gamma\\_M0** = 1 [1]\n\
+    ///      \n\
+    ///      **References**\n\
+    ///      \n\
+    ///      1. EN 1993-1-1 §6.1\n\
+    ///      2. EN 1993-1-1 §6.2.1\n\
+    ///      3. EN 1993-1-1 §3.2.1\n"
+    /// );
+    /// ```
+    pub fn to_markdown(&self, node: &dyn Cherries) -> serde_json::Result<String> {
+        let mut out = String::new();
+        let (primary, secondary) = self.trees(node)?;
+        let (mut notes, mut references) = (Vec::new(), Vec::new());
+        markdown(&mut out, &primary, secondary.as_ref(), 0, &mut notes, &mut references);
+        if !notes.is_empty() {
+            out.push('\n');
+            for (i, note) in notes.iter().enumerate() {
+                out.push_str(&format!("[^{}]: {}\n", i + 1, escape_markdown(note)));
+            }
+        }
+        if !references.is_empty() {
+            out.push_str("\n**References**\n\n");
+            for (i, reference) in references.iter().enumerate() {
+                out.push_str(&format!("{}. {}\n", i + 1, escape_markdown(reference)));
+            }
+        }
+        Ok(out)
+    }
+    ///
+    /// Returns tree of `node` as nested HTML list.
+    ///
+    /// Items of nodes with a recorded origin have attribute `data-origin`
+    /// and are filled with its color (`Origin::color`), and comments are shown as tooltips.
+    /// Clauses recorded by `Cherry::per` are cited by number
+    /// and listed once each in list `<ol class="references">` after the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Leaf, Origin};
+    /// use cherries::report::Renderer;
+    ///
+    /// let gamma = Leaf::new().value(1.1).name("gamma").build().with_origin(Origin::Assumed);
+    /// assert_eq!(
+    ///     Renderer::new().to_html(&gamma).unwrap(),
+    ///     "<ul><li data-origin=\"assumed\" style=\"background-color:#fdcdac\"><b>gamma</b> = 1.1</li></ul>"
+    /// );
+    /// assert_eq!(
+    ///     Renderer::new().to_html(&gamma.clone().labeled("g").comment("<conservative>")).unwrap(),
+    ///     "<ul><li data-origin=\"assumed\" style=\"background-color:#fdcdac\" title=\"&lt;conservative&gt;\">\
+    ///         <b>g</b> = 1.1</li></ul>"
+    /// );
+    /// assert_eq!(
+    ///     Renderer::new().to_html(&gamma.per("EN 1993-1-1 §6.1")).unwrap(),
+    ///     "<ul><li data-origin=\"assumed\" style=\"background-color:#fdcdac\"><b>gamma</b> = 1.1 \
+    ///         <cite>[1]</cite></li></ul><ol class=\"references\"><li>EN 1993-1-1 §6.1</li></ol>"
+    /// );
+    /// ```
+    pub fn to_html(&self, node: &dyn Cherries) -> serde_json::Result<String> {
+        let mut out = String::new();
+        let (primary, secondary) = self.trees(node)?;
+        let mut references = Vec::new();
+        out.push_str("<ul>");
+        html(&mut out, &primary, secondary.as_ref(), &mut references);
+        out.push_str("</ul>");
+        if !references.is_empty() {
+            out.push_str("<ol class=\"references\">");
+            for reference in references {
+                out.push_str(&format!("<li>{}</li>", escape_html(reference)));
+            }
+            out.push_str("</ol>");
+        }
+        Ok(out)
+    }
+    fn trees(&self, node: &dyn Cherries) -> serde_json::Result<(Tree, Option<Tree>)> {
+        let tree = |units: &DisplayUnits| {
+            let tree = Tree::from_json(&units.to_json(node))?;
+            Ok(if self.collapse { tree.collapsed() } else { tree })
+        };
+        let secondary = match &self.secondary {
+            Some(units) => Some(tree(units)?),
+            None => None,
+        };
+        Ok((tree(&self.primary)?, secondary))
+    }
+}
+
+fn quantity(tree: &Tree) -> String {
+    let value = match &tree.value {
+        Value::Number(value) => value.to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Text(value) => value.clone(),
+        Value::Interval(lower, upper) => format!("[{}, {}]", lower, upper),
+    };
+    match (&tree.value, tree.unit.as_str()) {
+        (Value::Number(_), unit) | (Value::Interval(..), unit) if unit != "dimensionless" => {
+            format!("{} {}", value, unit)
+        }
+        _ => value,
+    }
+}
+
+// `primary` value followed by `secondary` one (if it differs).
+fn quantities(primary: &Tree, secondary: Option<&Tree>) -> String {
+    let first = quantity(primary);
+    match secondary.map(quantity) {
+        Some(ref second) if second != &first => format!("{} ({})", first, second),
+        _ => first,
+    }
+}
+
+fn collapsed(count: usize) -> String {
+    match count {
+        1 => String::from("1 subexpression collapsed"),
+        _ => format!("{} subexpressions collapsed", count),
+    }
+}
+
+fn escape_markdown(s: &str) -> String {
+    s.chars().fold(String::with_capacity(s.len()), |mut acc, c| {
+        if "\\`*_[]<>#".contains(c) {
+            acc.push('\\');
+        }
+        acc.push(c);
+        acc
+    })
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Numbers (from 1) of the clauses `tree` follows in `references`, added when first cited.
+fn citations<'a>(tree: &'a Tree, references: &mut Vec<&'a str>) -> Vec<usize> {
+    tree.references
+        .iter()
+        .map(|reference| match references.iter().position(|cited| cited == reference) {
+            Some(i) => i + 1,
+            None => {
+                references.push(reference);
+                references.len()
+            }
+        })
+        .collect()
+}
+
+// Comments of the nodes written are collected into `notes`, numbered in order,
+// and clauses they follow into `references`.
+fn markdown<'a>(
+    out: &mut String,
+    primary: &'a Tree,
+    secondary: Option<&Tree>,
+    depth: usize,
+    notes: &mut Vec<&'a str>,
+    references: &mut Vec<&'a str>,
+) {
+    let cited = citations(primary, references);
+    let cited = if cited.is_empty() {
+        String::new()
+    } else {
+        let numbers = cited.iter().map(|i| format!("[{}]", i)).collect::<String>();
+        format!(" {}", numbers)
+    };
+    let footnote = match &primary.comment {
+        Some(comment) => {
+            notes.push(comment);
+            format!("[^{}]", notes.len())
+        }
+        None => String::new(),
+    };
+    out.push_str(&format!(
+        "{}- **{}** = {}{}{}{}\n",
+        "  ".repeat(depth),
+        escape_markdown(&primary.label),
+        escape_markdown(&quantities(primary, secondary)),
+        cited,
+        footnote,
+        primary.collapsed.map_or_else(String::new, |count| format!(" ({})", collapsed(count)))
+    ));
+    for (i, subexpr) in primary.subexpr.iter().enumerate() {
+        markdown(
+            out,
+            subexpr,
+            secondary.and_then(|tree| tree.subexpr.get(i)),
+            depth + 1,
+            notes,
+            references,
+        );
+    }
+}
+
+fn html<'a>(
+    out: &mut String,
+    primary: &'a Tree,
+    secondary: Option<&Tree>,
+    references: &mut Vec<&'a str>,
+) {
+    let mut origin = primary.origin.map_or_else(String::new, |origin| {
+        format!(
+            " data-origin=\"{}\" style=\"background-color:{}\"",
+            origin.as_str(),
+            origin.color()
+        )
+    });
+    if let Some(comment) = &primary.comment {
+        origin.push_str(&format!(" title=\"{}\"", escape_html(comment)));
+    }
+    out.push_str(&format!(
+        "<li{}><b>{}</b> = {}",
+        origin,
+        escape_html(&primary.label),
+        escape_html(&quantities(primary, secondary))
+    ));
+    let cited = citations(primary, references);
+    if !cited.is_empty() {
+        let numbers = cited.iter().map(|i| format!("[{}]", i)).collect::<String>();
+        out.push_str(&format!(" <cite>{}</cite>", numbers));
+    }
+    if let Some(count) = primary.collapsed {
+        out.push_str(&format!(" <small>({})</small>", collapsed(count)));
+    }
+    if !primary.subexpr.is_empty() {
+        out.push_str("<ul>");
+        for (i, subexpr) in primary.subexpr.iter().enumerate() {
+            html(
+                out,
+                subexpr,
+                secondary.and_then(|tree| tree.subexpr.get(i)),
+                references,
+            );
+        }
+        out.push_str("</ul>");
+    }
+    out.push_str("</li>");
+}
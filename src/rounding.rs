@@ -0,0 +1,312 @@
+use super::node::{Cherries, Cherry, Leaf, Node};
+use std::fmt::Debug;
+
+///
+/// Values which can be rounded through their raw floating point number.
+///
+/// For uom quantities the raw number is in base units (e.g. meter for length).
+///
+pub trait Round: Sized {
+    fn map_raw<F: Fn(f64) -> f64>(&self, f: F) -> Self;
+}
+
+impl Round for f64 {
+    fn map_raw<F: Fn(f64) -> f64>(&self, f: F) -> Self {
+        f(*self)
+    }
+}
+
+impl Round for f32 {
+    fn map_raw<F: Fn(f64) -> f64>(&self, f: F) -> Self {
+        f(f64::from(*self)) as f32
+    }
+}
+
+impl<D, U, V> Round for uom::si::Quantity<D, U, V>
+where
+    D: uom::si::Dimension + ?Sized,
+    U: uom::si::Units<V> + ?Sized,
+    V: uom::num::Num + uom::Conversion<V> + Round,
+{
+    fn map_raw<F: Fn(f64) -> f64>(&self, f: F) -> Self {
+        uom::si::Quantity {
+            dimension: std::marker::PhantomData,
+            units: std::marker::PhantomData,
+            value: self.value.map_raw(f),
+        }
+    }
+}
+
+///
+/// Number of digits kept by a rounding `Policy`.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// Digits after the decimal point (negative rounds to tens, hundreds, ...).
+    Decimals(i32),
+    /// Significant figures.
+    Significant(u32),
+}
+
+///
+/// How a rounding `Policy` resolves ties.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Rounds half away from zero (`2.5` to `3`).
+    HalfAwayFromZero,
+    /// Rounds half to even (`2.5` to `2`), a.k.a. banker's rounding.
+    HalfEven,
+}
+
+///
+/// Rounding policy.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::rounding::Policy;
+///
+/// assert_eq!(Policy::decimals(1).apply(0.25), 0.3);
+/// assert_eq!(Policy::decimals(1).half_even().apply(0.25), 0.2);
+/// assert_eq!(Policy::significant(3).apply(123456.0), 123000.0);
+/// assert_eq!(Policy::significant(2).apply(0.0012345), 0.0012);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy {
+    pub precision: Precision,
+    pub mode: Mode,
+}
+
+impl Policy {
+    ///
+    /// Makes policy keeping `decimals` digits after the decimal point.
+    ///
+    pub fn decimals(decimals: i32) -> Self {
+        Policy {
+            precision: Precision::Decimals(decimals),
+            mode: Mode::HalfAwayFromZero,
+        }
+    }
+    ///
+    /// Makes policy keeping `figures` significant figures.
+    ///
+    pub fn significant(figures: u32) -> Self {
+        Policy {
+            precision: Precision::Significant(figures),
+            mode: Mode::HalfAwayFromZero,
+        }
+    }
+    ///
+    /// Returns same policy resolving ties to even.
+    ///
+    pub fn half_even(self) -> Self {
+        Policy {
+            mode: Mode::HalfEven,
+            ..self
+        }
+    }
+    ///
+    /// Rounds `value` according to the policy.
+    ///
+    // Ties are exactly representable halves, so they are compared strictly.
+    #[allow(clippy::float_cmp)]
+    pub fn apply(&self, value: f64) -> f64 {
+        if !value.is_finite() || value == 0.0 {
+            return value;
+        }
+        let decimals = match self.precision {
+            Precision::Decimals(decimals) => decimals,
+            Precision::Significant(figures) => {
+                figures as i32 - 1 - value.abs().log10().floor() as i32
+            }
+        };
+        let scale = 10_f64.powi(decimals);
+        let scaled = value * scale;
+        let rounded = match self.mode {
+            Mode::HalfAwayFromZero => scaled.round(),
+            Mode::HalfEven => {
+                let rounded = scaled.round();
+                if (rounded - scaled).abs() == 0.5 {
+                    2.0 * (scaled / 2.0).round()
+                } else {
+                    rounded
+                }
+            }
+        };
+        rounded / scale
+    }
+    // Leaf recording the policy in a `(round)` node.
+    fn leaf(&self) -> Cherry<i64> {
+        let (name, digits) = match self.precision {
+            Precision::Decimals(decimals) => ("decimals", i64::from(decimals)),
+            Precision::Significant(figures) => ("significant_figures", i64::from(figures)),
+        };
+        match self.mode {
+            Mode::HalfAwayFromZero => Leaf::new().name(name).value(digits).build(),
+            Mode::HalfEven => Leaf::new()
+                .name(format!("{} (half-even)", name))
+                .value(digits)
+                .build(),
+        }
+    }
+}
+
+thread_local! {
+    static PRINT_POLICY: std::cell::Cell<Option<Policy>> = std::cell::Cell::new(None);
+}
+
+///
+/// Sets policy applied to every value printed by `to_json` on the current thread
+/// (`None` prints values as they are), and returns the previous one.
+///
+/// Values are rounded after conversion to display units (see `units::set_display_units`).
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::rounding::{set_print_policy, Policy};
+///
+/// let x = Leaf::new().value(2.0 / 3.0).name("x").build();
+/// let y = (x.clone() * Leaf::new().value(2.0).name("y").build()).labeled("z");
+/// set_print_policy(Some(Policy::significant(3)));
+/// assert_eq!(x.to_json(), "{\"label\":\"x\",\"value\":0.667,\"unit\":\"dimensionless\"}".to_string());
+/// assert_eq!(
+///     y.to_json(),
+///     "{\"label\":\"z\",\"value\":1.33,\"unit\":\"dimensionless\",\"op\":\"mul\",\"subexpr\":[\
+///         {\"label\":\"x\",\"value\":0.667,\"unit\":\"dimensionless\"},\
+///         {\"label\":\"y\",\"value\":2,\"unit\":\"dimensionless\"}\
+///     ]}".to_string()
+/// );
+/// set_print_policy(None);
+/// assert_eq!(x.quantity(), &(2.0 / 3.0));
+/// ```
+pub fn set_print_policy(policy: Option<Policy>) -> Option<Policy> {
+    PRINT_POLICY.with(|current| current.replace(policy))
+}
+
+///
+/// Returns policy applied to printed values on the current thread.
+///
+pub fn print_policy() -> Option<Policy> {
+    PRINT_POLICY.with(|current| current.get())
+}
+
+impl<T: Clone + Debug + Round> Cherry<T> {
+    ///
+    /// Rounds quantity according to `policy` and returns node labeled `(round)`.
+    ///
+    /// Both the original node and the policy (as a leaf, e.g. `significant_figures`) are recorded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::rounding::Policy;
+    /// extern crate uom;
+    /// use uom::si::{f64::*, force::kilonewton};
+    ///
+    /// let n = Leaf::new().value(Force::new::<kilonewton>(12.345)).name("N").build();
+    /// let rounded = n.rounded(Policy::significant(3).half_even());
+    /// assert!((rounded.quantity().get::<kilonewton>() - 12.3).abs() < 1e-9);
+    /// assert_eq!(
+    ///     rounded.to_json(),
+    ///     "{\"label\":\"(round)\",\"value\":12300,\"unit\":\"m^1 kg^1 s^-2\",\"dimensions\":{\"L\":1,\"M\":1,\"T\":-2},\"op\":\"round\",\"subexpr\":[\
+    ///         {\"label\":\"N\",\"value\":12345,\"unit\":\"m^1 kg^1 s^-2\",\"dimensions\":{\"L\":1,\"M\":1,\"T\":-2}},\
+    ///         {\"label\":\"significant_figures (half-even)\",\"value\":3,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// ```
+    pub fn rounded(&self, policy: Policy) -> Cherry<T> {
+        Node::new()
+            .name("(round)")
+            .op("round")
+            .value(self.quantity().map_raw(|value| policy.apply(value)))
+            .children(vec![self.as_subexpr(), policy.leaf().as_subexpr()])
+            .build()
+    }
+    ///
+    /// Rounds quantity to `decimals` decimal places and returns node labeled `(round)`.
+    /// Both the original node and `decimals` are recorded, so validators applied to the result
+    /// check the value at the precision the code defines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::validate::Validate;
+    ///
+    /// let ratio = Leaf::new().value(1.0000004_f64).name("utilization").build();
+    /// assert!(ratio.clone().validate("must be <= 1", |v| *v <= 1.0).into_result().is_err());
+    /// let rounded = ratio.rounded_for_check(3);
+    /// assert_eq!(rounded.quantity(), &1.0);
+    /// assert_eq!(
+    ///     rounded.to_json(),
+    ///     "{\"label\":\"(round)\",\"value\":1,\"unit\":\"dimensionless\",\"op\":\"round\",\"subexpr\":[\
+    ///         {\"label\":\"utilization\",\"value\":1.0000004,\"unit\":\"dimensionless\"},\
+    ///         {\"label\":\"decimals\",\"value\":3,\"unit\":\"dimensionless\"}\
+    ///     ]}".to_string()
+    /// );
+    /// assert!(rounded.validate("must be <= 1", |v| *v <= 1.0).into_result().is_ok());
+    /// ```
+    pub fn rounded_for_check(&self, decimals: i32) -> Cherry<T> {
+        self.rounded(Policy::decimals(decimals))
+    }
+}
+
+macro_rules! impl_round_in {
+    ( $v:ident; $( $q:ident :: $Q:ident ),* ) => {
+        $(
+            impl Cherry<uom::si::$v::$Q> {
+                ///
+                /// Returns largest integer less than or equal to quantity in unit `N`,
+                /// labeled `(floor: {abbreviation of N})`.
+                ///
+                pub fn floor_in<N>(&self) -> Self
+                where
+                    N: uom::si::$q::Unit + uom::Conversion<$v, T = $v>,
+                {
+                    self.round_in_with::<N, _>("floor", |q| q.floor::<N>())
+                }
+                ///
+                /// Returns smallest integer greater than or equal to quantity in unit `N`,
+                /// labeled `(ceil: {abbreviation of N})`.
+                ///
+                pub fn ceil_in<N>(&self) -> Self
+                where
+                    N: uom::si::$q::Unit + uom::Conversion<$v, T = $v>,
+                {
+                    self.round_in_with::<N, _>("ceil", |q| q.ceil::<N>())
+                }
+                ///
+                /// Returns nearest integer (half away from zero) to quantity in unit `N`,
+                /// labeled `(round: {abbreviation of N})`.
+                ///
+                pub fn round_in<N>(&self) -> Self
+                where
+                    N: uom::si::$q::Unit + uom::Conversion<$v, T = $v>,
+                {
+                    self.round_in_with::<N, _>("round", |q| q.round::<N>())
+                }
+            }
+        )*
+    };
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    fn round_in_with<N: uom::si::Unit, F: FnOnce(T) -> T>(&self, name: &str, f: F) -> Self {
+        Node::new()
+            .name(format!("({}: {})", name, N::abbreviation()))
+            .op(name)
+            .value(f(self.quantity().clone()))
+            .children(vec![self.as_subexpr()])
+            .build()
+    }
+}
+
+for_each_quantity!(impl_round_in);
@@ -0,0 +1,213 @@
+use super::export::Tree;
+use super::node::raw_json_number;
+use super::node::{json_dimensions, json_findings, json_string, json_strings, json_tags};
+use super::node::{Cherries, Cherry};
+use alloc::{format, string::String, vec, vec::Vec};
+use core::fmt::Debug;
+
+///
+/// Output format of expression trees (e.g. json, YAML or a spreadsheet), written by
+/// `Cherry::serialize_with` and `Tree::serialize_with`.
+///
+/// Every node is begun before and ended after its subexpressions, so serializers only
+/// follow the nesting; `JsonSerializer` writes the json of `to_json`.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::export::{Tree, Value};
+/// use cherries::serializer::TreeSerializer;
+///
+/// // Indented outline, e.g. for plain-text logs.
+/// #[derive(Default)]
+/// struct Outline {
+///     out: String,
+///     depth: usize,
+/// }
+///
+/// impl TreeSerializer for Outline {
+///     type Output = String;
+///
+///     fn begin(&mut self, node: &Tree) {
+///         let value = match &node.value {
+///             Value::Number(value) => value.to_string(),
+///             _ => String::from("?"),
+///         };
+///         let indent = "    ".repeat(self.depth);
+///         self.out.push_str(&format!("{}{} = {} [{}]\n", indent, node.label, value, node.unit));
+///         self.depth += 1;
+///     }
+///     fn end(&mut self) {
+///         self.depth -= 1;
+///     }
+///     fn finish(self) -> String {
+///         self.out
+///     }
+/// }
+///
+/// let a = Leaf::new().value(2.0).name("a").build();
+/// let b = Leaf::new().value(3.0).name("b").build();
+/// assert_eq!(
+///     (a * b).labeled("c").serialize_with(Outline::default()).unwrap(),
+///     "c = 6 [dimensionless]\n    a = 2 [dimensionless]\n    b = 3 [dimensionless]\n"
+/// );
+/// ```
+pub trait TreeSerializer {
+    /// Output written for a whole tree (e.g. `String`).
+    type Output;
+    /// Begins node `node` (label, value, unit, operation and metadata),
+    /// whose subexpressions `node.subexpr` are begun next.
+    fn begin(&mut self, node: &Tree);
+    /// Ends the node begun last, after its subexpressions.
+    fn end(&mut self);
+    /// Returns output after the root ended.
+    fn finish(self) -> Self::Output;
+}
+
+///
+/// Writes trees as json of `Cherries::to_json` (in the units of the tree).
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::serializer::JsonSerializer;
+///
+/// let a = Leaf::new().value(2.0).name("a").build().comment("measured");
+/// let b = Leaf::new().value(3.0).name("b").build();
+/// let c = (a * b).labeled("c").tag("stage", "design");
+/// assert_eq!(c.serialize_with(JsonSerializer::new()).unwrap(), c.to_json());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct JsonSerializer {
+    out: String,
+    // Count of subexpressions begun of every node not ended, innermost last.
+    open: Vec<usize>,
+}
+
+impl JsonSerializer {
+    ///
+    /// Makes serializer with empty output.
+    ///
+    pub fn new() -> Self {
+        JsonSerializer::default()
+    }
+}
+
+impl TreeSerializer for JsonSerializer {
+    type Output = String;
+
+    fn begin(&mut self, node: &Tree) {
+        if let Some(begun) = self.open.last_mut() {
+            self.out.push_str(if *begun == 0 { ",\"subexpr\":[" } else { "," });
+            *begun += 1;
+        }
+        self.out.push_str(&head(node));
+        self.open.push(0);
+    }
+    fn end(&mut self) {
+        if self.open.pop().map_or(false, |begun| begun > 0) {
+            self.out.push(']');
+        }
+        self.out.push('}');
+    }
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+// Json of `node` without subexpressions and closing brace.
+fn head(node: &Tree) -> String {
+    let uncertainty = node.uncertainty.map_or_else(String::new, |uncertainty| {
+        format!(",\"uncertainty\":{}", raw_json_number(uncertainty))
+    });
+    let mut meta = node
+        .op
+        .as_ref()
+        .map_or_else(String::new, |op| format!(",\"op\":{}", json_string(op.as_str())));
+    if let Some(origin) = node.origin {
+        meta.push_str(&format!(",\"origin\":\"{}\"", origin.as_str()));
+    }
+    if let Some(comment) = &node.comment {
+        meta.push_str(&format!(",\"comment\":{}", json_string(comment)));
+    }
+    if let Some(description) = &node.description {
+        meta.push_str(&format!(",\"description\":{}", json_string(description)));
+    }
+    if let Some(sig_figs) = node.sig_figs {
+        meta.push_str(&format!(",\"sig_figs\":{}", sig_figs));
+    }
+    if !node.meta.is_empty() {
+        meta.push_str(&format!(",\"meta\":{}", json_tags(&node.meta)));
+    }
+    if let Some(location) = &node.location {
+        meta.push_str(&format!(",\"location\":{}", json_string(location)));
+    }
+    if let Some(timestamp) = &node.timestamp {
+        meta.push_str(&format!(",\"timestamp\":{}", json_string(timestamp)));
+    }
+    if let Some(id) = node.id {
+        meta.push_str(&format!(",\"id\":{}", id));
+    }
+    if !node.references.is_empty() {
+        meta.push_str(&format!(",\"references\":{}", json_strings(&node.references)));
+    }
+    if node.detail {
+        meta.push_str(",\"detail\":true");
+    }
+    if !node.attachments.is_empty() {
+        meta.push_str(",\"attachments\":");
+        meta.push_str(&serde_json::to_string(&node.attachments).unwrap_or_default());
+    }
+    if !node.findings.is_empty() {
+        meta.push_str(&format!(",\"findings\":{}", json_findings(&node.findings)));
+    }
+    if let Some(collapsed) = node.collapsed {
+        meta.push_str(&format!(",\"collapsed\":{}", collapsed));
+    }
+    format!(
+        "{{\"label\":{label},\"value\":{value}{uncertainty},\"unit\":{unit}{dimensions}{meta}",
+        label = json_string(&node.label),
+        value = node.value.to_json(),
+        uncertainty = uncertainty,
+        unit = json_string(&node.unit),
+        dimensions = json_dimensions(node.dimensions),
+        meta = meta
+    )
+}
+
+impl Tree {
+    ///
+    /// Writes the tree with `serializer` (in pre-order, without recursion) and returns its output.
+    ///
+    pub fn serialize_with<S: TreeSerializer>(&self, mut serializer: S) -> S::Output {
+        enum Todo<'a> {
+            Begin(&'a Tree),
+            End,
+        }
+        let mut stack = vec![Todo::Begin(self)];
+        while let Some(todo) = stack.pop() {
+            match todo {
+                Todo::Begin(node) => {
+                    serializer.begin(node);
+                    stack.push(Todo::End);
+                    stack.extend(node.subexpr.iter().rev().map(Todo::Begin));
+                }
+                Todo::End => serializer.end(),
+            }
+        }
+        serializer.finish()
+    }
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    ///
+    /// Writes the tree (as written by `to_json`) with `serializer` and returns its output.
+    ///
+    /// Fails only for subexpressions recorded as invalid json text.
+    ///
+    pub fn serialize_with<S: TreeSerializer>(&self, serializer: S) -> serde_json::Result<S::Output> {
+        Ok(Tree::from_json(&self.to_json())?.serialize_with(serializer))
+    }
+}
@@ -0,0 +1,625 @@
+use super::node::{Cherries, Cherry};
+use super::validate;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::{Mutex, PoisonError};
+
+///
+/// Hook which receives every node built while a `Session` is running.
+///
+pub trait Observer {
+    fn on_build(&mut self, node: &dyn Cherries);
+}
+
+impl<O: Observer> Observer for Rc<RefCell<O>> {
+    fn on_build(&mut self, node: &dyn Cherries) {
+        self.borrow_mut().on_build(node)
+    }
+}
+
+// Type-checked rule applied to every node of quantity type `T` computed inside `Session::run`.
+struct Invariant<T, F> {
+    msg: String,
+    predicate: F,
+    quantity: std::marker::PhantomData<fn(&T)>,
+}
+
+trait Rule {
+    fn check(&self, node: &dyn Any) -> Option<&str>;
+}
+
+impl<T: 'static + Clone + Debug, F: Fn(&T) -> bool> Rule for Invariant<T, F> {
+    fn check(&self, node: &dyn Any) -> Option<&str> {
+        node.downcast_ref::<Cherry<T>>().and_then(|node| {
+            if (self.predicate)(node.quantity()) {
+                None
+            } else {
+                Some(self.msg.as_str())
+            }
+        })
+    }
+}
+
+///
+/// Validation failure reported to hooks registered by `Session::on_validation_failure`.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failure {
+    /// Label of the node which failed.
+    pub label: String,
+    /// Message of the failed rule.
+    pub msg: String,
+    /// Value of the node (in base units), if it is a number.
+    pub value: Option<f32>,
+    pub unit: String,
+}
+
+impl Failure {
+    fn new(node: &dyn Cherries, msg: &str) -> Failure {
+        Failure {
+            label: node.name().to_owned(),
+            msg: msg.to_owned(),
+            value: node.value().ok(),
+            unit: node.symbol(),
+        }
+    }
+}
+
+type FailureHook = Box<dyn FnMut(&Failure)>;
+
+// Observers, invariants, failure hooks and invariant violations of a running `Session`.
+#[derive(Default)]
+struct Frame {
+    observers: Vec<Box<dyn Observer>>,
+    invariants: Vec<Box<dyn Rule>>,
+    failure_hooks: Vec<FailureHook>,
+    violations: Vec<Check>,
+    // Keys of nodes checked against the invariants (see `Cherry::key`).
+    checked: BTreeSet<usize>,
+}
+
+impl Frame {
+    fn fail(&mut self, node: &dyn Cherries, msg: &str) {
+        if !self.failure_hooks.is_empty() {
+            let failure = Failure::new(node, msg);
+            for hook in self.failure_hooks.iter_mut() {
+                hook(&failure);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static FRAMES: RefCell<Vec<Frame>> = RefCell::new(vec![]);
+}
+
+pub(crate) fn notify<T: Clone + Debug>(node: &Cherry<T>) {
+    FRAMES.with(|frames| {
+        // Nodes built by observers themselves are not reported again.
+        if let Ok(mut frames) = frames.try_borrow_mut() {
+            for frame in frames.iter_mut() {
+                for observer in frame.observers.iter_mut() {
+                    observer.on_build(node);
+                }
+            }
+        }
+    })
+}
+
+// Checks `node` against invariants of running sessions,
+// once per node (its clones and relabeled copies are not checked again).
+pub(crate) fn check_invariants<T: 'static + Clone + Debug>(node: &Cherry<T>) {
+    FRAMES.with(|frames| {
+        if let Ok(mut frames) = frames.try_borrow_mut() {
+            for frame in frames.iter_mut() {
+                if frame.invariants.is_empty() || !frame.checked.insert(node.key()) {
+                    continue;
+                }
+                let msg = frame
+                    .invariants
+                    .iter()
+                    .filter_map(|invariant| invariant.check(node))
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>();
+                for msg in &msg {
+                    frame.fail(node, msg);
+                }
+                if !msg.is_empty() {
+                    frame.violations.push(Check {
+                        label: node.name().to_owned(),
+                        passed: false,
+                        msg,
+                    });
+                }
+            }
+        }
+    })
+}
+
+// Reports failed validation of `node` to hooks of running sessions.
+pub(crate) fn notify_failure<T: Clone + Debug>(node: &Cherry<T>, msg: &str) {
+    FRAMES.with(|frames| {
+        if let Ok(mut frames) = frames.try_borrow_mut() {
+            for frame in frames.iter_mut() {
+                frame.fail(node, msg);
+            }
+        }
+    })
+}
+
+///
+/// Summary of a single node recorded in a `Session`.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub label: String,
+    pub value: Option<f32>,
+    pub unit: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl Entry {
+    fn new<T: Clone + Debug>(node: &Cherry<T>, source: Option<String>) -> Entry {
+        Entry {
+            label: node.name().to_owned(),
+            value: node.value().ok(),
+            unit: node.symbol(),
+            source,
+        }
+    }
+}
+
+///
+/// Outcome of a single validation recorded in a `Session`.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Check {
+    pub label: String,
+    pub passed: bool,
+    pub msg: Vec<String>,
+}
+
+///
+/// Compact summary of a `Session`.
+///
+/// Holds inputs, outputs and check outcomes without the expression trees.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub inputs: Vec<Entry>,
+    pub outputs: Vec<Entry>,
+    pub checks: Vec<Check>,
+}
+
+impl Manifest {
+    ///
+    /// Returns manifest as json string, as serialized by serde.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Leaf;
+    /// use cherries::session::Session;
+    ///
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// let mut session = Session::new();
+    /// session.input_from(&x, "survey");
+    /// assert_eq!(
+    ///     session.manifest().to_json(),
+    ///     "{\
+    ///         \"inputs\":[{\"label\":\"x\",\"value\":2.0,\"unit\":\"dimensionless\",\"source\":\"survey\"}],\
+    ///         \"outputs\":[],\
+    ///         \"checks\":[]\
+    ///     }".to_string()
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+///
+/// Records inputs, outputs and check outcomes of a calculation.
+///
+#[derive(Default)]
+pub struct Session {
+    inputs: Vec<Entry>,
+    outputs: Vec<Entry>,
+    trees: Vec<String>,
+    checks: Vec<Check>,
+    observers: Vec<Box<dyn Observer>>,
+    invariants: Vec<Box<dyn Rule>>,
+    failure_hooks: Vec<FailureHook>,
+}
+
+impl Debug for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Session")
+            .field("inputs", &self.inputs)
+            .field("outputs", &self.outputs)
+            .field("trees", &self.trees)
+            .field("checks", &self.checks)
+            .field("observers", &self.observers.len())
+            .field("invariants", &self.invariants.len())
+            .field("failure_hooks", &self.failure_hooks.len())
+            .finish()
+    }
+}
+
+impl Session {
+    ///
+    /// Makes new empty session.
+    ///
+    pub fn new() -> Self {
+        Session::default()
+    }
+    ///
+    /// Records `node` as an input.
+    ///
+    pub fn input<T: Clone + Debug>(&mut self, node: &Cherry<T>) -> &mut Self {
+        self.inputs.push(Entry::new(node, None));
+        self
+    }
+    ///
+    /// Records `node` as an input taken from `source`.
+    ///
+    pub fn input_from<T: Clone + Debug, S: Into<String>>(
+        &mut self,
+        node: &Cherry<T>,
+        source: S,
+    ) -> &mut Self {
+        self.inputs.push(Entry::new(node, Some(source.into())));
+        self
+    }
+    ///
+    /// Records `node` as an output together with its expression tree.
+    ///
+    pub fn output<T: Clone + Debug>(&mut self, node: &Cherry<T>) -> &mut Self {
+        self.outputs.push(Entry::new(node, None));
+        self.trees.push(node.to_json());
+        self
+    }
+    ///
+    /// Records outcome of a validation.
+    ///
+    pub fn check<T: Clone + Debug>(&mut self, result: &validate::Result<T>) -> &mut Self {
+        self.checks.push(match result {
+            Ok(node) => Check {
+                label: node.name().to_owned(),
+                passed: true,
+                msg: vec![],
+            },
+            Err(err) => Check {
+                label: err.label.to_owned(),
+                passed: false,
+                msg: err.msg.to_owned(),
+            },
+        });
+        self
+    }
+    ///
+    /// Registers `observer` which is notified of every node built inside `run`.
+    ///
+    pub fn observe<O: Observer + 'static>(&mut self, observer: O) -> &mut Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+    ///
+    /// Registers invariant which every node of quantity type `T` inside `run` must satisfy.
+    ///
+    /// Nodes are checked once when they are computed by the arithmetic operators (`+`, `-`, `*`
+    /// and `/`), folds or `map`, or used as operands of them or `map` (e.g. leaves and clamped values).
+    ///
+    /// Violations are recorded as failed checks (labeled by the offending node) when `run` returns.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Leaf;
+    /// use cherries::session::Session;
+    /// extern crate uom;
+    /// use uom::si::{f32::*, length::meter};
+    ///
+    /// let mut session = Session::new();
+    /// session.invariant("length must be finite and non-negative", |l: &Length| {
+    ///     l.is_finite() && l.value >= 0.0
+    /// });
+    /// session.run(|_| {
+    ///     let a = Leaf::new().value(Length::new::<meter>(2.0)).name("a").build();
+    ///     let b = Leaf::new().value(Length::new::<meter>(-3.0)).name("b").build();
+    ///     let n = Leaf::new().value(4.0_f32).name("n").build();
+    ///     (a.clone() + b) * n
+    /// });
+    /// let checks = session.manifest().checks;
+    /// assert_eq!(checks.len(), 3);
+    /// assert_eq!(checks[0].label, "b".to_string());
+    /// assert_eq!(checks[1].label, "(add)".to_string());
+    /// assert_eq!(checks[2].label, "(mul)".to_string());
+    /// assert_eq!(checks[2].msg, vec!["length must be finite and non-negative".to_string()]);
+    /// ```
+    pub fn invariant<T, F, S>(&mut self, msg: S, predicate: F) -> &mut Self
+    where
+        T: 'static + Clone + Debug,
+        F: 'static + Fn(&T) -> bool,
+        S: Into<String>,
+    {
+        self.invariants.push(Box::new(Invariant {
+            msg: msg.into(),
+            predicate,
+            quantity: std::marker::PhantomData,
+        }));
+        self
+    }
+    ///
+    /// Registers `hook` which receives every validation failure inside `run` as it happens:
+    /// failed `validate` predicates and invariant violations.
+    ///
+    /// Failures are still aggregated by `into_result` and recorded as checks;
+    /// hooks let long batch runs alert without waiting for them.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Leaf;
+    /// use cherries::session::{Failure, Session};
+    /// use cherries::validate::Validate;
+    /// use std::{cell::RefCell, rc::Rc};
+    ///
+    /// let alerts = Rc::new(RefCell::new(Vec::<Failure>::new()));
+    /// let mut session = Session::new();
+    /// let sink = alerts.clone();
+    /// session
+    ///     .invariant("must be finite", |v: &f32| v.is_finite())
+    ///     .on_validation_failure(move |failure| sink.borrow_mut().push(failure.clone()));
+    /// session.run(|_| {
+    ///     for i in 0..3 {
+    ///         let x = Leaf::new().value(i as f32).name(format!("x{}", i)).build();
+    ///         let _ = x.validate("must be less than 2", |v| v < &2.0).into_result();
+    ///     }
+    ///     let _ = Leaf::new().value(1.0_f32).name("one").build()
+    ///         / Leaf::new().value(0.0_f32).name("zero").build();
+    /// });
+    /// let alerts = alerts.borrow();
+    /// assert_eq!(alerts.len(), 2);
+    /// assert_eq!(alerts[0].label, "x2".to_string());
+    /// assert_eq!(alerts[0].msg, "must be less than 2".to_string());
+    /// assert_eq!(alerts[0].value, Some(2.0));
+    /// assert_eq!(alerts[1].label, "(div)".to_string());
+    /// assert_eq!(alerts[1].msg, "must be finite".to_string());
+    /// ```
+    pub fn on_validation_failure<F: 'static + FnMut(&Failure)>(&mut self, hook: F) -> &mut Self {
+        self.failure_hooks.push(Box::new(hook));
+        self
+    }
+    ///
+    /// Runs `f` with observers of this session active on the current thread.
+    ///
+    /// If `f` panics, observers, invariants and failure hooks are kept by this session
+    /// (and violations found so far are recorded) before the panic is resumed.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::session::{Observer, Session};
+    /// use std::{cell::RefCell, rc::Rc};
+    ///
+    /// #[derive(Default)]
+    /// struct Labels(Vec<String>);
+    /// impl Observer for Labels {
+    ///     fn on_build(&mut self, node: &dyn Cherries) {
+    ///         self.0.push(node.name().to_owned());
+    ///     }
+    /// }
+    ///
+    /// let labels = Rc::new(RefCell::new(Labels::default()));
+    /// let mut session = Session::new();
+    /// session.observe(labels.clone());
+    /// let res = session.run(|session| {
+    ///     let x = Leaf::new().value(2).name("x").build();
+    ///     session.input(&x);
+    ///     x * Leaf::new().value(3).name("y").build()
+    /// });
+    /// assert_eq!(res.quantity(), &6);
+    /// assert_eq!(labels.borrow().0, vec!["x", "y", "(mul)"]);
+    /// ```
+    pub fn run<R, F: FnOnce(&mut Session) -> R>(&mut self, f: F) -> R {
+        let frame = Frame {
+            observers: std::mem::replace(&mut self.observers, vec![]),
+            invariants: std::mem::replace(&mut self.invariants, vec![]),
+            failure_hooks: std::mem::replace(&mut self.failure_hooks, vec![]),
+            ..Frame::default()
+        };
+        FRAMES.with(|frames| frames.borrow_mut().push(frame));
+        // The frame is popped and handed back to this session even if `f` panics.
+        let ret = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        let mut frame = FRAMES
+            .with(|frames| frames.borrow_mut().pop())
+            .unwrap_or_default();
+        frame.observers.append(&mut self.observers);
+        frame.invariants.append(&mut self.invariants);
+        frame.failure_hooks.append(&mut self.failure_hooks);
+        self.observers = frame.observers;
+        self.invariants = frame.invariants;
+        self.failure_hooks = frame.failure_hooks;
+        self.checks.append(&mut frame.violations);
+        match ret {
+            Ok(ret) => ret,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+    ///
+    /// Returns expression trees (as json string) of recorded outputs.
+    ///
+    pub fn trees(&self) -> &[String] {
+        &self.trees
+    }
+    ///
+    /// Returns compact summary of the session.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::Leaf;
+    /// use cherries::session::Session;
+    /// use cherries::validate::Validate;
+    ///
+    /// let x = Leaf::new().value(2).name("x").build();
+    /// let y = Leaf::new().value(3).name("y").build();
+    /// let mut session = Session::new();
+    /// session.input_from(&x, "drawing A-101").input(&y);
+    /// let z = (x + y).labeled("z");
+    /// session.output(&z);
+    /// session.check(&z.validate("must be even", |v| v % 2 == 0).into_result());
+    ///
+    /// let manifest = session.manifest();
+    /// assert_eq!(manifest.inputs.len(), 2);
+    /// assert_eq!(manifest.inputs[0].source, Some("drawing A-101".to_string()));
+    /// assert_eq!(manifest.outputs[0].value, Some(5.0));
+    /// assert_eq!(manifest.checks[0].passed, false);
+    /// assert_eq!(manifest.checks[0].msg, vec!["must be even".to_string()]);
+    /// ```
+    pub fn manifest(&self) -> Manifest {
+        Manifest {
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            checks: self.checks.clone(),
+        }
+    }
+    ///
+    /// Appends inputs, outputs, trees and checks recorded by `other` (its observers and invariants are dropped).
+    ///
+    pub fn merge(&mut self, other: Session) -> &mut Self {
+        self.append(Shard::from(other))
+    }
+    fn append(&mut self, mut shard: Shard) -> &mut Self {
+        self.inputs.append(&mut shard.inputs);
+        self.outputs.append(&mut shard.outputs);
+        self.trees.append(&mut shard.trees);
+        self.checks.append(&mut shard.checks);
+        self
+    }
+}
+
+// Records of a session, which can be sent across threads.
+#[derive(Debug, Default)]
+struct Shard {
+    inputs: Vec<Entry>,
+    outputs: Vec<Entry>,
+    trees: Vec<String>,
+    checks: Vec<Check>,
+}
+
+impl Shard {
+    fn append(&mut self, mut other: Shard) {
+        self.inputs.append(&mut other.inputs);
+        self.outputs.append(&mut other.outputs);
+        self.trees.append(&mut other.trees);
+        self.checks.append(&mut other.checks);
+    }
+}
+
+impl From<Session> for Shard {
+    fn from(session: Session) -> Shard {
+        Shard {
+            inputs: session.inputs,
+            outputs: session.outputs,
+            trees: session.trees,
+            checks: session.checks,
+        }
+    }
+}
+
+///
+/// Session shared by threads, each recording into its own shard.
+///
+/// Shards are merged in order of their keys, so the result does not depend on thread scheduling.
+/// Records of the same key are appended in the order `record` returned.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// use cherries::session::ShardedSession;
+/// use std::{sync::Arc, thread};
+///
+/// let shared = Arc::new(ShardedSession::new());
+/// let handles = (0..4)
+///     .map(|i| {
+///         let shared = shared.clone();
+///         thread::spawn(move || {
+///             shared.record(format!("component-{}", i), |session| {
+///                 let x = Leaf::new().value(i).name(format!("x{}", i)).build();
+///                 session.input(&x);
+///                 session.output(&(x * Leaf::new().value(2).name("k").build()).labeled(format!("y{}", i)));
+///             });
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// let session = Arc::try_unwrap(shared).unwrap().into_session();
+/// let outputs = session.manifest().outputs;
+/// let labels = outputs.iter().map(|entry| entry.label.as_str()).collect::<Vec<_>>();
+/// assert_eq!(labels, vec!["y0", "y1", "y2", "y3"]);
+/// assert_eq!(session.trees().len(), 4);
+/// ```
+#[derive(Debug, Default)]
+pub struct ShardedSession {
+    shards: Mutex<BTreeMap<String, Shard>>,
+}
+
+impl ShardedSession {
+    ///
+    /// Makes new empty session.
+    ///
+    pub fn new() -> Self {
+        ShardedSession::default()
+    }
+    ///
+    /// Runs `f` on a fresh `Session` of the current thread, and stores what it recorded in shard `key`.
+    ///
+    pub fn record<K, R, F>(&self, key: K, f: F) -> R
+    where
+        K: Into<String>,
+        F: FnOnce(&mut Session) -> R,
+    {
+        let mut session = Session::new();
+        let ret = session.run(f);
+        let mut shards = self.shards.lock().unwrap_or_else(PoisonError::into_inner);
+        shards
+            .entry(key.into())
+            .or_insert_with(Shard::default)
+            .append(Shard::from(session));
+        ret
+    }
+    ///
+    /// Returns keys of recorded shards in merge order.
+    ///
+    pub fn keys(&self) -> Vec<String> {
+        self.shards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect()
+    }
+    ///
+    /// Merges all shards in order of their keys into a single `Session`.
+    ///
+    pub fn into_session(self) -> Session {
+        let shards = self.shards.into_inner().unwrap_or_else(PoisonError::into_inner);
+        let mut session = Session::new();
+        for (_, shard) in shards {
+            session.append(shard);
+        }
+        session
+    }
+}
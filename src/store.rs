@@ -0,0 +1,246 @@
+use super::attachment::Reference;
+use super::export::{Tree, Value};
+use super::node::{Dimensions, Op, Origin};
+use super::validate::Finding;
+use alloc::collections::BTreeMap;
+use alloc::{format, string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+// Most nodes `Store::get` rebuilds, as shared nodes are rebuilt once per reference
+// (a tampered store of n nodes each referencing the previous one twice has 2^n).
+const MAX_NODES: usize = 1 << 20;
+
+///
+/// Node in a `Store`, whose subexpressions are referenced by hash.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Object {
+    pub label: String,
+    pub value: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uncertainty: Option<f32>,
+    pub unit: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<Dimensions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<Op>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sig_figs: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    pub detail: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Reference>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapsed: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subexpr: Vec<String>,
+}
+
+///
+/// Content-addressable store of expression trees.
+///
+/// Every node is stored once under the hash of its contents (label, value, uncertainty, unit,
+/// dimensions, operation, origin, comment, description, significant figures, tags, location,
+/// timestamp, id, references, detail mark, attachments, findings, count of collapsed subexpressions
+/// and hashes of its subexpressions), so identical subtrees of runs inserted in one store
+/// (e.g. unchanged inputs between revisions) are kept only once
+/// and runs are referenced by the hash of their root.
+///
+/// Hashes are 128-bit FNV-1a, which is fast but not cryptographic.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::export::Tree;
+/// use cherries::store::Store;
+/// use std::convert::TryFrom;
+///
+/// let a = Leaf::new().value(2).name("a").build();
+/// let b = Leaf::new().value(3).name("b").build();
+/// let rev1 = Tree::try_from(&(a.clone() * b.clone() + a.clone()).labeled("x")).unwrap();
+/// let rev2 = Tree::try_from(&(a.clone() * b + Leaf::new().value(4).name("c").build()).labeled("x")).unwrap();
+///
+/// let mut store = Store::new();
+/// let first = store.insert(&rev1);
+/// assert_eq!(store.len(), 4);
+/// let second = store.insert(&rev2);
+/// // only `c` and the root are new
+/// assert_eq!(store.len(), 6);
+/// assert_ne!(first, second);
+/// assert_eq!(store.insert(&rev1), first);
+///
+/// let archived = Store::from_json(&store.to_json()).unwrap();
+/// assert_eq!(archived.get(&first), Some(rev1));
+/// assert_eq!(archived.get(&second), Some(rev2));
+/// assert_eq!(archived.get("0"), None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Store {
+    objects: BTreeMap<String, Object>,
+}
+
+impl Store {
+    ///
+    /// Makes empty store.
+    ///
+    pub fn new() -> Self {
+        Store::default()
+    }
+    ///
+    /// Returns number of stored nodes.
+    ///
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+    ///
+    /// Returns `true` if no node is stored.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+    ///
+    /// Returns `true` if a node is stored under `hash`.
+    ///
+    pub fn contains(&self, hash: &str) -> bool {
+        self.objects.contains_key(hash)
+    }
+    ///
+    /// Returns node stored under `hash`.
+    ///
+    pub fn object(&self, hash: &str) -> Option<&Object> {
+        self.objects.get(hash)
+    }
+    ///
+    /// Stores every node of `tree` not stored yet and returns hash of its root.
+    ///
+    pub fn insert(&mut self, tree: &Tree) -> String {
+        let object = Object {
+            label: tree.label.clone(),
+            value: tree.value.clone(),
+            uncertainty: tree.uncertainty,
+            unit: tree.unit.clone(),
+            dimensions: tree.dimensions,
+            op: tree.op.clone(),
+            origin: tree.origin,
+            comment: tree.comment.clone(),
+            description: tree.description.clone(),
+            sig_figs: tree.sig_figs,
+            meta: tree.meta.clone(),
+            location: tree.location.clone(),
+            timestamp: tree.timestamp.clone(),
+            id: tree.id,
+            references: tree.references.clone(),
+            detail: tree.detail,
+            attachments: tree.attachments.clone(),
+            findings: tree.findings.clone(),
+            collapsed: tree.collapsed,
+            subexpr: tree.subexpr.iter().map(|sub| self.insert(sub)).collect(),
+        };
+        let hash = hash(&object);
+        self.objects.entry(hash.clone()).or_insert(object);
+        hash
+    }
+    ///
+    /// Rebuilds tree whose root is stored under `hash`.
+    ///
+    /// Returns `None` if a node of the tree is missing, the tree has more than 2^20 nodes
+    /// (see `get_with_limit`) or references form a cycle (which only a tampered store can have).
+    ///
+    pub fn get(&self, hash: &str) -> Option<Tree> {
+        self.get_with_limit(hash, MAX_NODES)
+    }
+    ///
+    /// Rebuilds tree whose root is stored under `hash`, as `get` does,
+    /// unless it has more than `max_nodes` nodes (counting shared nodes once per reference).
+    ///
+    pub fn get_with_limit(&self, hash: &str, max_nodes: usize) -> Option<Tree> {
+        let mut budget = max_nodes;
+        self.rebuild(hash, self.objects.len(), &mut budget)
+    }
+    // `depth` bounds the number of ancestors, every path in a store without cycles is shorter.
+    // `budget` is the number of nodes left to rebuild.
+    fn rebuild(&self, hash: &str, depth: usize, budget: &mut usize) -> Option<Tree> {
+        let object = self.objects.get(hash).filter(|_| depth > 0 && *budget > 0)?;
+        *budget -= 1;
+        Some(Tree {
+            label: object.label.clone(),
+            value: object.value.clone(),
+            uncertainty: object.uncertainty,
+            unit: object.unit.clone(),
+            dimensions: object.dimensions,
+            op: object.op.clone(),
+            origin: object.origin,
+            comment: object.comment.clone(),
+            description: object.description.clone(),
+            sig_figs: object.sig_figs,
+            meta: object.meta.clone(),
+            location: object.location.clone(),
+            timestamp: object.timestamp.clone(),
+            id: object.id,
+            references: object.references.clone(),
+            detail: object.detail,
+            attachments: object.attachments.clone(),
+            findings: object.findings.clone(),
+            collapsed: object.collapsed,
+            subexpr: object
+                .subexpr
+                .iter()
+                .map(|sub| self.rebuild(sub, depth - 1, budget))
+                .collect::<Option<_>>()?,
+        })
+    }
+    ///
+    /// Moves every node of `other` into `self`.
+    ///
+    pub fn merge(&mut self, other: Store) -> &mut Self {
+        for (hash, object) in other.objects {
+            self.objects.entry(hash).or_insert(object);
+        }
+        self
+    }
+    ///
+    /// Returns json of every stored node keyed by hash.
+    ///
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+    ///
+    /// Parses store from a json string produced by `Store::to_json()`.
+    ///
+    pub fn from_json(json: &str) -> serde_json::Result<Store> {
+        serde_json::from_str(json)
+    }
+}
+
+fn hash(object: &Object) -> String {
+    digest(serde_json::to_string(object).unwrap_or_default().as_bytes())
+}
+
+// 128-bit FNV-1a of `bytes` in hex.
+pub(crate) fn digest(bytes: &[u8]) -> String {
+    const OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+    let hash = bytes
+        .iter()
+        .fold(OFFSET, |hash, &byte| (hash ^ u128::from(byte)).wrapping_mul(PRIME));
+    format!("{:032x}", hash)
+}
@@ -0,0 +1,126 @@
+use super::node::Cherries;
+use super::session::Observer;
+use std::io::{self, Write};
+use super::timing;
+use std::time::Duration;
+
+///
+/// Streams nodes built in a `Session` as JSON Patch operations.
+///
+/// Every node is emitted as one line `{"op":"add","path":"/nodes/-","value":<tree>}`
+/// so a dashboard can apply them to a `{"nodes":[]}` document as they arrive.
+/// With `throttle`, lines are buffered and written at most once per interval
+/// (measured by the clock of `timing::set_clock`).
+/// Once a write fails, nodes are dropped instead of buffered until `flush` returns the error.
+///
+pub struct JsonStream<W: Write> {
+    writer: W,
+    interval: Duration,
+    last_flush: Option<Duration>,
+    pending: Vec<String>,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonStream<W> {
+    ///
+    /// Makes new stream writing every node immediately to `writer`.
+    ///
+    pub fn new(writer: W) -> Self {
+        JsonStream {
+            writer,
+            interval: Duration::from_secs(0),
+            last_flush: None,
+            pending: vec![],
+            error: None,
+        }
+    }
+    ///
+    /// Sets minimum interval between writes (buffering nodes in between).
+    ///
+    pub fn throttle(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+    ///
+    /// Returns reference of underlying writer.
+    ///
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+    ///
+    /// Writes all buffered lines.
+    ///
+    /// Returns first error occurred while streaming (if any).
+    ///
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        for line in self.pending.drain(..) {
+            writeln!(self.writer, "{}", line)?;
+        }
+        self.last_flush = Some(timing::now());
+        self.writer.flush()
+    }
+}
+
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// use cherries::session::Session;
+/// use cherries::stream::JsonStream;
+/// use std::{cell::RefCell, rc::Rc, time::Duration};
+///
+/// let stream = Rc::new(RefCell::new(
+///     JsonStream::new(vec![]).throttle(Duration::from_secs(60)),
+/// ));
+/// let mut session = Session::new();
+/// session.observe(stream.clone());
+/// session.run(|_| {
+///     let x = Leaf::new().value(2).name("x").build();
+///     let y = Leaf::new().value(3).name("y").build();
+///     x + y
+/// });
+/// // first node is written immediately, the rest waits for the interval.
+/// assert_eq!(String::from_utf8_lossy(stream.borrow().get_ref()).lines().count(), 1);
+/// stream.borrow_mut().flush().unwrap();
+/// let written = String::from_utf8(stream.borrow().get_ref().clone()).unwrap();
+/// assert_eq!(
+///     written.lines().last(),
+///     Some("{\"op\":\"add\",\"path\":\"/nodes/-\",\"value\":{\
+///         \"label\":\"(add)\",\"value\":5,\"unit\":\"dimensionless\",\"op\":\"add\",\"subexpr\":[\
+///             {\"label\":\"x\",\"value\":2,\"unit\":\"dimensionless\"},\
+///             {\"label\":\"y\",\"value\":3,\"unit\":\"dimensionless\"}\
+///         ]\
+///     }}")
+/// );
+/// ```
+impl<W: Write> Observer for JsonStream<W> {
+    fn on_build(&mut self, node: &dyn Cherries) {
+        // The writer is broken (e.g. a closed socket), buffered lines would only pile up.
+        if self.error.is_some() {
+            return;
+        }
+        self.pending.push(format!(
+            "{{\"op\":\"add\",\"path\":\"/nodes/-\",\"value\":{}}}",
+            node.to_json()
+        ));
+        let interval = self.interval;
+        if self
+            .last_flush
+            .map_or(true, |last| timing::now().checked_sub(last).unwrap_or_default() >= interval)
+        {
+            if let Err(err) = self.flush() {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+impl<W: Write> Drop for JsonStream<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -0,0 +1,188 @@
+use super::node::{Cherries, Cherry, Node};
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use alloc::collections::BTreeMap;
+use core::fmt::{self, Debug, Display};
+use core::str::FromStr;
+
+///
+/// Semantic version `major.minor.patch` of a calculation template.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    ///
+    /// Makes version `major.minor.patch`.
+    ///
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+    ///
+    /// Returns true if `self` can replace `required` (same major version and not older).
+    ///
+    pub fn is_compatible_with(&self, required: &Version) -> bool {
+        self.major == required.major && self >= required
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Version, String> {
+        let parts = s
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("invalid version `{}`.", s))?;
+        match parts.as_slice() {
+            [major, minor, patch] => Ok(Version::new(*major, *minor, *patch)),
+            _ => Err(format!("invalid version `{}`.", s)),
+        }
+    }
+}
+
+///
+/// Registry of replayable calculation templates under a name and version.
+///
+/// Every tree produced by the registry is wrapped in a node labeled
+/// `(template: {name}@{version})`, so results trace back to the exact formula revision.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Cherry, Leaf};
+/// use cherries::template::{TemplateRegistry, Version};
+///
+/// let mut registry = TemplateRegistry::<(f64, f64), f64>::new();
+/// registry
+///     .register("moment", Version::new(1, 0, 0), |&(w, l)| {
+///         Leaf::new().value(w).name("w").build() * Leaf::new().value(l * l / 8.0).name("l^2/8").build()
+///     })
+///     .unwrap();
+/// registry
+///     .register("moment", "1.1.0".parse().unwrap(), |&(w, l)| {
+///         (Leaf::new().value(w).name("w").build() * Leaf::new().value(l * l).name("l^2").build())
+///             / Leaf::new().value(8.0).name("8").build()
+///     })
+///     .unwrap();
+/// assert!(registry.register("moment", Version::new(1, 0, 0), |_| unreachable!()).is_err());
+///
+/// let m = registry.run_latest("moment", &(2.0, 4.0)).unwrap();
+/// assert_eq!(m.quantity(), &4.0);
+/// assert_eq!(m.name(), &"(template: moment@1.1.0)".to_string());
+/// let m = registry.run("moment", &Version::new(1, 0, 0), &(2.0, 4.0)).unwrap();
+/// assert_eq!(m.name(), &"(template: moment@1.0.0)".to_string());
+/// assert!(registry.run_compatible("moment", &Version::new(2, 0, 0), &(2.0, 4.0)).is_none());
+/// assert_eq!(registry.versions("moment"), vec![Version::new(1, 0, 0), Version::new(1, 1, 0)]);
+/// ```
+pub struct TemplateRegistry<I, T: Clone + Debug> {
+    templates: BTreeMap<String, BTreeMap<Version, Template<I, T>>>,
+}
+
+type Template<I, T> = Box<dyn Fn(&I) -> Cherry<T>>;
+
+impl<I, T: Clone + Debug> Default for TemplateRegistry<I, T> {
+    fn default() -> Self {
+        TemplateRegistry {
+            templates: BTreeMap::new(),
+        }
+    }
+}
+
+impl<I, T: Clone + Debug> Debug for TemplateRegistry<I, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.templates
+                    .iter()
+                    .map(|(name, versions)| (name, versions.keys().collect::<Vec<_>>())),
+            )
+            .finish()
+    }
+}
+
+impl<I, T: 'static + Clone + Debug> TemplateRegistry<I, T> {
+    ///
+    /// Makes empty registry.
+    ///
+    pub fn new() -> Self {
+        TemplateRegistry::default()
+    }
+    ///
+    /// Registers `template` as `name` at `version`.
+    ///
+    /// Returns `Err` if `name` is already registered at `version`.
+    ///
+    pub fn register<S, F>(&mut self, name: S, version: Version, template: F) -> Result<(), String>
+    where
+        S: Into<String>,
+        F: 'static + Fn(&I) -> Cherry<T>,
+    {
+        let name = name.into();
+        let versions = self.templates.entry(name.clone()).or_insert_with(BTreeMap::new);
+        if versions.contains_key(&version) {
+            return Err(format!("template `{}@{}` is already registered.", name, version));
+        }
+        versions.insert(version, Box::new(template));
+        Ok(())
+    }
+    ///
+    /// Returns registered versions of `name` in ascending order.
+    ///
+    pub fn versions(&self, name: &str) -> Vec<Version> {
+        self.templates
+            .get(name)
+            .map(|versions| versions.keys().cloned().collect())
+            .unwrap_or_else(Vec::new)
+    }
+    ///
+    /// Runs `name` at exactly `version` (`None` if not registered).
+    ///
+    pub fn run(&self, name: &str, version: &Version, input: &I) -> Option<Cherry<T>> {
+        let template = self.templates.get(name)?.get(version)?;
+        Some(record(name, version, template(input)))
+    }
+    ///
+    /// Runs latest version of `name` (`None` if not registered).
+    ///
+    pub fn run_latest(&self, name: &str, input: &I) -> Option<Cherry<T>> {
+        let (version, template) = self.templates.get(name)?.iter().next_back()?;
+        Some(record(name, version, template(input)))
+    }
+    ///
+    /// Runs latest version of `name` compatible with `required` (`None` if not registered).
+    ///
+    pub fn run_compatible(&self, name: &str, required: &Version, input: &I) -> Option<Cherry<T>> {
+        let (version, template) = self
+            .templates
+            .get(name)?
+            .iter()
+            .filter(|(version, _)| version.is_compatible_with(required))
+            .next_back()?;
+        Some(record(name, version, template(input)))
+    }
+}
+
+fn record<T: 'static + Clone + Debug>(name: &str, version: &Version, tree: Cherry<T>) -> Cherry<T> {
+    Node::new()
+        .name(format!("(template: {}@{})", name, version))
+        .op("template")
+        .value(tree.quantity().clone())
+        .children(vec![tree.as_subexpr()])
+        .build()
+}
@@ -0,0 +1,249 @@
+use super::node::{json_string, Cherries};
+use super::session::Observer;
+use std::cell::Cell;
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+thread_local! {
+    static ORIGIN: Instant = Instant::now();
+}
+
+thread_local! {
+    static CLOCK: Cell<Option<fn() -> Duration>> = Cell::new(None);
+}
+
+#[cfg(feature = "timestamps")]
+thread_local! {
+    static WALL_CLOCK: Cell<Option<fn() -> Duration>> = Cell::new(None);
+}
+
+///
+/// Sets clock used by `Profiler` and `stream::JsonStream` on the current thread
+/// (`None` uses the default one), and returns the previous one.
+///
+/// A clock returns monotonic time since an arbitrary origin.
+/// The default clock is `std::time::Instant`, which is not available on `wasm32-unknown-unknown`;
+/// there the default clock always returns zero, so set one backed by e.g. `performance.now()`.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// use cherries::session::Session;
+/// use cherries::timing::{set_clock, Profiler};
+/// use std::{cell::RefCell, rc::Rc, time::Duration};
+///
+/// fn frozen() -> Duration {
+///     Duration::from_millis(42)
+/// }
+///
+/// set_clock(Some(frozen));
+/// let profiler = Rc::new(RefCell::new(Profiler::new()));
+/// let mut session = Session::new();
+/// session.observe(profiler.clone());
+/// session.run(|_| Leaf::new().value(2).name("x").build());
+/// assert_eq!(profiler.borrow().report().total(), Duration::from_secs(0));
+/// set_clock(None);
+/// ```
+pub fn set_clock(clock: Option<fn() -> Duration>) -> Option<fn() -> Duration> {
+    CLOCK.with(|current| current.replace(clock))
+}
+
+// Current time of the clock of the current thread.
+pub(crate) fn now() -> Duration {
+    match CLOCK.with(|current| current.get()) {
+        Some(clock) => clock(),
+        None => default_now(),
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn default_now() -> Duration {
+    ORIGIN.with(|origin| origin.elapsed())
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn default_now() -> Duration {
+    Duration::from_secs(0)
+}
+
+#[cfg(feature = "timestamps")]
+///
+/// Sets wall clock used by timestamps of nodes (see `node::set_record_timestamps`) on the current thread
+/// (`None` uses the default one), and returns the previous one.
+///
+/// A wall clock returns time since the Unix epoch.
+/// The default wall clock is `std::time::SystemTime`, which is not available on `wasm32-unknown-unknown`;
+/// there the default wall clock always returns the epoch, so set one backed by e.g. `Date.now()`.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{set_record_timestamps, Leaf};
+/// use cherries::timing::set_wall_clock;
+/// use std::time::Duration;
+///
+/// fn leap_day() -> Duration {
+///     Duration::from_millis(1_582_977_600_250)
+/// }
+///
+/// set_wall_clock(Some(leap_day));
+/// let previous = set_record_timestamps(true);
+/// let x = Leaf::new().value(2).name("x").build();
+/// set_record_timestamps(previous);
+/// set_wall_clock(None);
+/// assert_eq!(x.timestamp(), Some("2020-02-29T12:00:00.250Z"));
+/// ```
+pub fn set_wall_clock(clock: Option<fn() -> Duration>) -> Option<fn() -> Duration> {
+    WALL_CLOCK.with(|current| current.replace(clock))
+}
+
+// Current time since the Unix epoch of the wall clock of the current thread.
+#[cfg(feature = "timestamps")]
+pub(crate) fn wall_now() -> Duration {
+    match WALL_CLOCK.with(|current| current.get()) {
+        Some(clock) => clock(),
+        None => default_wall_now(),
+    }
+}
+
+#[cfg(all(feature = "timestamps", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+fn default_wall_now() -> Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+#[cfg(all(feature = "timestamps", target_arch = "wasm32", target_os = "unknown"))]
+fn default_wall_now() -> Duration {
+    Duration::from_secs(0)
+}
+
+///
+/// Wall-clock time spent on building a single node.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timing {
+    pub label: String,
+    pub elapsed: Duration,
+}
+
+///
+/// Timings of all nodes built while a `Profiler` was observing.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimingReport {
+    pub timings: Vec<Timing>,
+}
+
+impl TimingReport {
+    ///
+    /// Returns sum of all timings.
+    ///
+    pub fn total(&self) -> Duration {
+        self.timings
+            .iter()
+            .fold(Duration::from_secs(0), |acc, timing| acc + timing.elapsed)
+    }
+    ///
+    /// Returns at most `n` slowest timings in descending order.
+    ///
+    pub fn slowest(&self, n: usize) -> Vec<&Timing> {
+        let mut timings = self.timings.iter().collect::<Vec<_>>();
+        timings.sort_by(|a, b| b.elapsed.cmp(&a.elapsed));
+        timings.truncate(n);
+        timings
+    }
+    ///
+    /// Returns report as json string (elapsed in nanoseconds).
+    ///
+    pub fn to_json(&self) -> String {
+        format!(
+            "[{}]",
+            self.timings
+                .iter()
+                .map(|timing| format!(
+                    "{{\"label\":{label},\"elapsed_ns\":{elapsed}}}",
+                    label = json_string(&timing.label),
+                    elapsed = timing.elapsed.as_nanos()
+                ))
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+}
+
+///
+/// Records wall-clock time each node took to build.
+///
+/// Time of a node is measured from the previous node built (or from `Profiler::new`),
+/// so it covers the operation itself and any lookup done in between.
+/// Nothing is measured unless the profiler is registered to a running `Session`.
+/// Time is read from the clock set by `set_clock`.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::Leaf;
+/// use cherries::session::Session;
+/// use cherries::timing::Profiler;
+/// use std::{cell::RefCell, rc::Rc};
+///
+/// let profiler = Rc::new(RefCell::new(Profiler::new()));
+/// let mut session = Session::new();
+/// session.observe(profiler.clone());
+/// session.run(|_| {
+///     let x = Leaf::new().value(2).name("x").build();
+///     let y = Leaf::new().value(3).name("y").build();
+///     x * y
+/// });
+/// let report = profiler.borrow().report();
+/// let labels = report.timings.iter().map(|t| t.label.as_str()).collect::<Vec<_>>();
+/// assert_eq!(labels, vec!["x", "y", "(mul)"]);
+/// assert_eq!(report.slowest(2).len(), 2);
+/// assert!(report.total() >= report.slowest(1)[0].elapsed);
+/// ```
+#[derive(Debug)]
+pub struct Profiler {
+    last: Duration,
+    timings: Vec<Timing>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
+
+impl Profiler {
+    ///
+    /// Makes new profiler starting its clock now.
+    ///
+    pub fn new() -> Self {
+        Profiler {
+            last: now(),
+            timings: vec![],
+        }
+    }
+    ///
+    /// Returns report of timings recorded so far.
+    ///
+    pub fn report(&self) -> TimingReport {
+        TimingReport {
+            timings: self.timings.clone(),
+        }
+    }
+}
+
+impl Observer for Profiler {
+    fn on_build(&mut self, node: &dyn Cherries) {
+        let now = now();
+        self.timings.push(Timing {
+            label: node.name().to_owned(),
+            elapsed: now.checked_sub(self.last).unwrap_or_default(),
+        });
+        self.last = now;
+    }
+}
@@ -0,0 +1,193 @@
+use alloc::format;
+use core::fmt::{self, Debug};
+use core::ops::{Add, Div, Mul, Sub};
+
+///
+/// Numbers (`f32`, `f64` and uom quantities in base units) whose uncertainty can be propagated.
+///
+pub trait Magnitude {
+    /// Returns the value (in base units).
+    fn magnitude(&self) -> f64;
+}
+
+impl Magnitude for f32 {
+    fn magnitude(&self) -> f64 {
+        f64::from(*self)
+    }
+}
+
+impl Magnitude for f64 {
+    fn magnitude(&self) -> f64 {
+        *self
+    }
+}
+
+macro_rules! impl_magnitude {
+    ( $v:ident; $( $q:ident :: $Q:ident ),* ) => {
+        $(
+            impl Magnitude for uom::si::$v::$Q {
+                fn magnitude(&self) -> f64 {
+                    self.value.magnitude()
+                }
+            }
+        )*
+    };
+}
+
+for_each_quantity!(impl_magnitude);
+
+///
+/// Value `nominal` with absolute standard uncertainty (in base units of `T`).
+///
+/// Sums, differences, products and quotients propagate uncertainties of independent operands
+/// to first order (in quadrature), so every node of a tree of `Cherry<Uncertain<T>>`
+/// records its uncertainty next to the value as `"uncertainty"` in json.
+///
+/// # Examples
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::export::{Tree, Value};
+/// use cherries::uncertain::Uncertain;
+/// use std::convert::TryFrom;
+/// extern crate uom;
+/// use uom::si::{f32::*, length::meter};
+///
+/// let width = Leaf::new()
+///     .value(Uncertain::new(Length::new::<meter>(3.0), 0.03))
+///     .name("width")
+///     .build();
+/// let height = Leaf::new()
+///     .value(Uncertain::relative(Length::new::<meter>(4.0), 0.01))
+///     .name("height")
+///     .build();
+/// let area = (width * height).labeled("area");
+/// assert!((area.quantity().uncertainty() - 0.12 * 2.0_f64.sqrt()).abs() < 1e-6);
+/// assert!((area.quantity().relative_uncertainty() - 0.01 * 2.0_f64.sqrt()).abs() < 1e-6);
+///
+/// let tree = Tree::try_from(&area).unwrap();
+/// assert_eq!(tree.value, Value::Number(12.0));
+/// assert_eq!(tree.unit, "m^2".to_string());
+/// assert!((tree.uncertainty.unwrap() - 0.16970563).abs() < 1e-6);
+/// assert!(area.to_json().ends_with(
+///     "{\"label\":\"width\",\"value\":3,\"uncertainty\":0.03,\"unit\":\"m^1\",\"dimensions\":{\"L\":1}},\
+///     {\"label\":\"height\",\"value\":4,\"uncertainty\":0.04,\"unit\":\"m^1\",\"dimensions\":{\"L\":1}}]}"
+/// ));
+/// ```
+#[derive(Clone, Copy, PartialEq)]
+pub struct Uncertain<T> {
+    nominal: T,
+    uncertainty: f64,
+}
+
+impl<T: Magnitude> Uncertain<T> {
+    ///
+    /// Makes value `nominal` with absolute uncertainty `uncertainty` (in base units).
+    ///
+    pub fn new(nominal: T, uncertainty: f64) -> Self {
+        Uncertain {
+            nominal,
+            uncertainty: uncertainty.abs(),
+        }
+    }
+    ///
+    /// Makes value `nominal` with uncertainty `fraction` of its magnitude (e.g. `0.01` for 1%).
+    ///
+    pub fn relative(nominal: T, fraction: f64) -> Self {
+        let uncertainty = nominal.magnitude() * fraction;
+        Uncertain::new(nominal, uncertainty)
+    }
+    ///
+    /// Makes value `nominal` without uncertainty (e.g. for defined constants).
+    ///
+    pub fn exact(nominal: T) -> Self {
+        Uncertain::new(nominal, 0.0)
+    }
+    ///
+    /// Returns the nominal value.
+    ///
+    pub fn nominal(&self) -> &T {
+        &self.nominal
+    }
+    ///
+    /// Returns absolute uncertainty (in base units).
+    ///
+    pub fn uncertainty(&self) -> f64 {
+        self.uncertainty
+    }
+    ///
+    /// Returns uncertainty relative to the magnitude of the nominal value
+    /// (infinite for zero nominal value with uncertainty).
+    ///
+    pub fn relative_uncertainty(&self) -> f64 {
+        self.uncertainty / self.nominal.magnitude().abs()
+    }
+}
+
+// Written as `{value} ± {uncertainty} {symbol}`, so json records the uncertainty next to the value.
+impl<T: Debug> Debug for Uncertain<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formats = format!("{:?}", self.nominal);
+        match formats.find(' ') {
+            Some(space) => write!(
+                f,
+                "{} ± {}{}",
+                &formats[..space],
+                self.uncertainty,
+                &formats[space..]
+            ),
+            None => write!(f, "{} ± {}", formats, self.uncertainty),
+        }
+    }
+}
+
+// Square root of the sum of squares, i.e. uncertainty of independent contributions.
+fn quadrature(lhs: f64, rhs: f64) -> f64 {
+    lhs.hypot(rhs)
+}
+
+impl<T: Add<U>, U> Add<Uncertain<U>> for Uncertain<T> {
+    type Output = Uncertain<<T as Add<U>>::Output>;
+
+    fn add(self, other: Uncertain<U>) -> Self::Output {
+        Uncertain {
+            nominal: self.nominal + other.nominal,
+            uncertainty: quadrature(self.uncertainty, other.uncertainty),
+        }
+    }
+}
+
+impl<T: Sub<U>, U> Sub<Uncertain<U>> for Uncertain<T> {
+    type Output = Uncertain<<T as Sub<U>>::Output>;
+
+    fn sub(self, other: Uncertain<U>) -> Self::Output {
+        Uncertain {
+            nominal: self.nominal - other.nominal,
+            uncertainty: quadrature(self.uncertainty, other.uncertainty),
+        }
+    }
+}
+
+impl<T: Magnitude + Mul<U>, U: Magnitude> Mul<Uncertain<U>> for Uncertain<T> {
+    type Output = Uncertain<<T as Mul<U>>::Output>;
+
+    fn mul(self, other: Uncertain<U>) -> Self::Output {
+        let (a, b) = (self.nominal.magnitude(), other.nominal.magnitude());
+        Uncertain {
+            uncertainty: quadrature(b * self.uncertainty, a * other.uncertainty),
+            nominal: self.nominal * other.nominal,
+        }
+    }
+}
+
+impl<T: Magnitude + Div<U>, U: Magnitude> Div<Uncertain<U>> for Uncertain<T> {
+    type Output = Uncertain<<T as Div<U>>::Output>;
+
+    fn div(self, other: Uncertain<U>) -> Self::Output {
+        let (a, b) = (self.nominal.magnitude(), other.nominal.magnitude());
+        Uncertain {
+            uncertainty: quadrature(self.uncertainty / b, a * other.uncertainty / (b * b)),
+            nominal: self.nominal / other.nominal,
+        }
+    }
+}
@@ -0,0 +1,579 @@
+use super::export::Tree;
+#[cfg(feature = "std")]
+use super::export::Value;
+use super::node::{Cherries, Cherry, Leaf, Node};
+use super::serializer::JsonSerializer;
+#[cfg(feature = "std")]
+use super::rounding::{print_policy, Policy};
+use alloc::{format, string::{String, ToString}, vec};
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+use core::fmt::{self, Debug};
+
+// Number shown in a display unit, e.g. `1234.5 mm`.
+#[derive(Clone, Copy)]
+pub(crate) struct Displayed {
+    pub(crate) value: f64,
+    pub(crate) unit: &'static str,
+}
+
+impl Debug for Displayed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.value, self.unit)
+    }
+}
+
+macro_rules! impl_convert_to {
+    ( $v:ident; $( $q:ident :: $Q:ident ),* ) => {
+        $(
+            impl Cherry<uom::si::$v::$Q> {
+                ///
+                /// Returns same quantity labeled `(convert)` recording the value in display unit `N`.
+                ///
+                /// Subexpressions are the original node and a leaf `{label} [{abbreviation of N}]`
+                /// holding the value in `N`.
+                ///
+                pub fn convert_to<N>(&self) -> Self
+                where
+                    N: uom::si::$q::Unit + uom::Conversion<$v, T = $v>,
+                {
+                    let displayed = Leaf::new()
+                        .name(format!(
+                            "{} [{}]",
+                            self.name(),
+                            <N as uom::si::Unit>::abbreviation()
+                        ))
+                        .value(Displayed {
+                            value: f64::from(self.quantity().get::<N>()),
+                            unit: <N as uom::si::Unit>::abbreviation(),
+                        })
+                        .build();
+                    Node::new()
+                        .name("(convert)")
+                        .op("convert")
+                        .value(*self.quantity())
+                        .children(vec![self.as_subexpr(), displayed.as_subexpr()])
+                        .build()
+                }
+            }
+        )*
+    };
+}
+
+for_each_quantity!(impl_convert_to);
+
+///
+/// Quantity types which can be shown in unit `N`, used by `DisplayUnits::prefer`.
+///
+pub trait Measure<N> {
+    /// Returns value in base units of `value` in `N`.
+    fn base_value(value: f64) -> f64;
+    /// Returns abbreviation of `N`.
+    fn abbreviation() -> &'static str;
+    /// Returns unit symbol (in base units) of the quantity type as written by `to_json`.
+    fn symbol() -> String;
+}
+
+macro_rules! impl_measure {
+    ( $v:ident; $( $q:ident :: $Q:ident ),* ) => {
+        $(
+            impl<N> Measure<N> for uom::si::$v::$Q
+            where
+                N: uom::si::$q::Unit + uom::Conversion<$v, T = $v>,
+            {
+                fn base_value(value: f64) -> f64 {
+                    f64::from(Self::new::<N>(value as $v).value)
+                }
+                fn abbreviation() -> &'static str {
+                    <N as uom::si::Unit>::abbreviation()
+                }
+                fn symbol() -> String {
+                    format!("{:?}", Self::new::<N>(1.0))
+                        .splitn(2, ' ')
+                        .nth(1)
+                        .unwrap_or("dimensionless")
+                        .to_string()
+                }
+            }
+        )*
+    };
+}
+
+for_each_quantity!(impl_measure);
+
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+struct DisplayUnit {
+    symbol: String,
+    abbreviation: String,
+    offset: f64,
+    scale: f64,
+}
+
+#[cfg(feature = "std")]
+///
+/// Preferred display unit per dimension, applied by `to_json`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::units::{set_display_units, DisplayUnits};
+/// extern crate uom;
+/// use uom::si::{f64::*, length::{meter, millimeter}, pressure::{megapascal, pascal}, force::newton};
+///
+/// let units = DisplayUnits::new()
+///     .prefer::<Length, millimeter>()
+///     .prefer::<Pressure, megapascal>();
+/// let f = Leaf::new().value(Force::new::<newton>(6.0e6)).name("F").build();
+/// let a = Leaf::new().value(Area::new::<uom::si::area::square_meter>(0.03)).name("A").build();
+/// let t = Leaf::new().value(Length::new::<meter>(0.012)).name("t").build();
+/// let sigma = (f / a).labeled("sigma");
+/// assert_eq!(
+///     units.to_json(&sigma),
+///     "{\"label\":\"sigma\",\"value\":200,\"unit\":\"MPa\",\"dimensions\":{\"L\":-1,\"M\":1,\"T\":-2},\"op\":\"div\",\"subexpr\":[\
+///         {\"label\":\"F\",\"value\":6000000,\"unit\":\"m^1 kg^1 s^-2\",\"dimensions\":{\"L\":1,\"M\":1,\"T\":-2}},\
+///         {\"label\":\"A\",\"value\":0.03,\"unit\":\"m^2\",\"dimensions\":{\"L\":2}}\
+///     ]}".to_string()
+/// );
+///
+/// set_display_units(Some(units));
+/// assert_eq!(t.to_json(), "{\"label\":\"t\",\"value\":12,\"unit\":\"mm\",\"dimensions\":{\"L\":1}}".to_string());
+/// set_display_units(None);
+/// assert_eq!(t.to_json(), "{\"label\":\"t\",\"value\":0.012,\"unit\":\"m^1\",\"dimensions\":{\"L\":1}}".to_string());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisplayUnits {
+    units: Vec<DisplayUnit>,
+}
+
+#[cfg(feature = "std")]
+impl DisplayUnits {
+    ///
+    /// Makes empty registry (values are written in base units).
+    ///
+    pub fn new() -> Self {
+        DisplayUnits::default()
+    }
+    ///
+    /// Shows every value of the dimension of `Q` in unit `N` (replacing previous preference).
+    ///
+    pub fn prefer<Q: Measure<N>, N>(self) -> Self {
+        self.prefer_as::<Q, N, _>(Q::abbreviation())
+    }
+    ///
+    /// Same as `prefer`, but writes unit as `abbreviation` (e.g. `psi` instead of `lbf/in²`).
+    ///
+    pub fn prefer_as<Q: Measure<N>, N, S: Into<String>>(mut self, abbreviation: S) -> Self {
+        let symbol = Q::symbol();
+        let offset = Q::base_value(0.0);
+        let unit = DisplayUnit {
+            abbreviation: abbreviation.into(),
+            scale: Q::base_value(1.0) - offset,
+            offset,
+            symbol,
+        };
+        self.units.retain(|u| u.symbol != unit.symbol);
+        self.units.push(unit);
+        self
+    }
+    ///
+    /// Makes registry of US customary units: in, in², in³, lbf, psi, lb, lbf·in, lb/in³ and ft/s.
+    ///
+    /// Temperatures are kept in base units, since absolute temperatures and temperature intervals
+    /// share their unit symbol. Torque and energy also share it, so both are written in lbf·in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::units::DisplayUnits;
+    /// extern crate uom;
+    /// use uom::si::{f64::*, length::millimeter, pressure::megapascal};
+    ///
+    /// let t = Leaf::new().value(Length::new::<millimeter>(25.4)).name("t").build();
+    /// let f_y = Leaf::new().value(Pressure::new::<megapascal>(250.0)).name("f_y").build();
+    /// let us = DisplayUnits::us_customary();
+    /// assert_eq!(us.to_json(&t), "{\"label\":\"t\",\"value\":1,\"unit\":\"in\",\"dimensions\":{\"L\":1}}".to_string());
+    /// assert_eq!(us.to_json(&f_y), "{\"label\":\"f_y\",\"value\":36259.43,\"unit\":\"psi\",\"dimensions\":{\"L\":-1,\"M\":1,\"T\":-2}}".to_string());
+    /// ```
+    pub fn us_customary() -> Self {
+        use uom::si::f64::*;
+        use uom::si::{area, force, length, mass, mass_density, pressure, torque, velocity, volume};
+        DisplayUnits::new()
+            .prefer::<Length, length::inch>()
+            .prefer::<Area, area::square_inch>()
+            .prefer::<Volume, volume::cubic_inch>()
+            .prefer::<Force, force::pound_force>()
+            .prefer_as::<Pressure, pressure::pound_force_per_square_inch, _>("psi")
+            .prefer::<Mass, mass::pound>()
+            .prefer_as::<Torque, torque::pound_force_inch, _>("lbf·in")
+            .prefer::<MassDensity, mass_density::pound_per_cubic_inch>()
+            .prefer::<Velocity, velocity::foot_per_second>()
+    }
+    ///
+    /// Returns `node.to_json()` written with these display units.
+    ///
+    pub fn to_json(&self, node: &dyn Cherries) -> String {
+        let previous = set_display_units(Some(self.clone()));
+        let json = node.to_json();
+        set_display_units(previous);
+        json
+    }
+    fn show(&self, tree: &mut Tree, policy: Option<Policy>) {
+        let unit = match tree.value {
+            Value::Number(_) | Value::Interval(..) => {
+                self.units.iter().find(|u| u.symbol == tree.unit)
+            }
+            _ => None,
+        };
+        let show = |value: f32| {
+            let mut value = f64::from(value);
+            if let Some(unit) = unit {
+                value = (value - unit.offset) / unit.scale;
+            }
+            if let Some(policy) = policy {
+                value = policy.apply(value);
+            }
+            value as f32
+        };
+        match tree.value {
+            Value::Number(value) => tree.value = Value::Number(show(value)),
+            Value::Interval(lower, upper) => tree.value = Value::Interval(show(lower), show(upper)),
+            _ => {}
+        }
+        if let Some(unit) = unit {
+            // uncertainties are differences, so offsets cancel
+            tree.uncertainty = tree
+                .uncertainty
+                .map(|uncertainty| (f64::from(uncertainty) / unit.scale) as f32);
+            tree.unit = unit.abbreviation.clone();
+        }
+        for subexpr in tree.subexpr.iter_mut() {
+            self.show(subexpr, policy);
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static DISPLAY_UNITS: RefCell<Option<DisplayUnits>> = RefCell::new(None);
+}
+
+#[cfg(feature = "std")]
+///
+/// Sets display units used by `to_json` on the current thread
+/// (`None` writes values in base units), and returns the previous one.
+///
+pub fn set_display_units(units: Option<DisplayUnits>) -> Option<DisplayUnits> {
+    DISPLAY_UNITS.with(|current| current.replace(units))
+}
+
+// Json of `tree` as written by `Cherries::to_json`.
+pub(crate) fn render(tree: &Tree) -> String {
+    tree.serialize_with(JsonSerializer::new())
+}
+
+///
+/// How units still in base units are written by `to_json`.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolStyle {
+    /// Base unit exponents, e.g. `m^-1 kg^1 s^-2` (default).
+    Base,
+    /// Conventional symbol, e.g. `Pa`.
+    Conventional,
+    /// Unit name, e.g. `pascal`.
+    Name,
+}
+
+// Base symbol, conventional symbol and name of common SI units.
+const SYMBOLS: &[(&str, &str, &str)] = &[
+    ("m^1", "m", "meter"),
+    ("m^2", "m²", "square meter"),
+    ("m^3", "m³", "cubic meter"),
+    ("kg^1", "kg", "kilogram"),
+    ("s^1", "s", "second"),
+    ("A^1", "A", "ampere"),
+    ("K^1", "K", "kelvin"),
+    ("mol^1", "mol", "mole"),
+    ("cd^1", "cd", "candela"),
+    ("s^-1", "Hz", "hertz"),
+    ("m^1 kg^1 s^-2", "N", "newton"),
+    ("m^-1 kg^1 s^-2", "Pa", "pascal"),
+    ("m^2 kg^1 s^-2", "N·m", "newton meter"),
+    ("m^2 kg^1 s^-3", "W", "watt"),
+    ("s^1 A^1", "C", "coulomb"),
+    ("m^2 kg^1 s^-3 A^-1", "V", "volt"),
+    ("m^-2 kg^-1 s^4 A^2", "F", "farad"),
+    ("m^2 kg^1 s^-3 A^-2", "Ω", "ohm"),
+    ("m^-2 kg^-1 s^3 A^2", "S", "siemens"),
+    ("m^2 kg^1 s^-2 A^-1", "Wb", "weber"),
+    ("kg^1 s^-2 A^-1", "T", "tesla"),
+    ("m^2 kg^1 s^-2 A^-2", "H", "henry"),
+    ("m^1 s^-1", "m/s", "meter per second"),
+    ("m^1 s^-2", "m/s²", "meter per second squared"),
+    ("m^-3 kg^1", "kg/m³", "kilogram per cubic meter"),
+    ("m^1 kg^1 s^-1", "kg·m/s", "kilogram meter per second"),
+    ("kg^1 s^-1", "kg/s", "kilogram per second"),
+    ("m^3 s^-1", "m³/s", "cubic meter per second"),
+    ("m^-2 cd^1", "cd/m²", "candela per square meter"),
+];
+
+///
+/// Returns symbol of `base` (as written by `Cherries::symbol`) in `style`,
+/// or `None` if it is not a common SI unit.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::units::{readable_symbol, SymbolStyle};
+///
+/// assert_eq!(readable_symbol("m^-1 kg^1 s^-2", SymbolStyle::Conventional), Some("Pa"));
+/// assert_eq!(readable_symbol("m^-1 kg^1 s^-2", SymbolStyle::Name), Some("pascal"));
+/// assert_eq!(readable_symbol("m^2 kg^1 s^-2", SymbolStyle::Conventional), Some("N·m"));
+/// assert_eq!(readable_symbol("m^5", SymbolStyle::Conventional), None);
+/// ```
+pub fn readable_symbol(base: &str, style: SymbolStyle) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|(symbol, _, _)| *symbol == base)
+        .map(|(symbol, conventional, name)| match style {
+            SymbolStyle::Base => *symbol,
+            SymbolStyle::Conventional => *conventional,
+            SymbolStyle::Name => *name,
+        })
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static SYMBOL_STYLE: core::cell::Cell<SymbolStyle> = core::cell::Cell::new(SymbolStyle::Base);
+}
+
+#[cfg(feature = "std")]
+///
+/// Sets style of units written by `to_json` on the current thread, and returns the previous one.
+///
+/// Units not in the table of common SI units (and display units) are written as they are.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::units::{set_symbol_style, SymbolStyle};
+/// extern crate uom;
+/// use uom::si::{f32::*, force::newton, length::meter};
+///
+/// let f = Leaf::new().value(Force::new::<newton>(2.0)).name("F").build();
+/// let e = Leaf::new().value(Length::new::<meter>(3.0)).name("e").build();
+/// let m = f * e;
+/// set_symbol_style(SymbolStyle::Conventional);
+/// assert_eq!(
+///     m.to_json(),
+///     "{\"label\":\"(mul)\",\"value\":6,\"unit\":\"N·m\",\"dimensions\":{\"L\":2,\"M\":1,\"T\":-2},\"op\":\"mul\",\"subexpr\":[\
+///         {\"label\":\"F\",\"value\":2,\"unit\":\"N\",\"dimensions\":{\"L\":1,\"M\":1,\"T\":-2}},\
+///         {\"label\":\"e\",\"value\":3,\"unit\":\"m\",\"dimensions\":{\"L\":1}}\
+///     ]}".to_string()
+/// );
+/// set_symbol_style(SymbolStyle::Base);
+/// assert_eq!(m.symbol(), "m^2 kg^1 s^-2".to_string());
+/// ```
+pub fn set_symbol_style(style: SymbolStyle) -> SymbolStyle {
+    SYMBOL_STYLE.with(|current| current.replace(style))
+}
+
+#[cfg(feature = "std")]
+fn restyle(tree: &mut Tree, style: SymbolStyle) {
+    if let Some(symbol) = readable_symbol(&tree.unit, style) {
+        tree.unit = symbol.to_string();
+    }
+    for subexpr in tree.subexpr.iter_mut() {
+        restyle(subexpr, style);
+    }
+}
+
+#[cfg(feature = "std")]
+// Applies display units, print policy and symbol style of the current thread to whole tree `json`.
+pub(crate) fn present(json: String) -> String {
+    let policy = print_policy();
+    let units = DISPLAY_UNITS.with(|current| current.borrow().clone());
+    let style = SYMBOL_STYLE.with(|current| current.get());
+    if policy.is_none() && units.is_none() && style == SymbolStyle::Base {
+        return json;
+    }
+    match Tree::from_json(&json) {
+        Ok(mut tree) => {
+            units.unwrap_or_default().show(&mut tree, policy);
+            restyle(&mut tree, style);
+            render(&tree)
+        }
+        Err(_) => json,
+    }
+}
+
+// Without `std` there are no thread settings to apply.
+#[cfg(not(feature = "std"))]
+pub(crate) fn present(json: String) -> String {
+    json
+}
+
+///
+/// Unit of non-uom values held in `Amount` (e.g. a currency or a counted thing).
+///
+/// Use `define_unit!` to declare one.
+///
+pub trait Unit {
+    /// Returns symbol written in the `unit` field of json (must not be empty).
+    fn symbol() -> &'static str;
+}
+
+///
+/// Declares unit types implementing `units::Unit`.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate cherries;
+/// use cherries::units::Unit;
+///
+/// define_unit!(Usd, "USD");
+/// define_unit!(pub Bolts, "bolts");
+///
+/// fn main() {
+///     assert_eq!(Usd::symbol(), "USD");
+///     assert_eq!(Bolts::symbol(), "bolts");
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_unit {
+    ( $vis:vis $name:ident, $symbol:expr ) => {
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+        $vis struct $name;
+        impl $crate::units::Unit for $name {
+            fn symbol() -> &'static str {
+                $symbol
+            }
+        }
+    };
+}
+
+///
+/// Value `V` tagged with unit `U`, written by `to_json` with `U::symbol()` as unit.
+///
+/// Amounts of the same unit can be added, subtracted and divided (giving `V`),
+/// and scaled by `V`.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate cherries;
+/// use cherries::node::{Cherries, Leaf};
+/// use cherries::units::Amount;
+///
+/// define_unit!(Usd, "USD");
+///
+/// fn main() {
+///     let price = Leaf::new().value(Amount::<f64, Usd>::new(12.5)).name("price").build();
+///     let count = Leaf::new().value(4.0).name("count").build();
+///     let total = (price * count).labeled("total");
+///     assert_eq!(total.quantity().value, 50.0);
+///     assert_eq!(total.symbol(), "USD".to_string());
+///     assert_eq!(
+///         total.to_json(),
+///         "{\"label\":\"total\",\"value\":50,\"unit\":\"USD\",\"op\":\"mul\",\"subexpr\":[\
+///             {\"label\":\"price\",\"value\":12.5,\"unit\":\"USD\"},\
+///             {\"label\":\"count\",\"value\":4,\"unit\":\"dimensionless\"}\
+///         ]}".to_string()
+///     );
+/// }
+/// ```
+pub struct Amount<V, U> {
+    pub value: V,
+    unit: core::marker::PhantomData<U>,
+}
+
+impl<V, U> Amount<V, U> {
+    ///
+    /// Makes amount of `value` in `U`.
+    ///
+    pub fn new(value: V) -> Self {
+        Amount {
+            value,
+            unit: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<V: Clone, U> Clone for Amount<V, U> {
+    fn clone(&self) -> Self {
+        Amount::new(self.value.clone())
+    }
+}
+
+impl<V: Copy, U> Copy for Amount<V, U> {}
+
+impl<V: PartialEq, U> PartialEq for Amount<V, U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<V: PartialOrd, U> PartialOrd for Amount<V, U> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<V: Debug, U: Unit> Debug for Amount<V, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.value, U::symbol())
+    }
+}
+
+impl<V: core::ops::Add<Output = V>, U> core::ops::Add for Amount<V, U> {
+    type Output = Amount<V, U>;
+
+    fn add(self, other: Self) -> Self::Output {
+        Amount::new(self.value + other.value)
+    }
+}
+
+impl<V: core::ops::Sub<Output = V>, U> core::ops::Sub for Amount<V, U> {
+    type Output = Amount<V, U>;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Amount::new(self.value - other.value)
+    }
+}
+
+impl<V: core::ops::Mul<Output = V>, U> core::ops::Mul<V> for Amount<V, U> {
+    type Output = Amount<V, U>;
+
+    fn mul(self, other: V) -> Self::Output {
+        Amount::new(self.value * other)
+    }
+}
+
+impl<V: core::ops::Div<Output = V>, U> core::ops::Div<V> for Amount<V, U> {
+    type Output = Amount<V, U>;
+
+    fn div(self, other: V) -> Self::Output {
+        Amount::new(self.value / other)
+    }
+}
+
+impl<V: core::ops::Div<Output = V>, U> core::ops::Div for Amount<V, U> {
+    type Output = V;
+
+    fn div(self, other: Self) -> V {
+        self.value / other.value
+    }
+}
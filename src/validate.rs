@@ -1,32 +1,142 @@
+use super::checks::Check;
+use super::export::Tree;
 use super::node::*;
-use std::cell::RefCell;
-use std::clone::Clone;
-use std::fmt::Debug;
+use alloc::{borrow::ToOwned, boxed::Box, string::{String, ToString}, vec, vec::Vec};
+use core::clone::Clone;
+use core::fmt::{Debug, Display};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
+use crate::session::notify_failure;
+
+// Without `std` there is no session to notify.
+#[cfg(not(feature = "std"))]
+fn notify_failure<T: Clone + Debug>(_: &Cherry<T>, _: &str) {}
 
 /// For validation.
 ///
-#[derive(Debug)]
+/// `codes` are the codes of failed checks made with `validate_code`, in the order checked.
+/// `tree` is the failing node with all its subexpressions (as written by `to_json`),
+/// to be inspected without parsing json (or the node alone, marked `collapsed`,
+/// if subexpressions were recorded as invalid json text, e.g. by archived trees).
+/// `against` are the trees of the other nodes of failed checks made with `validate_against`.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Error {
     pub label: String,
     pub msg: Vec<String>,
-    pub tree: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub codes: Vec<ErrorCode>,
+    pub tree: Tree,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub against: Vec<Tree>,
+}
+
+// Tree of `node`, without subexpressions if they cannot be read
+// (or with its label, value and unit alone if neither can its head).
+fn tree_of<T: Clone + Debug>(node: &Cherry<T>) -> Tree {
+    Tree::from_json(&node.to_json())
+        .or_else(|_| Tree::from_json(&node.to_json_with_depth(0)))
+        .unwrap_or_else(|_| Tree::bare(node))
+}
+
+///
+/// Severity of a validation check.
+///
+/// Failed `Error` checks fail `into_result`; failed `Warning` and `Info` checks
+/// (advisories and notes) don't, but are recorded as findings of the node
+/// (written in json as `"findings"`).
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::{node::{Cherries, Leaf}, validate::{Severity, Validate}};
+///
+/// let span = Leaf::new().name("L").value(12.0).build();
+/// let span = span
+///     .validate("must be positive", |l| l > &0.0)
+///     .check(Severity::Warning, "spans over 10 need a vibration check", |l| l <= &10.0)
+///     .check(Severity::Info, "span is not standard", |l| l % 3.0 == 0.0)
+///     .into_result()
+///     .unwrap();
+/// assert_eq!(span.findings().len(), 1);
+/// assert_eq!(
+///     span.to_json(),
+///     "{\"label\":\"L\",\"value\":12,\"unit\":\"dimensionless\",\"findings\":[\
+///         {\"severity\":\"warning\",\"msg\":\"spans over 10 need a vibration check\"}\
+///     ]}".to_string()
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    ///
+    /// Returns name written in json.
+    ///
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+///
+/// Failed validation check that does not fail validation (see `Severity`).
+///
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub msg: String,
+}
+
+///
+/// Machine-readable code of a failed check (written in json in snake case,
+/// e.g. `"utilization_exceeded"`), so downstream systems branch on codes instead of messages.
+///
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Utilization (ratio of action to resistance) is greater than 1.
+    UtilizationExceeded,
+    /// Value is outside its allowed range.
+    OutOfRange,
+    /// Value is zero or negative.
+    NotPositive,
+    /// Value is infinite or NaN.
+    NotFinite,
+    /// Code defined by the application.
+    Other(String),
 }
 
 /// Type synonym for `std::result::Result<Cherry<T>, Error>`.
 ///
 /// Used in validation.
 ///
-pub type Result<T> = std::result::Result<Cherry<T>, Error>;
+pub type Result<T> = core::result::Result<Cherry<T>, Error>;
 
 impl PartialEq for Error {
     fn eq(&self, other: &Self) -> bool {
-        (self.label == other.label) && (self.msg == other.msg)
+        (self.label == other.label) && (self.msg == other.msg) && (self.codes == other.codes)
     }
 }
 
+#[must_use = "validation errors are only reported by `into_result`"]
 pub struct ValidateChain<T: Clone + Debug> {
-    pub cherry: Cherry<T>,
-    pub errors: RefCell<Vec<String>>,
+    cherry: Cherry<T>,
+    errors: Vec<String>,
+    codes: Vec<ErrorCode>,
+    findings: Vec<Finding>,
+    against: Vec<Tree>,
 }
 
 ///
@@ -35,16 +145,89 @@ pub struct ValidateChain<T: Clone + Debug> {
 /// Provides method `into_result` to aggregate validation error.
 ///
 impl<T: Clone + Debug> ValidateChain<T> {
+    ///
+    /// Returns reference of the node under validation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::Validate};
+    ///
+    /// let x = Leaf::new().name("x").value(2).build();
+    /// let chain = x.validate("must be odd", |v| v % 2 == 1);
+    /// assert_eq!(chain.cherry().name(), &"x".to_string());
+    /// ```
+    pub fn cherry(&self) -> &Cherry<T> {
+        &self.cherry
+    }
+    ///
+    /// Returns error messages collected so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::Validate};
+    ///
+    /// let x = Leaf::new().name("x").value(2).build();
+    /// let chain = x
+    ///     .validate("must be odd", |v| v % 2 == 1)
+    ///     .validate("must be positive", |v| v > &0);
+    /// assert_eq!(chain.errors(), &["must be odd".to_string()]);
+    /// ```
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+    ///
+    /// Returns the number of errors collected so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::Validate};
+    ///
+    /// let x = Leaf::new().name("x").value(2).build();
+    /// let chain = x
+    ///     .validate("must be odd", |v| v % 2 == 1)
+    ///     .validate("must be negative", |v| v < &0);
+    /// assert_eq!(chain.error_count(), 2);
+    /// ```
+    pub fn error_count(&self) -> usize {
+        self.errors.len()
+    }
+    ///
+    /// Returns warnings and notes collected so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::{Severity, Validate}};
+    ///
+    /// let x = Leaf::new().name("x").value(2).build();
+    /// let chain = x
+    ///     .validate("must be positive", |v| v > &0)
+    ///     .check(Severity::Warning, "should be odd", |v| v % 2 == 1);
+    /// assert_eq!(chain.error_count(), 0);
+    /// assert_eq!(chain.findings()[0].severity, Severity::Warning);
+    /// ```
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
     ///
     /// Aggregates validation error.
     ///
     /// Coverts `ValidateProxy<T>` to [`cherries::Result<T>`](../node/type.Result.html).
+    /// Warnings and notes don't fail validation, but are recorded as findings of the node
+    /// (also of `tree` of the error).
     ///
     /// # Examples
     ///
     /// ```
     /// extern crate cherries;
-    /// use cherries::{node::Leaf, validate::{Validate, Error}};
+    /// use cherries::{export::Value, node::Leaf, validate::Validate};
     /// extern crate uom;
     /// use uom::si::{f32::*, length::meter, area::square_meter};
     ///
@@ -65,37 +248,82 @@ impl<T: Clone + Debug> ValidateChain<T> {
     ///         quantity < &Area::new::<square_meter>(0.0)
     ///     })
     ///     .into_result();
+    /// let err = validated.unwrap_err();
+    /// assert_eq!(err.label, "(mul)".to_string());
     /// assert_eq!(
-    ///     Err(Error {
-    ///         label: "(mul)".to_string(),
-    ///         msg: vec![
-    ///              "must be less than 1.0!!".to_string(),
-    ///              "must be less than 0.0!!".to_string()
-    ///         ],
-    ///         tree: "json tree".to_string(),
-    ///     }),
-    ///     validated
+    ///     err.msg,
+    ///     vec![
+    ///          "must be less than 1.0!!".to_string(),
+    ///          "must be less than 0.0!!".to_string()
+    ///     ]
     /// );
+    /// assert_eq!(err.tree.value, Value::Number(2.0));
+    /// assert_eq!(err.tree.unit, "m^2".to_string());
     /// ```
     pub fn into_result(self) -> Result<T> {
-        if self.errors.borrow().is_empty() {
-            Ok(self.cherry.to_owned())
+        let cherry = self.cherry.with_findings(self.findings);
+        if self.errors.is_empty() {
+            Ok(cherry)
         } else {
             Err(Error {
-                label: self.cherry.name().to_owned(),
-                msg: self.errors.into_inner(),
-                tree: self.cherry.to_json(),
+                label: cherry.name().to_owned(),
+                msg: self.errors,
+                codes: self.codes,
+                tree: tree_of(&cherry),
+                against: self.against,
             })
         }
     }
+    // Records failed check with `severity`.
+    fn fail(&mut self, severity: Severity, msg: String) {
+        match severity {
+            Severity::Error => {
+                notify_failure(&self.cherry, &msg);
+                self.errors.push(msg);
+            }
+            _ => self.findings.push(Finding { severity, msg }),
+        }
+    }
+}
+
+///
+/// Iterates over collected error messages (consuming the chain).
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::{node::Leaf, validate::Validate};
+///
+/// let x = Leaf::new().name("x").value(2).build();
+/// let errors = x
+///     .validate("must be odd", |v| v % 2 == 1)
+///     .validate("must be negative", |v| v < &0)
+///     .into_iter()
+///     .collect::<Vec<_>>();
+/// assert_eq!(errors, vec!["must be odd".to_string(), "must be negative".to_string()]);
+/// ```
+impl<T: Clone + Debug> IntoIterator for ValidateChain<T> {
+    type Item = String;
+    type IntoIter = alloc::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
 }
 
 ///
 /// Trait: Validate
 ///
-/// Provides method `validate`.
+/// Provides methods `validate` and `check`.
+///
+/// Implementors provide `validate`; the other methods are provided for types
+/// which start a `ValidateChain` (`Into<ValidateChain<T>>`).
 ///
 pub trait Validate<T: Clone + Debug> {
+    ///
+    /// Checks `predicate` as `Severity::Error`.
+    ///
     fn validate<IntoString, Predicate>(
         self,
         msg: IntoString,
@@ -104,6 +332,293 @@ pub trait Validate<T: Clone + Debug> {
     where
         IntoString: Into<String>,
         Predicate: FnOnce(&T) -> bool;
+    ///
+    /// Checks `predicate` with `severity`, recording `msg` if it fails.
+    ///
+    fn check<IntoString, Predicate>(
+        self,
+        severity: Severity,
+        msg: IntoString,
+        predicate: Predicate,
+    ) -> ValidateChain<T>
+    where
+        Self: Sized + Into<ValidateChain<T>>,
+        IntoString: Into<String>,
+        Predicate: FnOnce(&T) -> bool,
+    {
+        let mut chain = self.into();
+        if !predicate(chain.cherry.quantity()) {
+            chain.fail(severity, msg.into());
+        }
+        chain
+    }
+    ///
+    /// Checks `predicate` as `Severity::Error`, recording `code` (see `Error`) if it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::{ErrorCode, Validate}};
+    ///
+    /// let eta = Leaf::new().name("eta").value(1.2).build();
+    /// let err = eta
+    ///     .validate_code(ErrorCode::UtilizationExceeded, "must be at most 1", |v| v <= &1.0)
+    ///     .validate_code(ErrorCode::NotPositive, "must be positive", |v| v > &0.0)
+    ///     .validate("must be less than 1.1", |v| v < &1.1)
+    ///     .into_result()
+    ///     .unwrap_err();
+    /// assert_eq!(err.msg.len(), 2);
+    /// assert_eq!(err.codes, vec![ErrorCode::UtilizationExceeded]);
+    /// assert!(serde_json::to_string(&err).unwrap().contains("\"codes\":[\"utilization_exceeded\"]"));
+    /// ```
+    fn validate_code<IntoString, Predicate>(
+        self,
+        code: ErrorCode,
+        msg: IntoString,
+        predicate: Predicate,
+    ) -> ValidateChain<T>
+    where
+        Self: Sized + Into<ValidateChain<T>>,
+        IntoString: Into<String>,
+        Predicate: FnOnce(&T) -> bool,
+    {
+        let mut chain = self.into();
+        if !predicate(chain.cherry.quantity()) {
+            chain.fail(Severity::Error, msg.into());
+            chain.codes.push(code);
+        }
+        chain
+    }
+    ///
+    /// Checks `predicate` as `Severity::Error`, recording the message `msg` builds
+    /// from the quantity if it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::Validate};
+    ///
+    /// let eta = Leaf::new().name("eta").value(1.25).build();
+    /// let err = eta
+    ///     .validate_with(|v| format!("got {:?}, limit is 1.0", v), |v| v <= &1.0)
+    ///     .into_result()
+    ///     .unwrap_err();
+    /// assert_eq!(err.msg, vec!["got 1.25, limit is 1.0".to_string()]);
+    /// ```
+    fn validate_with<Message, Predicate>(self, msg: Message, predicate: Predicate) -> ValidateChain<T>
+    where
+        Self: Sized + Into<ValidateChain<T>>,
+        Message: FnOnce(&T) -> String,
+        Predicate: FnOnce(&T) -> bool,
+    {
+        let mut chain = self.into();
+        // the message is built while the quantity is borrowed, and recorded after
+        let quantity = chain.cherry.quantity();
+        let failure = if predicate(quantity) { None } else { Some(msg(quantity)) };
+        if let Some(msg) = failure {
+            chain.fail(Severity::Error, msg);
+        }
+        chain
+    }
+    ///
+    /// Checks the quantity with `validator` as `Severity::Error`,
+    /// recording the error it returns (e.g. a `String` or a custom error) as message if it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::Validate};
+    ///
+    /// fn slenderness(lambda: &f64) -> Result<(), String> {
+    ///     match *lambda {
+    ///         l if l > 200.0 => Err(format!("{} exceeds 200 for compression members", l)),
+    ///         l if l < 0.0 => Err("must not be negative".to_string()),
+    ///         _ => Ok(()),
+    ///     }
+    /// }
+    ///
+    /// let lambda = Leaf::new().name("lambda").value(240.0).build();
+    /// let err = lambda.validate_result(slenderness).into_result().unwrap_err();
+    /// assert_eq!(err.msg, vec!["240 exceeds 200 for compression members".to_string()]);
+    /// ```
+    fn validate_result<E, Validator>(self, validator: Validator) -> ValidateChain<T>
+    where
+        Self: Sized + Into<ValidateChain<T>>,
+        E: Display,
+        Validator: FnOnce(&T) -> core::result::Result<(), E>,
+    {
+        let mut chain = self.into();
+        let failure = validator(chain.cherry.quantity()).err().map(|err| err.to_string());
+        if let Some(msg) = failure {
+            chain.fail(Severity::Error, msg);
+        }
+        chain
+    }
+    ///
+    /// Checks `predicate` of the quantities of this node and `other` as `Severity::Error`
+    /// (e.g. deflection against span / 250), recording `msg` and the tree of `other`
+    /// (as `against` of the error) if it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::{node::Leaf, validate::Validate};
+    ///
+    /// let span = Leaf::new().name("L").value(6000.0).build();
+    /// let load = Leaf::new().name("w").value(4.0).build();
+    /// let deflection = (load * Leaf::new().name("k").value(7.0).build()).labeled("delta");
+    /// let err = deflection
+    ///     .validate_against(&span, "must be less than L/250", |delta, span| delta < &(span / 250.0))
+    ///     .into_result()
+    ///     .unwrap_err();
+    /// assert_eq!(err.label, "delta".to_string());
+    /// assert_eq!(err.tree.subexpr.len(), 2);
+    /// assert_eq!(err.against[0].label, "L".to_string());
+    /// ```
+    fn validate_against<U, IntoString, Predicate>(
+        self,
+        other: &Cherry<U>,
+        msg: IntoString,
+        predicate: Predicate,
+    ) -> ValidateChain<T>
+    where
+        Self: Sized + Into<ValidateChain<T>>,
+        U: Clone + Debug,
+        IntoString: Into<String>,
+        Predicate: FnOnce(&T, &U) -> bool,
+    {
+        let mut chain = self.into();
+        if !predicate(chain.cherry.quantity(), other.quantity()) {
+            chain.fail(Severity::Error, msg.into());
+            chain.against.push(tree_of(other));
+        }
+        chain
+    }
+    ///
+    /// Checks every rule of `rules` in order (see `RuleSet`).
+    ///
+    fn apply_rules(self, rules: &RuleSet<T>) -> ValidateChain<T>
+    where
+        Self: Sized + Into<ValidateChain<T>>,
+    {
+        rules.rules.iter().fold(self.into(), |chain, rule| {
+            chain.check(rule.severity, rule.name.as_str(), |v| (rule.predicate)(v))
+        })
+    }
+}
+
+// Named check of a `RuleSet`.
+struct Rule<T> {
+    name: String,
+    severity: Severity,
+    predicate: Box<dyn Fn(&T) -> bool>,
+}
+
+///
+/// Reusable battery of named checks with severities, applied to nodes by `apply_rules`.
+///
+/// Messages of failed checks are the names of their rules.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::{node::{Cherries, Leaf}, validate::{RuleSet, Severity, Validate}};
+///
+/// let utilization = RuleSet::new()
+///     .rule(Severity::Error, "must not exceed 1", |eta: &f64| *eta <= 1.0)
+///     .rule(Severity::Error, "must not be negative", |eta: &f64| *eta >= 0.0)
+///     .rule(Severity::Warning, "is uneconomic below 0.5", |eta: &f64| *eta >= 0.5);
+/// assert_eq!(utilization.len(), 3);
+///
+/// let members = vec![("B1", 0.7), ("B2", 1.1), ("B3", 0.3)];
+/// let results = members
+///     .into_iter()
+///     .map(|(name, eta)| Leaf::new().name(name).value(eta).build())
+///     .map(|eta| eta.apply_rules(&utilization).into_result())
+///     .collect::<Vec<_>>();
+/// assert!(results[0].is_ok());
+/// assert_eq!(results[1].as_ref().unwrap_err().msg, vec!["must not exceed 1".to_string()]);
+/// assert_eq!(results[2].as_ref().unwrap().findings()[0].msg, "is uneconomic below 0.5");
+/// ```
+pub struct RuleSet<T> {
+    rules: Vec<Rule<T>>,
+}
+
+impl<T> RuleSet<T> {
+    ///
+    /// Makes empty rule set.
+    ///
+    pub fn new() -> Self {
+        RuleSet { rules: vec![] }
+    }
+    ///
+    /// Adds rule `name` checking `predicate` with `severity`.
+    ///
+    pub fn rule<S, Predicate>(mut self, severity: Severity, name: S, predicate: Predicate) -> Self
+    where
+        S: Into<String>,
+        Predicate: Fn(&T) -> bool + 'static,
+    {
+        self.rules.push(Rule {
+            name: name.into(),
+            severity,
+            predicate: Box::new(predicate),
+        });
+        self
+    }
+    ///
+    /// Adds named check `check` (e.g. `checks::positive()`) with `severity`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::checks::{finite, in_range};
+    /// use cherries::{node::Leaf, validate::{RuleSet, Severity, Validate}};
+    ///
+    /// let rules = RuleSet::new()
+    ///     .check(Severity::Error, finite())
+    ///     .check(Severity::Warning, in_range(0.5, 1.0));
+    /// let eta = Leaf::new().name("eta").value(0.3).build();
+    /// let eta = eta.apply_rules(&rules).into_result().unwrap();
+    /// assert_eq!(eta.findings()[0].msg, "must be in range [0.5, 1.0]");
+    /// ```
+    pub fn check(self, severity: Severity, check: Check<T>) -> Self
+    where
+        T: 'static,
+    {
+        let name = check.name().to_owned();
+        self.rule(severity, name, move |v| check.passes(v))
+    }
+    ///
+    /// Returns names of the rules in order.
+    ///
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.name.as_str())
+    }
+    ///
+    /// Returns the number of rules.
+    ///
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+    ///
+    /// Returns whether there are no rules.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+impl<T> Default for RuleSet<T> {
+    fn default() -> Self {
+        RuleSet::new()
+    }
 }
 
 ///
@@ -117,7 +632,7 @@ pub trait Validate<T: Clone + Debug> {
 ///
 /// ```
 /// extern crate cherries;
-/// use cherries::{node::Leaf, validate::{Validate, Error}};
+/// use cherries::{node::Leaf, validate::Validate};
 /// extern crate uom;
 /// use uom::si::{f32::*, length::meter, area::square_meter};
 ///
@@ -135,14 +650,10 @@ pub trait Validate<T: Clone + Debug> {
 ///         quantity < &Area::new::<square_meter>(1.0)
 ///     })
 ///     .into_result();
-/// assert_eq!(
-///     Err(Error {
-///         label: "(mul)".to_string(),
-///         msg: vec!["must be less than 1.0!!".to_string()],
-///         tree: "json tree".to_string(),
-///     }),
-///     validated
-/// );
+/// let err = validated.unwrap_err();
+/// assert_eq!(err.label, "(mul)".to_string());
+/// assert_eq!(err.msg, vec!["must be less than 1.0!!".to_string()]);
+/// assert_eq!(err.tree.subexpr[1].label, "y".to_string());
 /// ```
 impl<T: Clone + Debug> Validate<T> for Cherry<T> {
     fn validate<IntoString, Predicate>(
@@ -154,16 +665,21 @@ impl<T: Clone + Debug> Validate<T> for Cherry<T> {
         IntoString: Into<String>,
         Predicate: FnOnce(&T) -> bool,
     {
-        if predicate(&self.quantity()) {
-            ValidateChain {
-                cherry: self,
-                errors: RefCell::new(vec![]),
-            }
-        } else {
-            ValidateChain {
-                cherry: self,
-                errors: RefCell::new(vec![msg.into()]),
-            }
+        self.check(Severity::Error, msg, predicate)
+    }
+}
+
+///
+/// Starts a chain of the node without checks.
+///
+impl<T: Clone + Debug> From<Cherry<T>> for ValidateChain<T> {
+    fn from(cherry: Cherry<T>) -> Self {
+        ValidateChain {
+            cherry,
+            errors: vec![],
+            codes: vec![],
+            findings: vec![],
+            against: vec![],
         }
     }
 }
@@ -177,7 +693,7 @@ impl<T: Clone + Debug> Validate<T> for Cherry<T> {
 ///
 /// ```
 /// extern crate cherries;
-/// use cherries::{node::Leaf, validate::{Validate, Error}};
+/// use cherries::{node::Leaf, validate::Validate};
 /// extern crate uom;
 /// use uom::si::{f32::*, length::meter, area::square_meter};
 ///
@@ -198,17 +714,10 @@ impl<T: Clone + Debug> Validate<T> for Cherry<T> {
 ///         quantity < &Area::new::<square_meter>(0.0)
 ///     })
 ///     .into_result();
-/// assert_eq!(
-///     Err(Error {
-///         label: "(mul)".to_string(),
-///         msg: vec![
-///              "must be less than 1.0!!".to_string(),
-///              "must be less than 0.0!!".to_string()
-///         ],
-///         tree: "json tree".to_string(),
-///     }),
-///     validated
-/// );
+/// let err = validated.unwrap_err();
+/// assert_eq!(err.label, "(mul)".to_string());
+/// assert_eq!(err.msg.len(), 2);
+/// assert_eq!(err.tree.subexpr[0].label, "x".to_string());
 /// 
 impl<T: Clone + Debug> Validate<T> for ValidateChain<T> {
     fn validate<IntoString, Predicate>(
@@ -220,11 +729,97 @@ impl<T: Clone + Debug> Validate<T> for ValidateChain<T> {
         IntoString: Into<String>,
         Predicate: FnOnce(&T) -> bool,
     {
-        if predicate(&self.cherry.quantity()) {
-            self
-        } else {
-            self.errors.borrow_mut().push(msg.into());
-            self
-        }
+        self.check(Severity::Error, msg, predicate)
     }
 }
+
+// Validates `item` with every (message, predicate) pair of `rules` (see `validate_all`).
+fn run_rules<T, S, F>(item: Cherry<T>, rules: &[(S, F)]) -> Result<T>
+where
+    T: Clone + Debug,
+    S: AsRef<str>,
+    F: Fn(&T) -> bool,
+{
+    rules
+        .iter()
+        .fold(
+            ValidateChain {
+                cherry: item,
+                errors: vec![],
+                codes: vec![],
+                findings: vec![],
+                against: vec![],
+            },
+            |chain, (msg, predicate)| chain.validate(msg.as_ref(), |v| predicate(v)),
+        )
+        .into_result()
+}
+
+///
+/// Applies all `rules` (pairs of message and predicate) to each of `items`.
+///
+/// Returns results in the same order as `items`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::{node::Leaf, validate::validate_all};
+///
+/// let items = vec![
+///     Leaf::new().name("a").value(2).build(),
+///     Leaf::new().name("b").value(3).build(),
+/// ];
+/// let rules: Vec<(&str, fn(&i32) -> bool)> = vec![
+///     ("must be even", |v| v % 2 == 0),
+///     ("must be positive", |v| v > &0),
+/// ];
+/// let results = validate_all(items, &rules);
+/// assert!(results[0].is_ok());
+/// assert_eq!(results[1].as_ref().unwrap_err().msg, vec!["must be even".to_string()]);
+/// ```
+pub fn validate_all<T, I, S, F>(items: I, rules: &[(S, F)]) -> Vec<Result<T>>
+where
+    T: Clone + Debug,
+    I: IntoIterator<Item = Cherry<T>>,
+    S: AsRef<str>,
+    F: Fn(&T) -> bool,
+{
+    items
+        .into_iter()
+        .map(|item| run_rules(item, rules))
+        .collect()
+}
+
+///
+/// Parallel version of [`validate_all`](fn.validate_all.html) (requires feature `rayon`).
+///
+/// Results are merged in the same order as `items` regardless of scheduling.
+///
+/// # Examples
+///
+/// ```
+/// extern crate cherries;
+/// use cherries::{node::Leaf, validate::validate_all_par};
+///
+/// let items = (0..1000)
+///     .map(|i| Leaf::new().name(format!("x{}", i)).value(i).build())
+///     .collect::<Vec<_>>();
+/// let rules: Vec<(&str, fn(&i32) -> bool)> = vec![("must be even", |v| v % 2 == 0)];
+/// let results = validate_all_par(items, &rules);
+/// assert_eq!(results.iter().filter(|res| res.is_err()).count(), 500);
+/// assert_eq!(results[1].as_ref().unwrap_err().label, "x1".to_string());
+/// ```
+#[cfg(feature = "rayon")]
+pub fn validate_all_par<T, I, S, F>(items: I, rules: &[(S, F)]) -> Vec<Result<T>>
+where
+    T: Clone + Debug + Send,
+    I: IntoParallelIterator<Item = Cherry<T>>,
+    S: AsRef<str> + Sync,
+    F: Fn(&T) -> bool + Sync,
+{
+    items
+        .into_par_iter()
+        .map(|item| run_rules(item, rules))
+        .collect()
+}
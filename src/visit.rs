@@ -0,0 +1,256 @@
+use super::export::{Tree, Value};
+use super::node::{Cherries, Cherry, Step, Subexpr};
+use alloc::{format, string::{String, ToString}, vec, vec::Vec};
+use core::fmt::Debug;
+use core::mem;
+
+///
+/// Custom analysis of expression trees (e.g. totals, unit audits or exporters),
+/// walked by `Cherry::accept`.
+///
+/// Every node is passed as a `Tree` without its subexpressions (label, value, unit, operation
+/// and recorded metadata), entered before and left after its subexpressions.
+///
+pub trait Visitor {
+    /// Visits `node` at `depth` (`0` for the root) before its subexpressions.
+    fn enter(&mut self, node: &Tree, depth: usize);
+    /// Visits `node` at `depth` after its subexpressions (does nothing by default).
+    fn leave(&mut self, _node: &Tree, _depth: usize) {}
+}
+
+impl<T: Clone + Debug> Cherry<T> {
+    ///
+    /// Walks every node of the tree (in pre-order, without recursion) with `visitor`.
+    ///
+    /// Fails only for subexpressions recorded as invalid json text
+    /// (e.g. by archived trees), after visiting the nodes before them.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::export::Tree;
+    /// use cherries::visit::Visitor;
+    /// use std::collections::BTreeSet;
+    /// extern crate uom;
+    /// use uom::si::{f64::*, force::newton, length::meter};
+    ///
+    /// #[derive(Default)]
+    /// struct Audit {
+    ///     leaves: usize,
+    ///     units: BTreeSet<String>,
+    ///     sexpr: String,
+    /// }
+    ///
+    /// impl Visitor for Audit {
+    ///     fn enter(&mut self, node: &Tree, depth: usize) {
+    ///         if node.op.is_none() {
+    ///             self.leaves += 1;
+    ///         }
+    ///         self.units.insert(node.unit.clone());
+    ///         if depth > 0 {
+    ///             self.sexpr.push(' ');
+    ///         }
+    ///         self.sexpr.push_str(&format!("({}", node.label));
+    ///     }
+    ///     fn leave(&mut self, _node: &Tree, _depth: usize) {
+    ///         self.sexpr.push(')');
+    ///     }
+    /// }
+    ///
+    /// let f = Leaf::new().value(Force::new::<newton>(6.0)).name("F").build();
+    /// let e = Leaf::new().value(Length::new::<meter>(0.5)).name("e").build();
+    /// let n = Leaf::new().value(2.0).name("n").build();
+    /// let m = (f * e).labeled("M") * n;
+    ///
+    /// let mut audit = Audit::default();
+    /// m.accept(&mut audit).unwrap();
+    /// assert_eq!(audit.leaves, 3);
+    /// assert_eq!(audit.sexpr, "((mul) (M (F) (e)) (n))");
+    /// assert!(audit.units.contains("m^2 kg^1 s^-2") && audit.units.contains("dimensionless"));
+    /// ```
+    pub fn accept<V: Visitor + ?Sized>(&self, visitor: &mut V) -> serde_json::Result<()> {
+        let mut entered: Vec<Tree> = Vec::new();
+        let mut error = None;
+        self.traverse(|step| {
+            if error.is_some() {
+                return;
+            }
+            match step {
+                Step::Enter(head) => match Tree::from_json(&format!("{}}}", head)) {
+                    Ok(node) => {
+                        visitor.enter(&node, entered.len());
+                        entered.push(node);
+                    }
+                    Err(err) => error = Some(err),
+                },
+                Step::Json(json) => match serde_json::from_str::<Vec<Tree>>(&format!("[{}]", json)) {
+                    Ok(trees) => {
+                        for tree in trees {
+                            accept_tree(visitor, tree, entered.len());
+                        }
+                    }
+                    Err(err) => error = Some(err),
+                },
+                Step::Leave => {
+                    if let Some(node) = entered.pop() {
+                        visitor.leave(&node, entered.len());
+                    }
+                }
+            }
+        });
+        error.map_or(Ok(()), Err)
+    }
+    ///
+    /// Returns `(depth, label, value, unit)` of every node of the tree in pre-order
+    /// (depth-first, `0` for the root), for queries by iterator adapters.
+    ///
+    /// Ends before subexpressions recorded as invalid json text (see `accept`).
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::export::Value;
+    /// extern crate uom;
+    /// use uom::si::{f64::*, length::meter};
+    ///
+    /// let b = Leaf::new().value(Length::new::<meter>(0.3)).name("b").build();
+    /// let h = Leaf::new().value(Length::new::<meter>(0.5)).name("h").build();
+    /// let a = (b * h).labeled("A");
+    /// let nodes = a.iter_nodes().map(|(depth, label, ..)| (depth, label)).collect::<Vec<_>>();
+    /// assert_eq!(nodes, vec![(0, "A".to_string()), (1, "b".to_string()), (1, "h".to_string())]);
+    /// let lengths = a.iter_nodes().filter(|(_, _, _, unit)| unit == "m^1").count();
+    /// assert_eq!(lengths, 2);
+    /// let largest = a
+    ///     .iter_nodes()
+    ///     .filter_map(|(_, label, value, _)| match value {
+    ///         Value::Number(value) if label != "A" => Some(value),
+    ///         _ => None,
+    ///     })
+    ///     .fold(0.0, f32::max);
+    /// assert_eq!(largest, 0.5);
+    /// ```
+    pub fn iter_nodes(&self) -> impl Iterator<Item = (usize, String, Value, String)> {
+        let mut nodes = Nodes(vec![]);
+        // nodes before invalid subexpressions are kept
+        let _ = self.accept(&mut nodes);
+        nodes.0.into_iter()
+    }
+    ///
+    /// Returns the first node labeled `label` (in pre-order, including the root)
+    /// with all its subexpressions, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate cherries;
+    /// use cherries::node::{Cherries, Leaf};
+    /// use cherries::report::Renderer;
+    /// extern crate uom;
+    /// use uom::si::{f64::*, force::kilonewton, length::meter};
+    ///
+    /// let p = Leaf::new().value(Force::new::<kilonewton>(40.0)).name("P").build();
+    /// let l = Leaf::new().value(Length::new::<meter>(6.0)).name("L").build();
+    /// let four = Leaf::new().value(4.0).name("4").build();
+    /// let gamma = Leaf::new().value(1.5).name("gamma").build();
+    /// let design = ((p * l / four).labeled("M") * gamma).labeled("M_Ed");
+    ///
+    /// let moment = design.subtree("M").unwrap();
+    /// assert_eq!(moment.name(), "M");
+    /// assert_eq!(moment.value(), Ok(60000.0));
+    /// assert_eq!(moment.symbol(), "m^2 kg^1 s^-2");
+    /// assert_eq!(moment.tree().unwrap().subexpr.len(), 2);
+    /// assert!(design.to_json().contains(&moment.to_json()));
+    /// assert_eq!(
+    ///     Renderer::new().to_markdown(&moment).unwrap(),
+    ///     "- **M** = 60000 m^2 kg^1 s^-2\n  - **(mul)** = 240000 m^2 kg^1 s^-2\n    \
+    ///      - **P** = 40000 m^1 kg^1 s^-2\n    - **L** = 6 m^1\n  - **4** = 4\n"
+    /// );
+    /// assert!(design.subtree("N").is_none());
+    /// ```
+    pub fn subtree(&self, label: &str) -> Option<Subtree> {
+        let subexpr = self.find(label)?;
+        let json = subexpr.to_json();
+        let mut node = Tree::from_json(&json).ok()?;
+        node.subexpr.clear();
+        Some(Subtree { node, subexpr, json })
+    }
+}
+
+///
+/// Node of a tree with all its subexpressions, taken out by `Cherry::subtree`
+/// to be reported, exported or used as subexpression of new nodes on its own.
+///
+/// Its value is untyped, written as in `export::Tree`.
+///
+#[derive(Clone, Debug)]
+pub struct Subtree {
+    // Node without subexpressions.
+    node: Tree,
+    subexpr: Subexpr,
+    json: String,
+}
+
+impl Subtree {
+    ///
+    /// Returns the node with all its subexpressions in the export schema.
+    ///
+    pub fn tree(&self) -> serde_json::Result<Tree> {
+        Tree::from_json(&self.json)
+    }
+}
+
+impl Cherries for Subtree {
+    fn name(&self) -> &str {
+        &self.node.label
+    }
+    fn value(&self) -> Result<f32, String> {
+        match &self.node.value {
+            Value::Number(value) => Ok(*value),
+            Value::Bool(value) => Err(value.to_string()),
+            Value::Text(value) => Err(value.clone()),
+            Value::Interval(lower, upper) => Err(format!("[{},{}]", lower, upper)),
+        }
+    }
+    fn symbol(&self) -> String {
+        self.node.unit.clone()
+    }
+    fn to_json(&self) -> String {
+        crate::units::present(self.json.clone())
+    }
+    fn as_subexpr(&self) -> Subexpr {
+        self.subexpr.clone()
+    }
+}
+
+// Collects nodes for `Cherry::iter_nodes`.
+struct Nodes(Vec<(usize, String, Value, String)>);
+
+impl Visitor for Nodes {
+    fn enter(&mut self, node: &Tree, depth: usize) {
+        let (label, value, unit) = (node.label.clone(), node.value.clone(), node.unit.clone());
+        self.0.push((depth, label, value, unit));
+    }
+}
+
+// Walks `tree` at `depth` (without recursion).
+fn accept_tree<V: Visitor + ?Sized>(visitor: &mut V, tree: Tree, depth: usize) {
+    enum Todo {
+        Enter(Tree, usize),
+        Leave(Tree, usize),
+    }
+    let mut stack = vec![Todo::Enter(tree, depth)];
+    while let Some(todo) = stack.pop() {
+        match todo {
+            Todo::Enter(mut tree, depth) => {
+                let subexpr = mem::replace(&mut tree.subexpr, vec![]);
+                visitor.enter(&tree, depth);
+                stack.push(Todo::Leave(tree, depth));
+                for sub in subexpr.into_iter().rev() {
+                    stack.push(Todo::Enter(sub, depth + 1));
+                }
+            }
+            Todo::Leave(tree, depth) => visitor.leave(&tree, depth),
+        }
+    }
+}
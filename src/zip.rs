@@ -0,0 +1,128 @@
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+// CRC-32 (IEEE) of `bytes`, as zip archives record it.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0_u32, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+// Writes zip archives of stored (uncompressed) files.
+pub(crate) struct ZipWriter<W: Write> {
+    out: W,
+    offset: u32,
+    entries: Vec<Entry>,
+}
+
+// 1980-01-01 00:00, the earliest time zip can record, so archives are reproducible.
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+// Version 2.0, needed for directories in names.
+const VERSION: u16 = 20;
+// Names are encoded in UTF-8.
+const FLAGS: u16 = 1 << 11;
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "zip archive exceeds 4 GiB")
+}
+
+fn u32_of(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| too_large())
+}
+
+fn u16_of(len: usize) -> io::Result<u16> {
+    u16::try_from(len).map_err(|_| too_large())
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub(crate) fn new(out: W) -> Self {
+        ZipWriter {
+            out,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out.write_all(bytes)?;
+        self.offset = self
+            .offset
+            .checked_add(u32_of(bytes.len())?)
+            .ok_or_else(too_large)?;
+        Ok(())
+    }
+    // Appends file `name` with `content`.
+    pub(crate) fn add(&mut self, name: &str, content: &[u8]) -> io::Result<()> {
+        let entry = Entry {
+            name: name.to_owned(),
+            crc: crc32(content),
+            size: u32_of(content.len())?,
+            offset: self.offset,
+        };
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x0403_4b50_u32.to_le_bytes());
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&FLAGS.to_le_bytes());
+        header.extend_from_slice(&0_u16.to_le_bytes());
+        header.extend_from_slice(&DOS_TIME.to_le_bytes());
+        header.extend_from_slice(&DOS_DATE.to_le_bytes());
+        header.extend_from_slice(&entry.crc.to_le_bytes());
+        header.extend_from_slice(&entry.size.to_le_bytes());
+        header.extend_from_slice(&entry.size.to_le_bytes());
+        header.extend_from_slice(&u16_of(name.len())?.to_le_bytes());
+        header.extend_from_slice(&0_u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.write(&header)?;
+        self.write(content)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+    // Writes the central directory and returns the underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        let start = self.offset;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&0x0201_4b50_u32.to_le_bytes());
+            directory.extend_from_slice(&VERSION.to_le_bytes());
+            directory.extend_from_slice(&VERSION.to_le_bytes());
+            directory.extend_from_slice(&FLAGS.to_le_bytes());
+            directory.extend_from_slice(&0_u16.to_le_bytes());
+            directory.extend_from_slice(&DOS_TIME.to_le_bytes());
+            directory.extend_from_slice(&DOS_DATE.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&u16_of(entry.name.len())?.to_le_bytes());
+            // extra field, comment, disk number, internal and external attributes
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let count = u16_of(self.entries.len())?;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x0605_4b50_u32.to_le_bytes());
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&u32_of(directory.len())?.to_le_bytes());
+        end.extend_from_slice(&start.to_le_bytes());
+        end.extend_from_slice(&0_u16.to_le_bytes());
+        self.write(&directory)?;
+        self.write(&end)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}